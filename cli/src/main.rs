@@ -6,19 +6,84 @@ use alloy::{
     signers::local::PrivateKeySigner,
     sol,
 };
-use alloy_primitives::{Address, B256};
+use alloy_primitives::{Address, FixedBytes, B256};
 use alloy_provider::WalletProvider;
 use anyhow::{Context, Result};
 use clap::{Parser, Subcommand};
 use futures::future::join_all;
 use reqwest::Url;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::PathBuf;
 use std::process::{Command, Stdio};
 use std::str::FromStr;
 
+/// Where `run da` serves the DA server, passed to the nodes it starts
+const DA_SERVER_URL: &str = "http://127.0.0.1:3000";
+
+/// Where `run nexus` serves the Nexus RPC by default
+const NEXUS_RPC: &str = "http://127.0.0.1:8545";
+
+/// Where `genesis init` saves the keys it generates for each rollup
+const KEYS_FILE: &str = "chains/genesis/keys.json";
+
+/// Where `test full init` saves the addresses of the contracts it deploys
+const DEPLOYMENTS_FILE: &str = "chains/deployments/run-latest.json";
+
+/// The rollups `genesis init` creates, with their chain ids
+const ROLLUPS: [(&str, u64); 2] = [("rollup1", 31338), ("rollup2", 31339)];
+
+/// Keys of the accounts relaying a rollup's roots and posting its DA commitments to Nexus,
+/// generated and funded by `genesis init`. Every rollup gets its own pair so their transactions
+/// to Nexus do not race each other or the default key for nonces
+#[derive(Serialize, Deserialize)]
+struct RollupKeys {
+    relayer: String,
+    poster: String,
+}
+
+/// The keys `genesis init` generated for rollup `name`
+fn rollup_keys(name: &str) -> Result<RollupKeys> {
+    let keys = fs::read_to_string(KEYS_FILE)
+        .with_context(|| format!("❌ Failed to read {}, run `genesis init` first", KEYS_FILE))?;
+    let mut keys: BTreeMap<String, RollupKeys> =
+        serde_json::from_str(&keys).context("Failed to parse keys file")?;
+    keys.remove(name)
+        .ok_or_else(|| anyhow::anyhow!("No keys for rollup {} in {}", name, KEYS_FILE))
+}
+
+/// The hex private key of `signer`, as `RollupKeys` holds it
+fn signer_key(signer: &PrivateKeySigner) -> String {
+    signer.to_bytes().to_string()
+}
+
+/// The address of `key`
+fn key_address(key: &str) -> Result<Address> {
+    Ok(key.parse::<PrivateKeySigner>()?.address())
+}
+
+/// The contracts `test full init` deployed, or `None` before it has run
+fn read_deployments() -> Result<Option<Value>> {
+    let deployments = match fs::read_to_string(DEPLOYMENTS_FILE) {
+        Ok(deployments) => deployments,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e).context("Failed to read deployments file"),
+    };
+    let deployments =
+        serde_json::from_str(&deployments).context("Failed to parse deployments file")?;
+    Ok(Some(deployments))
+}
+
+/// The address of contract `name` in `deployments`
+fn deployment(deployments: &Value, name: &str) -> Result<Address> {
+    let address = deployments[name]["address"]
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("Invalid {} address", name))?;
+    Ok(Address::from_str(address)?)
+}
+
 sol!(
     #[sol(rpc)]
     NexusSettler,
@@ -87,7 +152,7 @@ enum RunCommands {
     /// Run DA server
     #[command(
         about = "Run the DA server",
-        long_about = "Start the DA server using 'cargo run --bin da-server'"
+        long_about = "Start the DA server using 'cargo run --manifest-path da-server/Cargo.toml'"
     )]
     Da,
 
@@ -98,10 +163,7 @@ enum RunCommands {
     )]
     Smt,
 
-    #[command(
-        about = "Run the solver",
-        long_about = "Start the solver server"
-    )]
+    #[command(about = "Run the solver", long_about = "Start the solver server")]
     Solver,
 
     /// Run rollup node
@@ -130,6 +192,12 @@ enum RunCommands {
             default_value = "8552"
         )]
         authrpc_port: u16,
+        /// `NAME@RPC` of a peer rollup that is sent this rollup's roots
+        #[arg(
+            long = "peer",
+            help = "NAME@RPC of a peer rollup whose RollupSettler is sent this rollup's roots"
+        )]
+        peers: Vec<String>,
     },
 
     /// Run nexus node
@@ -162,7 +230,8 @@ enum GenesisCommands {
     /// Initialize genesis files
     #[command(
         about = "Initialize genesis files",
-        long_about = "Create genesis files for different chains with specific chain IDs (31337, 31338, 31339)"
+        long_about = "Create genesis files for different chains with specific chain IDs (31337, 31338, 31339), \
+                     funding newly generated relayer and poster keys for each rollup"
     )]
     Init,
 }
@@ -216,7 +285,8 @@ async fn deploy_nexus_settler(rpc: String, wallet: &EthereumWallet) -> Result<Ad
         .wallet(wallet)
         .on_http(rpc.parse::<Url>()?);
 
-    let contract = NexusSettler::deploy(&provider).await?;
+    let owner = provider.default_signer_address();
+    let contract = NexusSettler::deploy(&provider, owner).await?;
 
     println!("🧱 Deployed NexusSettler at: {}", contract.address());
     Ok(*contract.address())
@@ -234,11 +304,13 @@ async fn deploy_rollup_settler(rpc: String, wallet: &EthereumWallet) -> Result<A
     Ok(*contract.address())
 }
 
+/// Registers each `(chain id, relayer, DA poster, RollupSettler)` on the NexusSettler, without a
+/// rollup contract there to read state roots from
 async fn authorize_rollups(
     rpc: String,
     wallet: &EthereumWallet,
     contract_address: Address,
-    chain_ids: Vec<u64>,
+    rollups: Vec<(u64, Address, Address, Address)>,
 ) -> Result<()> {
     let provider = ProviderBuilder::new()
         .with_recommended_fillers()
@@ -246,13 +318,32 @@ async fn authorize_rollups(
         .on_http(rpc.parse::<Url>()?);
 
     let contract = NexusSettler::new(contract_address, provider);
-    for chain_id in chain_ids.clone() {
+    let mut chain_ids = Vec::new();
+    for (chain_id, relayer, poster, settler) in rollups {
+        contract
+            .createRollup(
+                U256::from(chain_id),
+                Address::ZERO,
+                settler,
+                FixedBytes::ZERO,
+            )
+            .send()
+            .await?
+            .register()
+            .await?;
         contract
-            .createRollup(U256::from(chain_id), Address::random())
+            .setRelayer(U256::from(chain_id), relayer)
             .send()
             .await?
             .register()
             .await?;
+        contract
+            .setPoster(U256::from(chain_id), poster)
+            .send()
+            .await?
+            .register()
+            .await?;
+        chain_ids.push(chain_id);
     }
     println!(
         "✍️ Authorized chain IDs {:?} on Nexus contract {}",
@@ -318,11 +409,12 @@ async fn save_deployments(
     });
 
     // Create deployments directory if it doesn't exist
-    let deployments_dir = PathBuf::from("chains/deployments");
-    fs::create_dir_all(&deployments_dir).context("❌ Failed to create deployments directory")?;
+    let file_path = PathBuf::from(DEPLOYMENTS_FILE);
+    if let Some(deployments_dir) = file_path.parent() {
+        fs::create_dir_all(deployments_dir).context("❌ Failed to create deployments directory")?;
+    }
 
     // Write to run-latest.json
-    let file_path = deployments_dir.join("run-latest.json");
     fs::write(
         &file_path,
         serde_json::to_string_pretty(&deployments)
@@ -331,6 +423,56 @@ async fn save_deployments(
     .with_context(|| format!("❌ Failed to write deployments to {}", file_path.display()))?;
 
     println!("📄 Deployments saved to {}", file_path.display());
+
+    // the solver's chain registry, every rollup it reads orders from and fills orders on
+    let solver_chains = json!({
+        "chains": [
+            {
+                "chainId": 31338,
+                "rpc": "http://127.0.0.1:8546",
+                "settler": format!("{:#x}", rollup1_settler),
+                "tokens": [{
+                    "symbol": "TERC20",
+                    "address": format!("{:#x}", rollup1_erc20),
+                    "minBalance": "1000000000000000000000",
+                    "targetBalance": "10000000000000000000000"
+                }]
+            },
+            {
+                "chainId": 31339,
+                "rpc": "http://127.0.0.1:8547",
+                "settler": format!("{:#x}", rollup2_settler),
+                "tokens": [{
+                    "symbol": "TERC20",
+                    "address": format!("{:#x}", rollup2_erc20),
+                    "minBalance": "1000000000000000000000",
+                    "targetBalance": "10000000000000000000000"
+                }]
+            }
+        ]
+    });
+    let solver_dir = PathBuf::from("chains/solver");
+    fs::create_dir_all(&solver_dir).context("❌ Failed to create solver directory")?;
+    let file_path = solver_dir.join("chains.json");
+    fs::write(
+        &file_path,
+        serde_json::to_string_pretty(&solver_chains)
+            .context("Failed to serialize solver chains JSON")?,
+    )
+    .with_context(|| format!("❌ Failed to write chains to {}", file_path.display()))?;
+
+    println!("📄 Solver chains saved to {}", file_path.display());
+
+    // stand-in USD prices the solver values orders and gas with
+    let prices = json!({ "ETH": 3000.0, "TERC20": 1.0 });
+    let file_path = solver_dir.join("prices.json");
+    fs::write(
+        &file_path,
+        serde_json::to_string_pretty(&prices).context("Failed to serialize prices JSON")?,
+    )
+    .with_context(|| format!("❌ Failed to write prices to {}", file_path.display()))?;
+
+    println!("📄 Solver prices saved to {}", file_path.display());
     Ok(())
 }
 
@@ -432,29 +574,32 @@ async fn create_order(
     Ok(index.index)
 }
 
-async fn update_nexus_order_root(
+/// How long `test full run` waits for the relayer to bring an order root to another chain
+const RELAY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+async fn wait_for_nexus_order_root(
     rpc: &str,
-    wallet: &EthereumWallet,
     settler: Address,
     chain_id: u64,
     root: B256,
 ) -> Result<()> {
-    let provider = ProviderBuilder::new()
-        .with_recommended_fillers()
-        .wallet(wallet)
-        .on_http(rpc.parse::<Url>()?);
-
+    let provider = ProviderBuilder::new().on_http(rpc.parse::<Url>()?);
     let settler = NexusSettler::new(settler, &provider);
-    settler
-        .updateRollupOrderRoot(U256::from(chain_id), root)
-        .send()
-        .await?
-        .with_required_confirmations(1)
-        .watch()
-        .await?;
+    let start = std::time::Instant::now();
+    while settler.orderRoots(U256::from(chain_id)).call().await?._0 != root {
+        if start.elapsed() > RELAY_TIMEOUT {
+            anyhow::bail!(
+                "Order root {} of chain {} was not relayed to NexusSettler {}",
+                root,
+                chain_id,
+                *settler.address()
+            );
+        }
+        tokio::time::sleep(tokio::time::Duration::from_millis(200)).await;
+    }
 
     println!(
-        "⬆️ Updated order root {} for chain {} on NexusSettler {}",
+        "⬆️ Order root {} for chain {} relayed to NexusSettler {}",
         root,
         chain_id,
         *settler.address()
@@ -462,29 +607,29 @@ async fn update_nexus_order_root(
     Ok(())
 }
 
-async fn update_rollup_order_root(
+async fn wait_for_rollup_order_root(
     rpc: &str,
-    wallet: &EthereumWallet,
     settler: Address,
     chain_id: u64,
     root: B256,
 ) -> Result<()> {
-    let provider = ProviderBuilder::new()
-        .with_recommended_fillers()
-        .wallet(wallet)
-        .on_http(rpc.parse::<Url>()?);
-
+    let provider = ProviderBuilder::new().on_http(rpc.parse::<Url>()?);
     let settler = RollupSettler::new(settler, &provider);
-    settler
-        .updateRollupOrderRoot(U256::from(chain_id), root)
-        .send()
-        .await?
-        .with_required_confirmations(1)
-        .watch()
-        .await?;
+    let start = std::time::Instant::now();
+    while settler.orderRoots(U256::from(chain_id)).call().await?._0 != root {
+        if start.elapsed() > RELAY_TIMEOUT {
+            anyhow::bail!(
+                "Order root {} of chain {} was not relayed to RollupSettler {}",
+                root,
+                chain_id,
+                *settler.address()
+            );
+        }
+        tokio::time::sleep(tokio::time::Duration::from_millis(200)).await;
+    }
 
     println!(
-        "⬆️ Updated order root {} for chain {} on RollupSettler {}",
+        "⬆️ Order root {} for chain {} relayed to RollupSettler {}",
         root,
         chain_id,
         *settler.address()
@@ -611,12 +756,27 @@ async fn test_full_init() -> Result<()> {
     )
     .await?;
 
-    // Authorize rollups on NexusSettler
+    // Authorize rollups on NexusSettler, whose roots and DA commitments the rollup nodes post
+    let rollup1_keys = rollup_keys("rollup1")?;
+    let rollup2_keys = rollup_keys("rollup2")?;
     authorize_rollups(
         chains[0].rpc.clone(),
         &wallet,
         nexus_settler,
-        vec![chains[1].chain_id, chains[2].chain_id],
+        vec![
+            (
+                chains[1].chain_id,
+                key_address(&rollup1_keys.relayer)?,
+                key_address(&rollup1_keys.poster)?,
+                rollup1_settler,
+            ),
+            (
+                chains[2].chain_id,
+                key_address(&rollup2_keys.relayer)?,
+                key_address(&rollup2_keys.poster)?,
+                rollup2_settler,
+            ),
+        ],
     )
     .await?;
 
@@ -655,36 +815,18 @@ async fn test_full_init() -> Result<()> {
 
 async fn test_full_run() -> Result<()> {
     // Read deployments
-    let deployments_file = fs::read_to_string("chains/deployments/run-latest.json")
-        .context("Failed to read deployments file")?;
-    let deployments: serde_json::Value =
-        serde_json::from_str(&deployments_file).context("Failed to parse deployments file")?;
-
-    let nexus_settler_addr = Address::from_str(
-        deployments["nexusSettler"]["address"]
-            .as_str()
-            .ok_or_else(|| anyhow::anyhow!("Invalid nexus settler address"))?,
-    )?;
-    let rollup1_settler_addr = Address::from_str(
-        deployments["rollup1Settler"]["address"]
-            .as_str()
-            .ok_or_else(|| anyhow::anyhow!("Invalid rollup1 settler address"))?,
-    )?;
-    let rollup2_settler_addr = Address::from_str(
-        deployments["rollup2Settler"]["address"]
-            .as_str()
-            .ok_or_else(|| anyhow::anyhow!("Invalid rollup2 settler address"))?,
-    )?;
-    let rollup1_erc20_addr = Address::from_str(
-        deployments["rollup1ERC20"]["address"]
-            .as_str()
-            .ok_or_else(|| anyhow::anyhow!("Invalid rollup1 ERC20 address"))?,
-    )?;
-    let rollup2_erc20_addr = Address::from_str(
-        deployments["rollup2ERC20"]["address"]
-            .as_str()
-            .ok_or_else(|| anyhow::anyhow!("Invalid rollup2 ERC20 address"))?,
-    )?;
+    let deployments = read_deployments()?.ok_or_else(|| {
+        anyhow::anyhow!(
+            "No deployments in {}, run `test full init` first",
+            DEPLOYMENTS_FILE
+        )
+    })?;
+
+    let nexus_settler_addr = deployment(&deployments, "nexusSettler")?;
+    let rollup1_settler_addr = deployment(&deployments, "rollup1Settler")?;
+    let rollup2_settler_addr = deployment(&deployments, "rollup2Settler")?;
+    let rollup1_erc20_addr = deployment(&deployments, "rollup1ERC20")?;
+    let rollup2_erc20_addr = deployment(&deployments, "rollup2ERC20")?;
 
     let signer: PrivateKeySigner =
         "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
@@ -693,6 +835,8 @@ async fn test_full_run() -> Result<()> {
 
     let address = Address::from_str("0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266")?;
     let amount_wei = U256::from(1e18 as u64);
+    // leaves a 1% spread, so the solver's default strategy finds the order worth its gas
+    let min_amount_wei = amount_wei * U256::from(99) / U256::from(100);
 
     let rollup1_provider = ProviderBuilder::new()
         .with_recommended_fillers()
@@ -715,26 +859,24 @@ async fn test_full_run() -> Result<()> {
         rollup2_erc20_addr,
         address,
         amount_wei,
-        amount_wei,
+        min_amount_wei,
         31339,
     )
     .await?;
 
     let order_root = rollup1_settler.orderRoot().call().await?._0;
 
-    // Update roots across chains
-    update_nexus_order_root(
+    // The rollup1 node relays its roots to Nexus and rollup2
+    wait_for_nexus_order_root(
         "http://127.0.0.1:8545",
-        &wallet,
         nexus_settler_addr,
         31338,
         order_root,
     )
     .await?;
 
-    update_rollup_order_root(
+    wait_for_rollup_order_root(
         "http://127.0.0.1:8547",
-        &wallet,
         rollup2_settler_addr,
         31338,
         order_root,
@@ -749,8 +891,8 @@ async fn test_full_run() -> Result<()> {
         rollup2_settler_addr,
         rollup1_erc20_addr,
         rollup2_erc20_addr,
-        amount_wei, // 1 token
-        amount_wei, // 1 token minimum
+        amount_wei,     // 1 token
+        min_amount_wei, // 0.99 token minimum
         31338,
         nonce, // rollup1 chain id
     )
@@ -763,7 +905,7 @@ async fn test_full_run() -> Result<()> {
 
 fn run_da() -> Result<()> {
     Command::new("cargo")
-        .args(["run", "--bin", "da-server"])
+        .args(["run", "--manifest-path", "da-server/Cargo.toml"])
         .stdout(Stdio::inherit())
         .stderr(Stdio::inherit())
         .spawn()
@@ -797,7 +939,60 @@ fn run_solver() -> Result<()> {
     Ok(())
 }
 
-fn run_rollup(name: &str, port: u16, p2p_port: u16, authrpc_port: u16) -> Result<()> {
+/// The arguments posting the DA commitments of rollup `name` to Nexus, indexing its orders and
+/// relaying its roots, or `--da.disable` until `test full init` has deployed the settlers, as
+/// the batches of a rollup must all reach Nexus for `run nexus` to replay them
+fn rollup_settler_args(name: &str, peers: &[String]) -> Result<Vec<String>> {
+    let Some(deployments) = read_deployments()? else {
+        println!(
+            "ℹ️ No deployments in {}, running {} without DA, orders and relaying until `test full init` deploys the settlers",
+            DEPLOYMENTS_FILE, name
+        );
+        return Ok(vec!["--da.disable".to_string()]);
+    };
+    let keys = rollup_keys(name)?;
+    let nexus_settler = format!("{:#x}", deployment(&deployments, "nexusSettler")?);
+    let settler = format!(
+        "{:#x}",
+        deployment(&deployments, &format!("{}Settler", name))?
+    );
+    let mut args = vec![
+        "--da.url".to_string(),
+        DA_SERVER_URL.to_string(),
+        "--da.nexus-rpc".to_string(),
+        NEXUS_RPC.to_string(),
+        "--da.nexus-settler".to_string(),
+        nexus_settler.clone(),
+        "--da.poster-key".to_string(),
+        keys.poster,
+        "--orders.settler".to_string(),
+        settler,
+        "--relay.key".to_string(),
+        keys.relayer,
+        "--relay.nexus-rpc".to_string(),
+        NEXUS_RPC.to_string(),
+        "--relay.nexus-settler".to_string(),
+        nexus_settler,
+    ];
+    for peer in peers {
+        let (peer, rpc) = peer
+            .split_once('@')
+            .ok_or_else(|| anyhow::anyhow!("Peer {} is not NAME@RPC", peer))?;
+        let settler = deployment(&deployments, &format!("{}Settler", peer))?;
+        args.push("--relay.peer".to_string());
+        args.push(format!("{:#x}@{}", settler, rpc));
+    }
+    Ok(args)
+}
+
+fn run_rollup(
+    name: &str,
+    port: u16,
+    p2p_port: u16,
+    authrpc_port: u16,
+    peers: &[String],
+) -> Result<()> {
+    let settler_args = rollup_settler_args(name, peers)?;
     Command::new("cargo")
         .args([
             "run",
@@ -821,6 +1016,9 @@ fn run_rollup(name: &str, port: u16, p2p_port: u16, authrpc_port: u16) -> Result
             &port.to_string(),
             "--http.corsdomain",
             "*",
+            // the solver traces transactions that send orders through other contracts
+            "--http.api",
+            "eth,net,web3,debug",
             "--datadir",
             &format!("chains/{}", name),
             "--no-persist-peers",
@@ -829,6 +1027,7 @@ fn run_rollup(name: &str, port: u16, p2p_port: u16, authrpc_port: u16) -> Result
             "--authrpc.port",
             &authrpc_port.to_string(),
         ])
+        .args(settler_args)
         .stdout(Stdio::inherit())
         .stderr(Stdio::inherit())
         .spawn()
@@ -838,7 +1037,35 @@ fn run_rollup(name: &str, port: u16, p2p_port: u16, authrpc_port: u16) -> Result
     Ok(())
 }
 
+/// The arguments verifying the rollups against the commitments on NexusSettler, none until
+/// `test full init` has deployed it
+fn nexus_verify_args(name: &str) -> Result<Vec<String>> {
+    let Some(deployments) = read_deployments()? else {
+        println!(
+            "ℹ️ No deployments in {}, running {} without verifying rollups until `test full init` deploys NexusSettler",
+            DEPLOYMENTS_FILE, name
+        );
+        return Ok(Vec::new());
+    };
+    let mut args = vec![
+        "--verify.settler".to_string(),
+        format!("{:#x}", deployment(&deployments, "nexusSettler")?),
+        "--verify.da-url".to_string(),
+        DA_SERVER_URL.to_string(),
+    ];
+    // only the batches of the posters registered on NexusSettler are replayed
+    for (rollup, chain_id) in ROLLUPS {
+        let poster = key_address(&rollup_keys(rollup)?.poster)?;
+        args.push("--verify.rollup".to_string());
+        args.push(format!("chains/genesis/{}.json", rollup));
+        args.push("--verify.poster".to_string());
+        args.push(format!("{}={}", chain_id, poster));
+    }
+    Ok(args)
+}
+
 fn run_nexus(name: &str, port: u16) -> Result<()> {
+    let verify_args = nexus_verify_args(name)?;
     Command::new("cargo")
         .args([
             "run",
@@ -865,7 +1092,10 @@ fn run_nexus(name: &str, port: u16) -> Result<()> {
             "--datadir",
             &format!("chains/{}", name),
             "--no-persist-peers",
+            "--da.url",
+            DA_SERVER_URL,
         ])
+        .args(verify_args)
         .stdout(Stdio::inherit())
         .stderr(Stdio::inherit())
         .spawn()
@@ -877,7 +1107,7 @@ fn run_nexus(name: &str, port: u16) -> Result<()> {
 
 fn create_genesis_files() -> Result<()> {
     let start = std::time::Instant::now();
-    let base_genesis: Value = json!({
+    let mut base_genesis: Value = json!({
         "config": {
             "homesteadBlock": 0,
             "eip150Block": 0,
@@ -931,14 +1161,40 @@ fn create_genesis_files() -> Result<()> {
         }
     });
 
+    // Generate the relayer and poster keys of each rollup, funded on every chain
+    let mut keys = BTreeMap::new();
+    for (name, _) in ROLLUPS {
+        let relayer = PrivateKeySigner::random();
+        let poster = PrivateKeySigner::random();
+        for signer in [&relayer, &poster] {
+            base_genesis["alloc"][signer.address().to_string()] = json!({
+                "balance": "0x200000000000000000000000000000000000000000000000000000000000000"
+            });
+        }
+        keys.insert(
+            name,
+            RollupKeys {
+                relayer: signer_key(&relayer),
+                poster: signer_key(&poster),
+            },
+        );
+    }
+
     // Create chains/genesis directory if it doesn't exist
     let genesis_dir = PathBuf::from("chains/genesis");
     fs::create_dir_all(&genesis_dir).context("❌ Failed to create genesis directory")?;
 
+    fs::write(
+        KEYS_FILE,
+        serde_json::to_string_pretty(&keys).context("Failed to serialize keys JSON")?,
+    )
+    .with_context(|| format!("❌ Failed to write keys to {}", KEYS_FILE))?;
+    println!("🔑 Relayer and poster keys saved to {}", KEYS_FILE);
+
     // Create genesis files for different chain IDs
-    let chain_configs = [("nexus", 31337), ("rollup1", 31338), ("rollup2", 31339)];
+    let chain_configs = [("nexus", 31337)].into_iter().chain(ROLLUPS);
 
-    for (name, chain_id) in chain_configs.iter() {
+    for (name, chain_id) in chain_configs {
         let mut genesis = base_genesis.clone();
         genesis["config"]["chainId"] = json!(chain_id);
 
@@ -1018,7 +1274,8 @@ async fn main() -> Result<()> {
                 port,
                 p2p_port,
                 authrpc_port,
-            } => run_rollup(&name, port, p2p_port, authrpc_port)?,
+                peers,
+            } => run_rollup(&name, port, p2p_port, authrpc_port, &peers)?,
             RunCommands::Nexus { name, port } => run_nexus(&name, port)?,
         },
         Commands::Genesis { action } => match action {
@@ -0,0 +1,11 @@
+#!/bin/bash
+# Rebuilds the contracts and copies the artifacts of the given ones, NexusSettler by default,
+# to cli/artifacts, which the cli, da-exex and solver bind to
+set -euo pipefail
+cd "$(dirname "$0")"
+
+forge build
+for contract in "${@:-NexusSettler}"; do
+    cp "out/${contract}.sol/${contract}.json" "../cli/artifacts/${contract}.json"
+    echo "📦 Copied ${contract} to cli/artifacts"
+done
@@ -14,18 +14,55 @@ contract NexusSettler is Ownable2Step, INexusSettler {
     mapping(uint256 => Rollup) public rollups;
     /// @dev chainId => (state root => timestamp)
     mapping(uint256 => mapping (bytes32 => uint256)) public stateRoots;
+    /// @dev chainId => sent order root
+    mapping(uint256 => bytes32) public orderRoots;
+    /// @dev chainId => filled order root
+    mapping(uint256 => bytes32) public fillRoots;
+    /// @dev chainId => address allowed to relay order and fill roots
+    mapping(uint256 => address) public relayers;
+    /// @dev chainId => address allowed to post DA commitments
+    mapping(uint256 => address) public posters;
+    /// @dev chainId => DA commitments in the order they were posted
+    mapping(uint256 => DACommitment[]) public daCommitments;
 
     constructor(address governance) Ownable(governance) {}
 
+    modifier onlyRelayer(uint256 chainId) {
+        require(rollups[chainId].settlerContract != address(0), UnauthorizedRollup(chainId));
+        require(msg.sender == relayers[chainId], UnauthorizedRelayer(chainId, msg.sender));
+        _;
+    }
+
+    modifier onlyPoster(uint256 chainId) {
+        require(rollups[chainId].settlerContract != address(0), UnauthorizedRollup(chainId));
+        require(msg.sender == posters[chainId], UnauthorizedPoster(chainId, msg.sender));
+        _;
+    }
+
+    /// @dev A rollup without a rollup contract on this chain has no state roots recorded, only its DA commitments and order roots
     function createRollup(uint256 chainId, address rollupContract, address settlerContract, bytes4 stateRootFn) external onlyOwner {
-        require(rollups[chainId].rollupContract == address(0), RollupAlreadyExists(chainId));
-        require(rollupContract.code.length != 0, InvalidRollupContract(rollupContract));
+        require(rollups[chainId].settlerContract == address(0), RollupAlreadyExists(chainId));
         require(settlerContract != address(0), ZeroAddress());
-        bytes memory result = rollupContract.functionStaticCall(abi.encodeWithSelector(stateRootFn));
         rollups[chainId] = Rollup(rollupContract, settlerContract, chainId, stateRootFn);
+        if (rollupContract == address(0)) {
+            return;
+        }
+        require(rollupContract.code.length != 0, InvalidRollupContract(rollupContract));
+        bytes memory result = rollupContract.functionStaticCall(abi.encodeWithSelector(stateRootFn));
         bytes32 stateRoot = abi.decode(result, (bytes32));
         require(stateRoot != bytes32(0), InvalidStateRoot(chainId, stateRoot));
         stateRoots[chainId][stateRoot] = block.timestamp;
+        emit StateRootUpdated(chainId, stateRoot);
+    }
+
+    function setRelayer(uint256 chainId, address relayer) external onlyOwner {
+        require(rollups[chainId].settlerContract != address(0), UnauthorizedRollup(chainId));
+        relayers[chainId] = relayer;
+    }
+
+    function setPoster(uint256 chainId, address poster) external onlyOwner {
+        require(rollups[chainId].settlerContract != address(0), UnauthorizedRollup(chainId));
+        posters[chainId] = poster;
     }
 
     function updateRollupRoot(uint256 chainId) external {
@@ -35,5 +72,29 @@ contract NexusSettler is Ownable2Step, INexusSettler {
         bytes32 stateRoot = abi.decode(result, (bytes32));
         require(stateRoot != bytes32(0), InvalidStateRoot(chainId, stateRoot));
         stateRoots[chainId][stateRoot] = block.timestamp;
+        emit StateRootUpdated(chainId, stateRoot);
+    }
+
+    function updateRollupOrderRoot(uint256 chainId, bytes32 root) external onlyRelayer(chainId) {
+        orderRoots[chainId] = root;
+    }
+
+    function updateRollupFillRoot(uint256 chainId, bytes32 root) external onlyRelayer(chainId) {
+        fillRoots[chainId] = root;
+    }
+
+    /// @dev Only the rollup's poster may post, so verifiers can replay every commitment of a rollup
+    function postDACommitment(uint256 chainId, uint64 fromBlock, uint64 toBlock, bytes32 commitment) external onlyPoster(chainId) {
+        require(fromBlock <= toBlock, InvalidBlockRange(fromBlock, toBlock));
+        daCommitments[chainId].push(DACommitment(fromBlock, toBlock, commitment, msg.sender, block.timestamp));
+        emit DACommitmentPosted(chainId, daCommitments[chainId].length - 1, fromBlock, toBlock, commitment);
+    }
+
+    function daCommitmentCount(uint256 chainId) external view returns (uint256) {
+        return daCommitments[chainId].length;
+    }
+
+    function getDACommitment(uint256 chainId, uint256 index) external view returns (DACommitment memory) {
+        return daCommitments[chainId][index];
     }
 }
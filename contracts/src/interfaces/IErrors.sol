@@ -2,6 +2,7 @@
 pragma solidity ^0.8.28;
 
 error InsufficientBond(uint256 amount);
+error InvalidBlockRange(uint64 fromBlock, uint64 toBlock);
 error InvalidDeadline(uint256 deadline);
 error InvalidOrder(bytes32 orderHash);
 error InvalidRollupContract(address rollup);
@@ -16,5 +17,7 @@ error OrderAlreadyFulfilled(bytes32 orderHash);
 error OrderNotFulfilled(bytes32 orderHash);
 error RollupAlreadyExists(uint256 chainId);
 error SignatureExpired(uint256 timestamp);
+error UnauthorizedPoster(uint256 chainId, address sender);
+error UnauthorizedRelayer(uint256 chainId, address sender);
 error UnauthorizedRollup(uint256 chainId);
 error ZeroAddress();
@@ -3,6 +3,7 @@ pragma solidity ^0.8.28;
 
 import {ResolvedCrossChainOrder} from "./IStructs.sol";
 
+event DACommitmentPosted(uint256 indexed chainId, uint256 index, uint64 fromBlock, uint64 toBlock, bytes32 commitment);
 event Evicted(address solver);
 /// @notice Signals that an order has been opened
 /// @param orderId a unique order identifier within this settlement system
@@ -11,5 +12,6 @@ event Open(bytes32 indexed orderId, ResolvedCrossChainOrder resolvedOrder);
 event OrderSent(bytes32 orderHash);
 event OrderFilled(bytes32 orderHash);
 event Slashed(address solver, uint256 amount);
+event StateRootUpdated(uint256 indexed chainId, bytes32 stateRoot);
 event Unbonded(address solver);
 event UnbondingStarted(address solver, uint256 exitTimestamp);
@@ -1,9 +1,13 @@
 // SPDX-License-Identifier: Apache-2.0
 pragma solidity ^0.8.28;
 
-import {Rollup} from "./IStructs.sol";
+import {DACommitment, Rollup} from "./IStructs.sol";
 
 interface INexusSettler {
     function rollups(uint256 chainId) external view returns (Rollup memory rollup);
     function stateRoots(uint256 chainId, bytes32 stateRoot) external view returns (uint256 timestamp);
+    function orderRoots(uint256 chainId) external view returns (bytes32 root);
+    function fillRoots(uint256 chainId) external view returns (bytes32 root);
+    function daCommitmentCount(uint256 chainId) external view returns (uint256 count);
+    function getDACommitment(uint256 chainId, uint256 index) external view returns (DACommitment memory commitment);
 }
@@ -131,6 +131,19 @@ struct SolverSignature {
     uint256 deadline;
 }
 
+struct DACommitment {
+    /// @dev First rollup block covered by the DA batch
+    uint64 fromBlock;
+    /// @dev Last rollup block covered by the DA batch
+    uint64 toBlock;
+    /// @dev Commitment of the batch on the DA layer
+    bytes32 commitment;
+    /// @dev The address that posted the commitment
+    address poster;
+    /// @dev The timestamp at which the commitment was posted
+    uint256 timestamp;
+}
+
 struct Rollup {
     /// @dev The address of the rollup contract
     address rollupContract;
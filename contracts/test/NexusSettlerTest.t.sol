@@ -0,0 +1,139 @@
+// SPDX-License-Identifier: Apache-2.0
+pragma solidity ^0.8.28;
+import {Test} from "forge-std/Test.sol";
+import {Ownable} from "lib/openzeppelin-contracts/contracts/access/Ownable.sol";
+import {NexusSettler} from "src/NexusSettler.sol";
+import "src/interfaces/IErrors.sol";
+import "src/interfaces/IEvents.sol";
+import "src/interfaces/IStructs.sol";
+
+contract NexusSettlerTest is Test {
+    uint256 constant CHAIN_ID = 31338;
+    address constant SETTLER = address(0x5e771e5);
+    address constant POSTER = address(0x9057e5);
+    address constant RELAYER = address(0x7e1a7e5);
+
+    NexusSettler settler;
+
+    function setUp() external {
+        settler = new NexusSettler(address(this));
+        settler.createRollup(CHAIN_ID, address(0), SETTLER, bytes4(0));
+        settler.setPoster(CHAIN_ID, POSTER);
+        settler.setRelayer(CHAIN_ID, RELAYER);
+    }
+
+    function test_postDACommitment() external {
+        vm.expectEmit(address(settler));
+        emit DACommitmentPosted(CHAIN_ID, 0, 1, 10, bytes32(uint256(1)));
+        vm.prank(POSTER);
+        settler.postDACommitment(CHAIN_ID, 1, 10, bytes32(uint256(1)));
+        vm.expectEmit(address(settler));
+        emit DACommitmentPosted(CHAIN_ID, 1, 11, 11, bytes32(uint256(2)));
+        vm.prank(POSTER);
+        settler.postDACommitment(CHAIN_ID, 11, 11, bytes32(uint256(2)));
+
+        assertEq(settler.daCommitmentCount(CHAIN_ID), 2);
+        DACommitment memory commitment = settler.getDACommitment(CHAIN_ID, 1);
+        assertEq(commitment.fromBlock, 11);
+        assertEq(commitment.toBlock, 11);
+        assertEq(commitment.commitment, bytes32(uint256(2)));
+        assertEq(commitment.poster, POSTER);
+        assertEq(commitment.timestamp, block.timestamp);
+    }
+
+    function test_postDACommitment_invalidBlockRange() external {
+        vm.expectRevert(abi.encodeWithSelector(InvalidBlockRange.selector, uint64(2), uint64(1)));
+        vm.prank(POSTER);
+        settler.postDACommitment(CHAIN_ID, 2, 1, bytes32(uint256(1)));
+    }
+
+    function test_onlyPoster() external {
+        address other = address(0xbad);
+        vm.expectRevert(abi.encodeWithSelector(UnauthorizedPoster.selector, CHAIN_ID, other));
+        vm.prank(other);
+        settler.postDACommitment(CHAIN_ID, 1, 10, bytes32(uint256(1)));
+
+        vm.expectRevert(abi.encodeWithSelector(UnauthorizedRollup.selector, CHAIN_ID + 1));
+        vm.prank(POSTER);
+        settler.postDACommitment(CHAIN_ID + 1, 1, 10, bytes32(uint256(1)));
+        assertEq(settler.daCommitmentCount(CHAIN_ID), 0);
+    }
+
+    function test_setPoster_onlyOwner() external {
+        address other = address(0xbad);
+        vm.expectRevert(abi.encodeWithSelector(Ownable.OwnableUnauthorizedAccount.selector, other));
+        vm.prank(other);
+        settler.setPoster(CHAIN_ID, other);
+
+        vm.expectRevert(abi.encodeWithSelector(UnauthorizedRollup.selector, CHAIN_ID + 1));
+        settler.setPoster(CHAIN_ID + 1, POSTER);
+    }
+
+    function test_onlyRelayer() external {
+        vm.startPrank(RELAYER);
+        settler.updateRollupOrderRoot(CHAIN_ID, bytes32(uint256(1)));
+        settler.updateRollupFillRoot(CHAIN_ID, bytes32(uint256(2)));
+        vm.stopPrank();
+        assertEq(settler.orderRoots(CHAIN_ID), bytes32(uint256(1)));
+        assertEq(settler.fillRoots(CHAIN_ID), bytes32(uint256(2)));
+
+        // the poster and the owner are not the relayer
+        vm.expectRevert(abi.encodeWithSelector(UnauthorizedRelayer.selector, CHAIN_ID, POSTER));
+        vm.prank(POSTER);
+        settler.updateRollupOrderRoot(CHAIN_ID, bytes32(uint256(3)));
+        vm.expectRevert(abi.encodeWithSelector(UnauthorizedRelayer.selector, CHAIN_ID, address(this)));
+        settler.updateRollupFillRoot(CHAIN_ID, bytes32(uint256(3)));
+        vm.expectRevert(abi.encodeWithSelector(UnauthorizedRollup.selector, CHAIN_ID + 1));
+        vm.prank(RELAYER);
+        settler.updateRollupOrderRoot(CHAIN_ID + 1, bytes32(uint256(3)));
+        assertEq(settler.orderRoots(CHAIN_ID), bytes32(uint256(1)));
+        assertEq(settler.fillRoots(CHAIN_ID), bytes32(uint256(2)));
+    }
+
+    function test_setRelayer_onlyOwner() external {
+        address other = address(0xbad);
+        vm.expectRevert(abi.encodeWithSelector(Ownable.OwnableUnauthorizedAccount.selector, other));
+        vm.prank(other);
+        settler.setRelayer(CHAIN_ID, other);
+    }
+
+    function test_stateRootUpdated() external {
+        MockRollup rollup = new MockRollup(bytes32(uint256(1)));
+        vm.expectEmit(address(settler));
+        emit StateRootUpdated(CHAIN_ID + 1, bytes32(uint256(1)));
+        settler.createRollup(CHAIN_ID + 1, address(rollup), SETTLER, MockRollup.stateRoot.selector);
+        assertEq(settler.stateRoots(CHAIN_ID + 1, bytes32(uint256(1))), block.timestamp);
+
+        rollup.setStateRoot(bytes32(uint256(2)));
+        vm.warp(block.timestamp + 1);
+        vm.expectEmit(address(settler));
+        emit StateRootUpdated(CHAIN_ID + 1, bytes32(uint256(2)));
+        // anyone may have the root read from the rollup contract
+        vm.prank(address(0xbad));
+        settler.updateRollupRoot(CHAIN_ID + 1);
+        assertEq(settler.stateRoots(CHAIN_ID + 1, bytes32(uint256(2))), block.timestamp);
+
+        rollup.setStateRoot(bytes32(0));
+        vm.expectRevert(abi.encodeWithSelector(InvalidStateRoot.selector, CHAIN_ID + 1, bytes32(0)));
+        settler.updateRollupRoot(CHAIN_ID + 1);
+    }
+
+    function test_createRollup_withoutRollupContract() external {
+        vm.expectRevert(abi.encodeWithSelector(UnauthorizedRollup.selector, CHAIN_ID));
+        settler.updateRollupRoot(CHAIN_ID);
+        vm.expectRevert(abi.encodeWithSelector(RollupAlreadyExists.selector, CHAIN_ID));
+        settler.createRollup(CHAIN_ID, address(0), SETTLER, bytes4(0));
+    }
+}
+
+contract MockRollup {
+    bytes32 public stateRoot;
+
+    constructor(bytes32 _stateRoot) {
+        stateRoot = _stateRoot;
+    }
+
+    function setStateRoot(bytes32 _stateRoot) external {
+        stateRoot = _stateRoot;
+    }
+}
@@ -0,0 +1,15 @@
+//! How sequencers sign their submissions to the DA server, shared by the DA ExEx signing them
+//! and the DA server checking them
+
+use alloy_primitives::{keccak256, B256};
+
+/// Header carrying the sequencer's 65-byte signature over the submission, hex encoded
+pub const SIGNATURE_HEADER: &str = "x-da-signature";
+
+/// The digest a sequencer signs, binding the blob commitment to its chain
+pub fn signing_digest(chain_id: u64, commitment: &[u8; 32]) -> B256 {
+    let mut preimage = [0u8; 40];
+    preimage[..8].copy_from_slice(&chain_id.to_be_bytes());
+    preimage[8..].copy_from_slice(commitment);
+    keccak256(preimage)
+}
@@ -0,0 +1,603 @@
+use crate::{
+    batch::{Compression, PostingMode},
+    nexus::NexusPoster,
+    relay::RelayTarget,
+};
+use alloy::{genesis::Genesis, primitives::Address, signers::local::PrivateKeySigner};
+use clap::Args;
+use eyre::{eyre, WrapErr};
+use reqwest::Url;
+use std::{collections::HashMap, path::PathBuf, time::Duration};
+
+/// Command line arguments of the DA ExEx, added to reth's `node` command
+#[derive(Debug, Clone, Args)]
+#[command(next_help_heading = "DA")]
+pub struct DAArgs {
+    /// Run the node without submitting blocks to DA
+    #[arg(long = "da.disable")]
+    pub disable: bool,
+
+    /// Base URL of the DA server, such as http://127.0.0.1:3000
+    #[arg(
+        long = "da.url",
+        value_name = "URL",
+        required_unless_present = "disable"
+    )]
+    pub url: Option<String>,
+
+    /// Bearer token sent with every request to the DA server
+    #[arg(long = "da.auth-token", value_name = "TOKEN", env = "DA_AUTH_TOKEN")]
+    pub auth_token: Option<String>,
+
+    /// Most blocks put in one batch
+    #[arg(long = "da.batch-size", value_name = "BLOCKS", default_value_t = 100)]
+    pub batch_size: u64,
+
+    /// Seconds before a request to the DA server times out
+    #[arg(long = "da.request-timeout", value_name = "SECS", default_value_t = 30)]
+    pub request_timeout: u64,
+
+    /// Seconds before a failed DA submission or Nexus post is first retried, doubling with every
+    /// failure
+    #[arg(long = "da.retry-delay", value_name = "SECS", default_value_t = 2)]
+    pub retry_delay: u64,
+
+    /// Batch submissions to the DA server in flight at once
+    #[arg(long = "da.max-in-flight", value_name = "N", default_value_t = 8)]
+    pub max_in_flight: usize,
+
+    /// What batches carry: blocks to execute or the state changes of each block
+    #[arg(long = "da.mode", value_enum, default_value_t = PostingMode::Blocks)]
+    pub mode: PostingMode,
+
+    /// Compression applied to batches before they are submitted
+    #[arg(long = "da.compression", value_enum, default_value_t = Compression::None)]
+    pub compression: Compression,
+
+    /// Private key signing batches, required when the DA server restricts submitters
+    #[arg(
+        long = "da.sequencer-key",
+        value_name = "KEY",
+        env = "DA_SEQUENCER_PRIVATE_KEY",
+        hide_env_values = true
+    )]
+    pub sequencer_key: Option<String>,
+
+    /// RPC of the Nexus chain batch commitments are posted to
+    #[arg(long = "da.nexus-rpc", value_name = "URL")]
+    pub nexus_rpc: Option<String>,
+
+    /// Address of the NexusSettler contract
+    #[arg(long = "da.nexus-settler", value_name = "ADDRESS")]
+    pub nexus_settler: Option<Address>,
+
+    /// Private key paying for Nexus posts
+    #[arg(
+        long = "da.poster-key",
+        value_name = "KEY",
+        env = "DA_POSTER_PRIVATE_KEY",
+        hide_env_values = true
+    )]
+    pub poster_key: Option<String>,
+}
+
+/// Command line arguments of the order tree ExEx
+#[derive(Debug, Clone, Args)]
+#[command(next_help_heading = "Orders")]
+pub struct OrderArgs {
+    /// Address of the rollup's RollupSettler, whose orders and fills are indexed and served
+    /// over the `reimann_` RPC namespace
+    #[arg(long = "orders.settler", value_name = "ADDRESS")]
+    pub settler: Option<Address>,
+}
+
+/// Command line arguments of the relayer, which runs in the order tree ExEx
+#[derive(Debug, Clone, Args)]
+#[command(next_help_heading = "Relay")]
+pub struct RelayArgs {
+    /// Private key paying for root updates, roots are not relayed without it
+    #[arg(
+        long = "relay.key",
+        value_name = "KEY",
+        env = "RELAYER_PRIVATE_KEY",
+        hide_env_values = true
+    )]
+    pub key: Option<String>,
+
+    /// RPC of the Nexus chain roots are relayed to
+    #[arg(long = "relay.nexus-rpc", value_name = "URL")]
+    pub nexus_rpc: Option<String>,
+
+    /// Address of the NexusSettler contract
+    #[arg(long = "relay.nexus-settler", value_name = "ADDRESS")]
+    pub nexus_settler: Option<Address>,
+
+    /// Also have NexusSettler record the rollup's state root through `updateRollupRoot`
+    #[arg(long = "relay.state-root")]
+    pub state_root: bool,
+
+    /// RollupSettler of a peer rollup roots are relayed to, may be repeated
+    #[arg(long = "relay.peer", value_name = "SETTLER@URL")]
+    pub peers: Vec<String>,
+
+    /// Blocks built on top of a block before its roots are relayed
+    #[arg(
+        long = "relay.confirmations",
+        value_name = "BLOCKS",
+        default_value_t = 0
+    )]
+    pub confirmations: u64,
+
+    /// Seconds new roots are collected for before they are relayed together
+    #[arg(long = "relay.interval", value_name = "SECS", default_value_t = 1)]
+    pub interval: u64,
+
+    /// Seconds before a failed root update is retried
+    #[arg(long = "relay.retry-delay", value_name = "SECS", default_value_t = 2)]
+    pub retry_delay: u64,
+}
+
+/// Validated relayer configuration
+#[derive(Clone)]
+pub struct RelayConfig {
+    pub signer: PrivateKeySigner,
+    pub targets: Vec<RelayTarget>,
+    pub confirmations: u64,
+    pub interval: Duration,
+    pub retry_delay: Duration,
+}
+
+/// Command line arguments of the root verifier, run by the Nexus node
+#[derive(Debug, Clone, Args)]
+#[command(next_help_heading = "Verify")]
+pub struct VerifyArgs {
+    /// Address of this chain's NexusSettler, whose DA commitments are replayed and recorded
+    /// state roots checked
+    #[arg(long = "verify.settler", value_name = "ADDRESS")]
+    pub settler: Option<Address>,
+
+    /// Genesis file of a rollup to verify, may be repeated
+    #[arg(long = "verify.rollup", value_name = "GENESIS")]
+    pub rollups: Vec<PathBuf>,
+
+    /// The address registered on NexusSettler to post a rollup's batches, the only one they are
+    /// replayed from, required for every verified rollup
+    #[arg(long = "verify.poster", value_name = "CHAIN_ID=ADDRESS")]
+    pub posters: Vec<String>,
+
+    /// Base URL of the DA server batches are fetched from
+    #[arg(long = "verify.da-url", value_name = "URL")]
+    pub da_url: Option<String>,
+
+    /// URL every alert is posted to as JSON
+    #[arg(long = "verify.webhook", value_name = "URL")]
+    pub webhook: Option<String>,
+
+    /// Seconds before a failed batch fetch is first retried, doubling with every failure up to
+    /// 5 minutes
+    #[arg(long = "verify.retry-delay", value_name = "SECS", default_value_t = 2)]
+    pub retry_delay: u64,
+}
+
+/// Validated root verifier configuration
+#[derive(Clone)]
+pub struct VerifyConfig {
+    pub settler: Address,
+    pub rollups: Vec<Genesis>,
+    pub posters: HashMap<u64, Address>,
+    pub da_url: String,
+    pub webhook: Option<Url>,
+    pub retry_delay: Duration,
+}
+
+/// Everything added to reth's `node` command
+#[derive(Debug, Clone, Args)]
+pub struct NodeArgs {
+    #[command(flatten)]
+    pub da: DAArgs,
+
+    #[command(flatten)]
+    pub orders: OrderArgs,
+
+    #[command(flatten)]
+    pub relay: RelayArgs,
+
+    #[command(flatten)]
+    pub verify: VerifyArgs,
+}
+
+const NEXUS_ARGS: &str = "--da.nexus-rpc, --da.nexus-settler and --da.poster-key";
+
+/// Validated DA ExEx configuration
+#[derive(Clone)]
+pub struct DAConfig {
+    pub url: String,
+    pub auth_token: Option<String>,
+    pub batch_size: u64,
+    pub request_timeout: Duration,
+    pub retry_delay: Duration,
+    pub max_in_flight: usize,
+    pub mode: PostingMode,
+    pub compression: Compression,
+    pub sequencer_key: Option<PrivateKeySigner>,
+    pub nexus_poster: Option<NexusPoster>,
+}
+
+impl DAArgs {
+    /// Checks the arguments, returning `None` if DA submission is disabled
+    pub fn validate(&self) -> eyre::Result<Option<DAConfig>> {
+        if self.disable {
+            return Ok(None);
+        }
+        let url = self
+            .url
+            .as_deref()
+            .ok_or_else(|| eyre!("--da.url is required unless --da.disable is set"))?;
+        let parsed = Url::parse(url).wrap_err_with(|| format!("--da.url {} is not a URL", url))?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return Err(eyre!("--da.url must be an http or https URL, got {}", url));
+        }
+        if self.batch_size == 0 {
+            return Err(eyre!("--da.batch-size must be at least 1"));
+        }
+        if self.max_in_flight == 0 {
+            return Err(eyre!("--da.max-in-flight must be at least 1"));
+        }
+        if self.request_timeout == 0 {
+            return Err(eyre!("--da.request-timeout must be at least 1 second"));
+        }
+        let sequencer_key = self
+            .sequencer_key
+            .as_deref()
+            .map(|key| {
+                key.parse::<PrivateKeySigner>()
+                    .wrap_err("--da.sequencer-key is not a valid private key")
+            })
+            .transpose()?;
+        Ok(Some(DAConfig {
+            // route paths are appended to the base URL
+            url: url.trim_end_matches('/').to_string(),
+            auth_token: self.auth_token.clone(),
+            batch_size: self.batch_size,
+            request_timeout: Duration::from_secs(self.request_timeout),
+            retry_delay: Duration::from_secs(self.retry_delay),
+            max_in_flight: self.max_in_flight,
+            mode: self.mode,
+            compression: self.compression,
+            sequencer_key,
+            nexus_poster: self.nexus_poster()?,
+        }))
+    }
+
+    /// Nexus posting needs all of its arguments or none of them
+    fn nexus_poster(&self) -> eyre::Result<Option<NexusPoster>> {
+        let (rpc_url, settler, poster_key) =
+            match (&self.nexus_rpc, &self.nexus_settler, &self.poster_key) {
+                (None, None, _) => return Ok(None),
+                (Some(rpc_url), Some(settler), Some(poster_key)) => (rpc_url, *settler, poster_key),
+                _ => return Err(eyre!("Posting to Nexus needs all of {}", NEXUS_ARGS)),
+            };
+        let rpc_url = rpc_url
+            .parse::<Url>()
+            .wrap_err_with(|| format!("--da.nexus-rpc {} is not a URL", rpc_url))?;
+        let signer = poster_key
+            .parse::<PrivateKeySigner>()
+            .wrap_err("--da.poster-key is not a valid private key")?;
+        Ok(Some(NexusPoster::new(rpc_url, settler, signer)))
+    }
+}
+
+impl RelayArgs {
+    /// Checks the arguments, returning `None` if no key is given
+    pub fn validate(&self) -> eyre::Result<Option<RelayConfig>> {
+        let has_targets = self.nexus_rpc.is_some() || !self.peers.is_empty();
+        let Some(key) = self.key.as_deref() else {
+            if has_targets {
+                return Err(eyre!("Relaying roots needs --relay.key"));
+            }
+            return Ok(None);
+        };
+        let signer = key
+            .parse::<PrivateKeySigner>()
+            .wrap_err("--relay.key is not a valid private key")?;
+        let mut targets = Vec::new();
+        match (&self.nexus_rpc, &self.nexus_settler) {
+            (Some(rpc_url), Some(settler)) => targets.push(RelayTarget {
+                rpc_url: parse_url("--relay.nexus-rpc", rpc_url)?,
+                settler: *settler,
+                state_root: self.state_root,
+            }),
+            (None, None) if self.state_root => {
+                return Err(eyre!("--relay.state-root needs --relay.nexus-rpc"))
+            }
+            (None, None) => {}
+            _ => {
+                return Err(eyre!(
+                    "Relaying to Nexus needs both --relay.nexus-rpc and --relay.nexus-settler"
+                ))
+            }
+        }
+        for peer in &self.peers {
+            let (settler, rpc_url) = peer
+                .split_once('@')
+                .ok_or_else(|| eyre!("--relay.peer {} is not SETTLER@URL", peer))?;
+            targets.push(RelayTarget {
+                rpc_url: parse_url("--relay.peer", rpc_url)?,
+                settler: parse_address("--relay.peer", settler)?,
+                state_root: false,
+            });
+        }
+        if targets.is_empty() {
+            return Err(eyre!(
+                "--relay.key is set but there is nothing to relay to, add --relay.nexus-rpc or --relay.peer"
+            ));
+        }
+        Ok(Some(RelayConfig {
+            signer,
+            targets,
+            confirmations: self.confirmations,
+            interval: Duration::from_secs(self.interval),
+            retry_delay: Duration::from_secs(self.retry_delay),
+        }))
+    }
+}
+
+impl VerifyArgs {
+    /// Checks the arguments and reads the genesis files, returning `None` without a settler
+    pub fn validate(&self) -> eyre::Result<Option<VerifyConfig>> {
+        let Some(settler) = self.settler else {
+            if !self.rollups.is_empty() || self.da_url.is_some() {
+                return Err(eyre!("Verifying rollups needs --verify.settler"));
+            }
+            return Ok(None);
+        };
+        if self.rollups.is_empty() {
+            return Err(eyre!(
+                "--verify.settler is set but no --verify.rollup is given"
+            ));
+        }
+        let da_url = self
+            .da_url
+            .as_deref()
+            .ok_or_else(|| eyre!("Verifying rollups needs --verify.da-url"))?;
+        parse_url("--verify.da-url", da_url)?;
+        let mut rollups = Vec::new();
+        for path in &self.rollups {
+            let genesis = std::fs::read_to_string(path)
+                .wrap_err_with(|| format!("Failed to read genesis file {}", path.display()))?;
+            let genesis: Genesis = serde_json::from_str(&genesis)
+                .wrap_err_with(|| format!("{} is not a genesis file", path.display()))?;
+            let chain_id = genesis.config.chain_id;
+            if rollups
+                .iter()
+                .any(|g: &Genesis| g.config.chain_id == chain_id)
+            {
+                return Err(eyre!("Rollup {} is given more than once", chain_id));
+            }
+            rollups.push(genesis);
+        }
+        let mut posters = HashMap::new();
+        for poster in &self.posters {
+            let (chain_id, address) = poster
+                .split_once('=')
+                .ok_or_else(|| eyre!("--verify.poster {} is not CHAIN_ID=ADDRESS", poster))?;
+            let chain_id = chain_id
+                .parse::<u64>()
+                .wrap_err_with(|| format!("--verify.poster {} has no chain id", poster))?;
+            if !rollups.iter().any(|g| g.config.chain_id == chain_id) {
+                return Err(eyre!(
+                    "--verify.poster names rollup {}, which is not verified",
+                    chain_id
+                ));
+            }
+            posters.insert(chain_id, parse_address("--verify.poster", address)?);
+        }
+        if let Some(genesis) = rollups
+            .iter()
+            .find(|g| !posters.contains_key(&g.config.chain_id))
+        {
+            return Err(eyre!(
+                "Rollup {} is verified but has no --verify.poster",
+                genesis.config.chain_id
+            ));
+        }
+        let webhook = self
+            .webhook
+            .as_deref()
+            .map(|url| parse_url("--verify.webhook", url))
+            .transpose()?;
+        Ok(Some(VerifyConfig {
+            settler,
+            rollups,
+            posters,
+            da_url: da_url.trim_end_matches('/').to_string(),
+            webhook,
+            retry_delay: Duration::from_secs(self.retry_delay),
+        }))
+    }
+}
+
+fn parse_url(arg: &str, url: &str) -> eyre::Result<Url> {
+    url.parse::<Url>()
+        .wrap_err_with(|| format!("{} {} is not a URL", arg, url))
+}
+
+fn parse_address(arg: &str, address: &str) -> eyre::Result<Address> {
+    address
+        .parse::<Address>()
+        .wrap_err_with(|| format!("{} {} is not an address", arg, address))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[derive(Parser)]
+    struct Cli {
+        #[command(flatten)]
+        node: NodeArgs,
+    }
+
+    fn parse(args: &[&str]) -> eyre::Result<Option<DAConfig>> {
+        Cli::try_parse_from(std::iter::once("node").chain(args.iter().copied()))?
+            .node
+            .da
+            .validate()
+    }
+
+    #[test]
+    fn test_validate_args() {
+        let config = parse(&[
+            "--da.url",
+            "http://127.0.0.1:3000/",
+            "--da.compression",
+            "zstd",
+            "--da.mode",
+            "state-diffs",
+        ])
+        .unwrap()
+        .unwrap();
+        assert_eq!(config.url, "http://127.0.0.1:3000");
+        assert_eq!(config.batch_size, 100);
+        assert_eq!(config.compression, Compression::Zstd);
+        assert_eq!(config.mode, PostingMode::StateDiffs);
+        assert!(config.nexus_poster.is_none());
+
+        assert!(parse(&["--da.disable"]).unwrap().is_none());
+        assert!(parse(&[]).is_err());
+        assert!(parse(&["--da.url", "127.0.0.1:3000"]).is_err());
+        assert!(parse(&["--da.url", "http://127.0.0.1:3000", "--da.batch-size", "0"]).is_err());
+        assert!(parse(&[
+            "--da.url",
+            "http://127.0.0.1:3000",
+            "--da.nexus-rpc",
+            "http://127.0.0.1:8545"
+        ])
+        .is_err());
+        assert!(parse(&[
+            "--da.url",
+            "http://127.0.0.1:3000",
+            "--da.nexus-settler",
+            "0x01"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_order_args() {
+        let settler = "0x5FbDB2315678afecb367f032d93F642f64180aa3";
+        let cli =
+            Cli::try_parse_from(["node", "--da.disable", "--orders.settler", settler]).unwrap();
+        assert_eq!(cli.node.orders.settler, Some(settler.parse().unwrap()));
+        assert!(Cli::try_parse_from(["node", "--da.disable", "--orders.settler", "0x01"]).is_err());
+    }
+
+    #[test]
+    fn test_relay_args() {
+        let relay = |args: &[&str]| {
+            Cli::try_parse_from(["node", "--da.disable"].iter().chain(args))
+                .unwrap()
+                .node
+                .relay
+                .validate()
+        };
+        let settler = "0x5FbDB2315678afecb367f032d93F642f64180aa3";
+        let key = "0x59c6995e998f97a5a0044966f0945389dc9e86dae88c7a8412f4603b6b78690d";
+        let peer = format!("{}@http://127.0.0.1:8547", settler);
+        let config = relay(&[
+            "--relay.key",
+            key,
+            "--relay.nexus-rpc",
+            "http://127.0.0.1:8545",
+            "--relay.nexus-settler",
+            settler,
+            "--relay.peer",
+            peer.as_str(),
+        ])
+        .unwrap()
+        .unwrap();
+        assert_eq!(config.targets.len(), 2);
+        assert_eq!(config.targets[1].rpc_url.port(), Some(8547));
+        assert!(!config.targets[1].state_root);
+
+        assert!(relay(&[]).unwrap().is_none());
+        assert!(relay(&["--relay.peer", peer.as_str()]).is_err());
+        assert!(relay(&["--relay.key", key]).is_err());
+        assert!(relay(&["--relay.key", key, "--relay.peer", "http://127.0.0.1:8547"]).is_err());
+        assert!(relay(&[
+            "--relay.key",
+            key,
+            "--relay.peer",
+            peer.as_str(),
+            "--relay.state-root"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_verify_args() {
+        let verify = |args: &[&str]| {
+            Cli::try_parse_from(["node", "--da.disable"].iter().chain(args))
+                .unwrap()
+                .node
+                .verify
+                .validate()
+        };
+        let dir = std::env::temp_dir().join(format!("verify-args-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let genesis = dir.join("rollup1.json");
+        let mut config = Genesis::default();
+        config.config.chain_id = 31338;
+        std::fs::write(&genesis, serde_json::to_string(&config).unwrap()).unwrap();
+        let genesis = genesis.to_str().unwrap();
+        let settler = "0x5FbDB2315678afecb367f032d93F642f64180aa3";
+        let poster = "31338=0x70997970C51812dc3A010C7d01b50e0d17dc79C8";
+
+        let config = verify(&[
+            "--verify.settler",
+            settler,
+            "--verify.rollup",
+            genesis,
+            "--verify.poster",
+            poster,
+            "--verify.da-url",
+            "http://127.0.0.1:3000/",
+        ])
+        .unwrap()
+        .unwrap();
+        assert_eq!(config.rollups[0].config.chain_id, 31338);
+        assert!(config.posters.contains_key(&31338));
+        assert_eq!(config.da_url, "http://127.0.0.1:3000");
+
+        assert!(verify(&[]).unwrap().is_none());
+        assert!(verify(&["--verify.rollup", genesis]).is_err());
+        assert!(verify(&[
+            "--verify.settler",
+            settler,
+            "--verify.da-url",
+            "http://127.0.0.1:3000"
+        ])
+        .is_err());
+        // a rollup without a poster, given twice, and a poster for a rollup that is not verified
+        let base = [
+            "--verify.settler",
+            settler,
+            "--verify.rollup",
+            genesis,
+            "--verify.da-url",
+            "http://127.0.0.1:3000",
+        ];
+        assert!(verify(&base).is_err());
+        let mut twice = base.to_vec();
+        twice.extend(["--verify.rollup", genesis]);
+        assert!(verify(&twice).is_err());
+        let mut unknown_poster = base.to_vec();
+        unknown_poster.extend([
+            "--verify.poster",
+            "31339=0x70997970C51812dc3A010C7d01b50e0d17dc79C8",
+        ]);
+        assert!(verify(&unknown_poster).is_err());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
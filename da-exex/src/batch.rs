@@ -0,0 +1,363 @@
+use crate::diff::BlockDiff;
+use alloy::{eips::eip4895::Withdrawal, primitives::B256};
+use alloy_rlp::{Decodable, Encodable, RlpDecodable, RlpEncodable};
+use bytes::{BufMut, Bytes, BytesMut};
+use eyre::eyre;
+use reth_primitives::{
+    proofs::{calculate_transaction_root, calculate_withdrawals_root},
+    Header, SealedBlockWithSenders, TransactionSigned,
+};
+
+/// Leading byte of every batch posted to DA, bumped whenever the encoding changes
+///
+/// A batch is this byte, a byte naming what it carries, a byte naming the compression and the
+/// RLP list of blocks or state diffs.
+pub const BATCH_VERSION: u8 = 1;
+
+/// What a rollup posts to DA for its blocks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum PostingMode {
+    /// Blocks with their headers and transactions, replayed by executing them
+    #[default]
+    Blocks,
+    /// The account and storage changes of each block, applied directly
+    StateDiffs,
+}
+
+impl PostingMode {
+    fn to_byte(self) -> u8 {
+        match self {
+            Self::Blocks => 0,
+            Self::StateDiffs => 1,
+        }
+    }
+
+    fn from_byte(byte: u8) -> eyre::Result<Self> {
+        match byte {
+            0 => Ok(Self::Blocks),
+            1 => Ok(Self::StateDiffs),
+            other => Err(eyre!("Unknown batch posting mode {}", other)),
+        }
+    }
+}
+
+/// How the block list of a batch is compressed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Compression {
+    #[default]
+    None,
+    Zstd,
+}
+
+impl Compression {
+    fn to_byte(self) -> u8 {
+        match self {
+            Self::None => 0,
+            Self::Zstd => 1,
+        }
+    }
+
+    fn from_byte(byte: u8) -> eyre::Result<Self> {
+        match byte {
+            0 => Ok(Self::None),
+            1 => Ok(Self::Zstd),
+            other => Err(eyre!("Unknown batch compression {}", other)),
+        }
+    }
+}
+
+/// A rollup block as posted to DA, with its sealed header so the chain can be derived from
+/// DA alone
+#[derive(Debug, Clone, PartialEq, Eq, RlpEncodable, RlpDecodable)]
+pub struct BatchBlock {
+    pub hash: B256,
+    pub header: Header,
+    pub transactions: Vec<TransactionSigned>,
+    pub withdrawals: Vec<Withdrawal>,
+}
+
+impl BatchBlock {
+    pub fn from_block(block: &SealedBlockWithSenders) -> Self {
+        Self {
+            hash: block.hash(),
+            header: block.header.header().clone(),
+            transactions: block.body.transactions().cloned().collect(),
+            withdrawals: block
+                .body
+                .withdrawals
+                .clone()
+                .map(|withdrawals| withdrawals.into_inner())
+                .unwrap_or_default(),
+        }
+    }
+
+    pub fn number(&self) -> u64 {
+        self.header.number
+    }
+
+    /// Checks that the header hashes to the block hash and commits to the body
+    pub fn verify(&self) -> eyre::Result<()> {
+        let hash = self.header.hash_slow();
+        if hash != self.hash {
+            return Err(eyre!(
+                "Header of block {} hashes to {}, expected {}",
+                self.number(),
+                hash,
+                self.hash
+            ));
+        }
+        if calculate_transaction_root(&self.transactions) != self.header.transactions_root {
+            return Err(eyre!(
+                "Transactions of block {} do not match its header",
+                self.number()
+            ));
+        }
+        let withdrawals_root = (!self.withdrawals.is_empty()
+            || self.header.withdrawals_root.is_some())
+        .then(|| calculate_withdrawals_root(&self.withdrawals));
+        if withdrawals_root != self.header.withdrawals_root {
+            return Err(eyre!(
+                "Withdrawals of block {} do not match its header",
+                self.number()
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Encodes items as a versioned, optionally compressed RLP list
+fn encode_items<T: Encodable>(
+    mode: PostingMode,
+    items: &[T],
+    compression: Compression,
+) -> eyre::Result<Bytes> {
+    let mut list = Vec::new();
+    items.encode(&mut list);
+    let mut out = BytesMut::new();
+    out.put_u8(BATCH_VERSION);
+    out.put_u8(mode.to_byte());
+    out.put_u8(compression.to_byte());
+    match compression {
+        Compression::None => out.put_slice(&list),
+        Compression::Zstd => out.put_slice(&zstd::encode_all(list.as_slice(), 0)?),
+    }
+    Ok(out.freeze())
+}
+
+fn decode_items<T: Decodable>(data: &[u8], mode: PostingMode) -> eyre::Result<Vec<T>> {
+    let actual = batch_mode(data)?;
+    if actual != mode {
+        return Err(eyre!("Expected a batch of {:?}, got {:?}", mode, actual));
+    }
+    let list = match Compression::from_byte(data[2])? {
+        Compression::None => data[3..].to_vec(),
+        Compression::Zstd => zstd::decode_all(&data[3..])?,
+    };
+    let mut list = list.as_slice();
+    let items = Vec::<T>::decode(&mut list)?;
+    if !list.is_empty() {
+        return Err(eyre!("{} trailing bytes after batch", list.len()));
+    }
+    Ok(items)
+}
+
+/// Reads what a batch carries from its prefix
+pub fn batch_mode(data: &[u8]) -> eyre::Result<PostingMode> {
+    let [version, mode, _compression, ..] = data else {
+        return Err(eyre!("Batch of {} bytes is too short", data.len()));
+    };
+    if *version != BATCH_VERSION {
+        return Err(eyre!("Unsupported batch version {}", version));
+    }
+    PostingMode::from_byte(*mode)
+}
+
+pub fn encode_batch(blocks: &[BatchBlock], compression: Compression) -> eyre::Result<Bytes> {
+    encode_items(PostingMode::Blocks, blocks, compression)
+}
+
+pub fn encode_diffs(diffs: &[BlockDiff], compression: Compression) -> eyre::Result<Bytes> {
+    encode_items(PostingMode::StateDiffs, diffs, compression)
+}
+
+/// Decodes a batch of blocks and checks that they are sealed and form a chain
+pub fn decode_batch(data: &[u8]) -> eyre::Result<Vec<BatchBlock>> {
+    let blocks = decode_items::<BatchBlock>(data, PostingMode::Blocks)?;
+    for block in &blocks {
+        block.verify()?;
+    }
+    for pair in blocks.windows(2) {
+        if pair[1].number() != pair[0].number() + 1 || pair[1].header.parent_hash != pair[0].hash {
+            return Err(eyre!(
+                "Block {} does not follow block {}",
+                pair[1].number(),
+                pair[0].number()
+            ));
+        }
+    }
+    Ok(blocks)
+}
+
+/// Decodes a batch of state diffs and checks that their blocks form a chain
+pub fn decode_diffs(data: &[u8]) -> eyre::Result<Vec<BlockDiff>> {
+    let diffs = decode_items::<BlockDiff>(data, PostingMode::StateDiffs)?;
+    for pair in diffs.windows(2) {
+        if pair[1].number != pair[0].number + 1 || pair[1].parent_hash != pair[0].hash {
+            return Err(eyre!(
+                "Diff of block {} does not follow block {}",
+                pair[1].number,
+                pair[0].number
+            ));
+        }
+    }
+    Ok(diffs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::{AccountDiff, SlotDiff};
+    use alloy::primitives::{Address, U256};
+    use reth_primitives::{Signature, Transaction, TxKind, TxLegacy};
+
+    /// Builds a chain of sealed blocks holding the given numbers of transactions
+    fn chain(transactions: &[usize]) -> Vec<BatchBlock> {
+        let mut parent_hash = B256::ZERO;
+        transactions
+            .iter()
+            .enumerate()
+            .map(|(i, count)| {
+                let transactions = (0..*count as u64)
+                    .map(|nonce| {
+                        let tx = Transaction::Legacy(TxLegacy {
+                            chain_id: Some(31338),
+                            nonce,
+                            gas_price: 7,
+                            gas_limit: 21_000,
+                            to: TxKind::Call(Address::with_last_byte(1)),
+                            value: U256::from(1),
+                            input: Default::default(),
+                        });
+                        TransactionSigned::from_transaction_and_signature(
+                            tx,
+                            Signature::test_signature(),
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                let number = i as u64 + 1;
+                let header = Header {
+                    parent_hash,
+                    number,
+                    timestamp: 1_700_000_000 + number,
+                    beneficiary: Address::with_last_byte(2),
+                    gas_limit: 1_000_000_000,
+                    base_fee_per_gas: Some(7),
+                    mix_hash: B256::with_last_byte(3),
+                    state_root: B256::with_last_byte(4),
+                    transactions_root: calculate_transaction_root(&transactions),
+                    withdrawals_root: Some(calculate_withdrawals_root(&[])),
+                    ..Default::default()
+                };
+                parent_hash = header.hash_slow();
+                BatchBlock {
+                    hash: parent_hash,
+                    header,
+                    transactions,
+                    withdrawals: Vec::new(),
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_batch_roundtrip() {
+        let blocks = chain(&[2, 0, 1]);
+        for compression in [Compression::None, Compression::Zstd] {
+            let encoded = encode_batch(&blocks, compression).unwrap();
+            assert_eq!(
+                encoded[..3],
+                [
+                    BATCH_VERSION,
+                    PostingMode::Blocks.to_byte(),
+                    compression.to_byte()
+                ]
+            );
+            assert_eq!(decode_batch(&encoded).unwrap(), blocks);
+        }
+    }
+
+    #[test]
+    fn test_diffs_roundtrip() {
+        let account = AccountDiff {
+            address: Address::with_last_byte(1),
+            destroyed: false,
+            wipe_storage: false,
+            nonce: 1,
+            balance: U256::from(100),
+            code_hash: B256::with_last_byte(2),
+            code: vec![0x60, 0x00].into(),
+            storage: vec![SlotDiff {
+                slot: U256::from(1),
+                value: U256::from(2),
+            }],
+        };
+        let diffs = (1..=2)
+            .map(|number| BlockDiff {
+                number,
+                hash: B256::with_last_byte(number as u8),
+                parent_hash: B256::with_last_byte(number as u8 - 1),
+                timestamp: number,
+                state_root: B256::with_last_byte(9),
+                accounts: vec![account.clone()],
+            })
+            .collect::<Vec<_>>();
+        let encoded = encode_diffs(&diffs, Compression::Zstd).unwrap();
+        assert_eq!(batch_mode(&encoded).unwrap(), PostingMode::StateDiffs);
+        assert_eq!(decode_diffs(&encoded).unwrap(), diffs);
+        // each kind of batch is only read as itself
+        assert!(decode_batch(&encoded).is_err());
+        assert!(decode_diffs(&encode_batch(&chain(&[1]), Compression::None).unwrap()).is_err());
+        assert!(decode_diffs(
+            &encode_diffs(&[diffs[1].clone(), diffs[0].clone()], Compression::None).unwrap()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_decode_rejects_malformed() {
+        assert!(decode_batch(&[]).is_err());
+        assert!(decode_batch(&[BATCH_VERSION]).is_err());
+        let mut encoded = encode_batch(&chain(&[1]), Compression::None)
+            .unwrap()
+            .to_vec();
+        encoded[0] = BATCH_VERSION + 1;
+        assert!(decode_batch(&encoded).is_err());
+        encoded[0] = BATCH_VERSION;
+        encoded[1] = 9;
+        assert!(decode_batch(&encoded).is_err());
+        encoded[1] = PostingMode::Blocks.to_byte();
+        encoded[2] = 9;
+        assert!(decode_batch(&encoded).is_err());
+        encoded[2] = Compression::None.to_byte();
+        encoded.push(0);
+        assert!(decode_batch(&encoded).is_err());
+    }
+
+    #[test]
+    fn test_decode_rejects_unsealed_blocks() {
+        let decode =
+            |blocks: &[BatchBlock]| decode_batch(&encode_batch(blocks, Compression::None).unwrap());
+        let mut blocks = chain(&[1, 1]);
+        blocks[0].transactions.clear();
+        assert!(decode(&blocks).is_err());
+
+        let mut blocks = chain(&[1, 1]);
+        blocks[0].header.state_root = B256::ZERO;
+        assert!(decode(&blocks).is_err());
+
+        // blocks must link up even when each is sealed on its own
+        let blocks = chain(&[1, 1]);
+        assert!(decode(&[blocks[0].clone(), blocks[0].clone()]).is_err());
+        assert!(decode(&blocks[1..]).is_ok());
+    }
+}
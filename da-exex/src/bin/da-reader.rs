@@ -0,0 +1,229 @@
+use alloy::{
+    eips::BlockNumberOrTag,
+    primitives::{Address, B256},
+    providers::{Provider, ProviderBuilder},
+    rpc::types::BlockTransactionsKind,
+};
+use clap::{Args, Parser, Subcommand};
+use da_exex::{
+    batch::{batch_mode, decode_batch, decode_diffs, PostingMode},
+    nexus::{fetch_batch, read_commitments, DACommitmentRecord},
+    replay::Replayer,
+};
+use eyre::eyre;
+use reqwest::Url;
+use std::path::PathBuf;
+
+/// Reads rollup batches back from DA and reconstructs the chain from them
+#[derive(Parser)]
+#[command(version, about = "Read and replay rollup batches posted to DA")]
+struct Cli {
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// List the batches posted to the Nexus chain for a rollup
+    List {
+        #[command(flatten)]
+        nexus: NexusArgs,
+    },
+    /// Fetch a batch from DA and print its blocks and transactions
+    Decode {
+        /// Commitment returned by the DA server
+        #[arg(long)]
+        commitment: B256,
+        /// Chain id of the rollup the batch was submitted for
+        #[arg(long)]
+        chain_id: u64,
+        #[arg(long, default_value = "http://127.0.0.1:3000")]
+        da_url: String,
+    },
+    /// Rebuild the state from every posted batch on top of the genesis, executing blocks or
+    /// applying state diffs, and print the state roots
+    Replay {
+        #[command(flatten)]
+        nexus: NexusArgs,
+        #[arg(long, default_value = "http://127.0.0.1:3000")]
+        da_url: String,
+        /// Genesis file the rollup was started with
+        #[arg(long)]
+        genesis: PathBuf,
+        /// Compare the recomputed state roots with the ones served by this rollup node
+        #[arg(long)]
+        rollup_rpc: Option<Url>,
+    },
+}
+
+#[derive(Args)]
+struct NexusArgs {
+    #[arg(long, default_value = "http://127.0.0.1:8545")]
+    nexus_rpc: Url,
+    /// Address of the NexusSettler contract
+    #[arg(long)]
+    settler: Address,
+    /// Chain id of the rollup
+    #[arg(long)]
+    chain_id: u64,
+    /// Only consider batches posted by this address, usually the rollup's sequencer
+    #[arg(long)]
+    poster: Option<Address>,
+}
+
+impl NexusArgs {
+    async fn records(&self) -> eyre::Result<Vec<DACommitmentRecord>> {
+        let records = read_commitments(self.nexus_rpc.clone(), self.settler, self.chain_id).await?;
+        Ok(records
+            .into_iter()
+            .filter(|record| self.poster.is_none_or(|poster| record.poster == poster))
+            .collect())
+    }
+}
+
+async fn list(nexus: NexusArgs) -> eyre::Result<()> {
+    let records = nexus.records().await?;
+    println!(
+        "📚 {} batches posted for chain {}",
+        records.len(),
+        nexus.chain_id
+    );
+    for record in records {
+        println!(
+            "#{} blocks {}..={} commitment {} posted by {} at {}",
+            record.index,
+            record.from_block,
+            record.to_block,
+            record.commitment,
+            record.poster,
+            record.timestamp
+        );
+    }
+    Ok(())
+}
+
+async fn decode(commitment: B256, chain_id: u64, da_url: String) -> eyre::Result<()> {
+    let data = fetch_batch(&reqwest::Client::new(), &da_url, chain_id, commitment).await?;
+    if batch_mode(&data)? == PostingMode::StateDiffs {
+        let diffs = decode_diffs(&data)?;
+        println!("📦 Batch {} holds {} state diffs", commitment, diffs.len());
+        for diff in diffs {
+            println!(
+                "🧱 Block {} ({}) touches {} accounts, state root {}",
+                diff.number,
+                diff.hash,
+                diff.accounts.len(),
+                diff.state_root
+            );
+            for account in &diff.accounts {
+                if account.destroyed {
+                    println!("  ↳ {} destroyed", account.address);
+                } else {
+                    println!(
+                        "  ↳ {} nonce {} balance {} with {} slots written",
+                        account.address,
+                        account.nonce,
+                        account.balance,
+                        account.storage.len()
+                    );
+                }
+            }
+        }
+        return Ok(());
+    }
+    let blocks = decode_batch(&data)?;
+    println!("📦 Batch {} holds {} blocks", commitment, blocks.len());
+    for block in blocks {
+        println!(
+            "🧱 Block {} ({}) at {} with {} transactions, state root {}",
+            block.number(),
+            block.hash,
+            block.header.timestamp,
+            block.transactions.len(),
+            block.header.state_root
+        );
+        for transaction in &block.transactions {
+            let sender = transaction
+                .recover_signer()
+                .ok_or_else(|| eyre!("Invalid signature on {}", transaction.hash()))?;
+            println!("  ↳ {} from {}", transaction.hash(), sender);
+        }
+    }
+    Ok(())
+}
+
+async fn replay(
+    nexus: NexusArgs,
+    da_url: String,
+    genesis: PathBuf,
+    rollup_rpc: Option<Url>,
+) -> eyre::Result<()> {
+    let client = reqwest::Client::new();
+    let rollup = rollup_rpc.map(|url| ProviderBuilder::new().on_http(url));
+    let mut replayer = Replayer::from_genesis_file(&genesis)?;
+    for record in nexus.records().await? {
+        let data = fetch_batch(&client, &da_url, nexus.chain_id, record.commitment).await?;
+        let mut expected = None;
+        replayer.apply_batch(&data, |_, block| {
+            expected = Some(block.posted_root);
+            Ok(())
+        })?;
+        // a batch holding nothing new
+        let Some(expected) = expected else {
+            continue;
+        };
+        let state_root = replayer.state_root();
+        if state_root != expected {
+            return Err(eyre!(
+                "State root mismatch at block {}: its header has {}, replaying gives {}",
+                replayer.head(),
+                expected,
+                state_root
+            ));
+        }
+        println!(
+            "🔁 Replayed batch #{} up to block {}, state root {}",
+            record.index,
+            replayer.head(),
+            state_root
+        );
+        if let Some(rollup) = &rollup {
+            let header = rollup
+                .get_block_by_number(
+                    BlockNumberOrTag::Number(replayer.head()),
+                    BlockTransactionsKind::Hashes,
+                )
+                .await?
+                .ok_or_else(|| eyre!("Rollup node has no block {}", replayer.head()))?
+                .header;
+            if header.state_root != state_root {
+                return Err(eyre!(
+                    "State root mismatch at block {}: rollup has {}, DA data gives {}",
+                    replayer.head(),
+                    header.state_root,
+                    state_root
+                ));
+            }
+            println!("✅ State root matches the rollup node");
+        }
+    }
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> eyre::Result<()> {
+    match Cli::parse().command {
+        Commands::List { nexus } => list(nexus).await,
+        Commands::Decode {
+            commitment,
+            chain_id,
+            da_url,
+        } => decode(commitment, chain_id, da_url).await,
+        Commands::Replay {
+            nexus,
+            da_url,
+            genesis,
+            rollup_rpc,
+        } => replay(nexus, da_url, genesis, rollup_rpc).await,
+    }
+}
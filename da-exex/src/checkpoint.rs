@@ -0,0 +1,160 @@
+use alloy::primitives::B256;
+use eyre::WrapErr;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    fs,
+    io::ErrorKind,
+    marker::PhantomData,
+    path::{Path, PathBuf},
+};
+
+/// Earlier acknowledged blocks a checkpoint keeps
+const HISTORY: usize = 64;
+
+/// The last block whose batch was acknowledged by DA, and by Nexus if posting is enabled
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub number: u64,
+    pub hash: B256,
+    /// The last blocks of the batches acknowledged before, newest first, to find where the
+    /// chain forked off if the checkpoint was reorged out while the node was down
+    pub history: Vec<Checkpoint>,
+}
+
+impl Checkpoint {
+    /// The checkpoint after acknowledging block `number` on top of `previous`, forgetting the
+    /// blocks it replaced
+    pub fn advance(previous: Option<Self>, number: u64, hash: B256) -> Self {
+        let history = previous
+            .into_iter()
+            .flat_map(|mut previous| {
+                let history = std::mem::take(&mut previous.history);
+                std::iter::once(previous).chain(history)
+            })
+            .filter(|earlier| earlier.number < number)
+            .take(HISTORY)
+            .collect();
+        Self {
+            number,
+            hash,
+            history,
+        }
+    }
+
+    /// Rewinds to the newest acknowledged block still on the canonical chain, `None` if the
+    /// chain forked off before all of them
+    pub fn last_canonical(
+        &self,
+        mut is_canonical: impl FnMut(u64, B256) -> eyre::Result<bool>,
+    ) -> eyre::Result<Option<Self>> {
+        if is_canonical(self.number, self.hash)? {
+            return Ok(Some(self.clone()));
+        }
+        for (index, earlier) in self.history.iter().enumerate() {
+            if is_canonical(earlier.number, earlier.hash)? {
+                return Ok(Some(Self {
+                    number: earlier.number,
+                    hash: earlier.hash,
+                    history: self.history[index + 1..].to_vec(),
+                }));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Persists a checkpoint in a file, so a restarted node knows where to resume from
+///
+/// `T` is what gets persisted, the DA checkpoint unless an ExEx keeps more state with it.
+#[derive(Debug, Clone)]
+pub struct CheckpointFile<T = Checkpoint> {
+    path: PathBuf,
+    _checkpoint: PhantomData<fn() -> T>,
+}
+
+impl<T: Serialize + DeserializeOwned> CheckpointFile<T> {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            _checkpoint: PhantomData,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns `None` if nothing was acknowledged yet
+    pub fn load(&self) -> eyre::Result<Option<T>> {
+        match fs::read(&self.path) {
+            Ok(checkpoint) => Ok(Some(
+                serde_json::from_slice(&checkpoint)
+                    .wrap_err_with(|| format!("Corrupt checkpoint {}", self.path.display()))?,
+            )),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Writes to a temporary file first, so a crash never leaves a partial checkpoint behind
+    pub fn save(&self, checkpoint: &T) -> eyre::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let tmp = self.path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_vec(checkpoint)?)?;
+        fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checkpoint_roundtrip() {
+        let dir = std::env::temp_dir().join(format!("da-checkpoint-{}", std::process::id()));
+        let file: CheckpointFile = CheckpointFile::new(dir.join("da-exex.json"));
+        assert_eq!(file.load().unwrap(), None);
+        let checkpoint = Checkpoint::advance(None, 42, B256::with_last_byte(1));
+        file.save(&checkpoint).unwrap();
+        assert_eq!(file.load().unwrap(), Some(checkpoint));
+        fs::write(file.path(), b"{").unwrap();
+        assert!(file.load().is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_last_canonical() {
+        let mut checkpoint = None;
+        for number in [10, 20, 30] {
+            checkpoint = Some(Checkpoint::advance(
+                checkpoint,
+                number,
+                B256::with_last_byte(number as u8),
+            ));
+        }
+        // blocks after 15 were replaced and acknowledged again
+        let checkpoint = Checkpoint::advance(checkpoint, 18, B256::with_last_byte(99));
+        assert_eq!(
+            checkpoint
+                .history
+                .iter()
+                .map(|earlier| earlier.number)
+                .collect::<Vec<_>>(),
+            vec![10]
+        );
+
+        let canonical = |number: u64, hash: B256| Ok(hash == B256::with_last_byte(number as u8));
+        assert_eq!(
+            checkpoint.last_canonical(canonical).unwrap(),
+            Some(Checkpoint::advance(None, 10, B256::with_last_byte(10)))
+        );
+        assert_eq!(
+            checkpoint.last_canonical(|_, _| Ok(true)).unwrap(),
+            Some(checkpoint.clone())
+        );
+        assert_eq!(checkpoint.last_canonical(|_, _| Ok(false)).unwrap(), None);
+    }
+}
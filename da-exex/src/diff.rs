@@ -0,0 +1,296 @@
+use alloy::primitives::{Address, Bytes, B256, U256};
+use alloy_rlp::{RlpDecodable, RlpEncodable};
+use eyre::eyre;
+use reth::revm::db::{
+    states::reverts::{AccountInfoRevert, AccountRevert},
+    BundleState,
+};
+use reth_execution_types::ExecutionOutcome;
+use reth_primitives::SealedBlockWithSenders;
+
+/// A storage slot as left by a block
+#[derive(Debug, Clone, PartialEq, Eq, RlpEncodable, RlpDecodable)]
+pub struct SlotDiff {
+    pub slot: U256,
+    pub value: U256,
+}
+
+/// An account as left by a block that touched it
+#[derive(Debug, Clone, PartialEq, Eq, RlpEncodable, RlpDecodable)]
+pub struct AccountDiff {
+    pub address: Address,
+    /// The account no longer exists, the other fields are empty
+    pub destroyed: bool,
+    /// Storage was cleared before `storage` was written, as when an account is destroyed and
+    /// created again
+    pub wipe_storage: bool,
+    pub nonce: u64,
+    pub balance: U256,
+    pub code_hash: B256,
+    /// Code of the account if the block deployed it, empty otherwise
+    pub code: Bytes,
+    /// Slots written by the block, in slot order
+    pub storage: Vec<SlotDiff>,
+}
+
+/// The state changes of a block, with the header fields needed to check them
+#[derive(Debug, Clone, PartialEq, Eq, RlpEncodable, RlpDecodable)]
+pub struct BlockDiff {
+    pub number: u64,
+    pub hash: B256,
+    pub parent_hash: B256,
+    pub timestamp: u64,
+    pub state_root: B256,
+    /// Accounts touched by the block, in address order
+    pub accounts: Vec<AccountDiff>,
+}
+
+/// Derives the state changes of each block of `outcome`, in block order
+///
+/// The bundle state holds the state after the last block and, for every block, the values it
+/// overwrote. Walking the blocks backwards, the accounts a block overwrote are read from the
+/// bundle before reverting it, which leaves the state after the previous block.
+pub fn state_diffs(
+    blocks: &[&SealedBlockWithSenders],
+    outcome: &ExecutionOutcome,
+) -> eyre::Result<Vec<BlockDiff>> {
+    let mut bundle = outcome.bundle.clone();
+    if blocks.first().map(|block| block.number) != Some(outcome.first_block)
+        || bundle.reverts.len() != blocks.len()
+    {
+        return Err(eyre!(
+            "Execution outcome from block {} with {} reverts does not cover the {} blocks given",
+            outcome.first_block,
+            bundle.reverts.len(),
+            blocks.len()
+        ));
+    }
+    let mut diffs = Vec::with_capacity(blocks.len());
+    for block in blocks.iter().rev() {
+        let mut accounts = bundle
+            .reverts
+            .last()
+            .expect("one revert per block")
+            .iter()
+            .map(|(address, revert)| account_diff(&bundle, *address, revert))
+            .collect::<Vec<_>>();
+        accounts.sort_by_key(|account| account.address);
+        diffs.push(BlockDiff {
+            number: block.number,
+            hash: block.hash(),
+            parent_hash: block.parent_hash,
+            timestamp: block.timestamp,
+            state_root: block.state_root,
+            accounts,
+        });
+        bundle.revert_latest();
+    }
+    diffs.reverse();
+    Ok(diffs)
+}
+
+/// Reads the account a revert belongs to as the bundle currently holds it
+fn account_diff(bundle: &BundleState, address: Address, revert: &AccountRevert) -> AccountDiff {
+    let account = bundle.state.get(&address);
+    let mut storage = revert
+        .storage
+        .keys()
+        .map(|slot| SlotDiff {
+            slot: *slot,
+            value: account
+                .and_then(|account| account.storage.get(slot))
+                .map(|slot| slot.present_value)
+                .unwrap_or_default(),
+        })
+        .collect::<Vec<_>>();
+    storage.sort_by_key(|slot| slot.slot);
+    let Some(info) = account.and_then(|account| account.info.clone()) else {
+        return AccountDiff {
+            address,
+            destroyed: true,
+            wipe_storage: true,
+            nonce: 0,
+            balance: U256::ZERO,
+            code_hash: B256::ZERO,
+            code: Bytes::new(),
+            storage: Vec::new(),
+        };
+    };
+    let deployed = match &revert.account {
+        AccountInfoRevert::DeleteIt => true,
+        AccountInfoRevert::RevertTo(previous) => previous.code_hash != info.code_hash,
+        AccountInfoRevert::DoNothing => false,
+    };
+    let code = deployed
+        .then(|| {
+            info.code
+                .clone()
+                .or_else(|| bundle.contracts.get(&info.code_hash).cloned())
+        })
+        .flatten()
+        .map(|code| code.original_bytes())
+        .unwrap_or_default();
+    AccountDiff {
+        address,
+        destroyed: false,
+        wipe_storage: revert.wipe_storage,
+        nonce: info.nonce,
+        balance: info.balance,
+        code_hash: info.code_hash,
+        code,
+        storage,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reth::revm::{
+        db::{states::bundle_state::BundleRetention, CacheDB, EmptyDB, State},
+        primitives::{Account, AccountInfo, AccountStatus, EvmStorageSlot},
+        Database, DatabaseCommit,
+    };
+    use reth_primitives::{BlockBody, Header, SealedBlock, SealedHeader};
+
+    fn block(number: u64) -> SealedBlockWithSenders {
+        let header = Header {
+            number,
+            ..Default::default()
+        };
+        let hash = header.hash_slow();
+        let block = SealedBlock::new(SealedHeader::new(header, hash), BlockBody::default());
+        SealedBlockWithSenders::new(block, Vec::new()).unwrap()
+    }
+
+    fn info(nonce: u64, balance: u64) -> AccountInfo {
+        AccountInfo {
+            nonce,
+            balance: U256::from(balance),
+            ..Default::default()
+        }
+    }
+
+    /// A touched account with slots written as `(slot, original, present)`
+    fn account(info: AccountInfo, status: AccountStatus, slots: &[(u64, u64, u64)]) -> Account {
+        Account {
+            info,
+            storage: slots
+                .iter()
+                .map(|(slot, original, present)| {
+                    let slot_value =
+                        EvmStorageSlot::new_changed(U256::from(*original), U256::from(*present));
+                    (U256::from(*slot), slot_value)
+                })
+                .collect(),
+            status: status | AccountStatus::Touched,
+        }
+    }
+
+    fn slot(slot: u64, value: u64) -> SlotDiff {
+        SlotDiff {
+            slot: U256::from(slot),
+            value: U256::from(value),
+        }
+    }
+
+    #[test]
+    fn test_state_diffs_from_reverts() {
+        let (a, b) = (Address::with_last_byte(1), Address::with_last_byte(2));
+        let mut db = CacheDB::new(EmptyDB::default());
+        db.insert_account_info(a, info(1, 100));
+        db.insert_account_storage(a, U256::from(1), U256::from(1))
+            .unwrap();
+        db.insert_account_storage(a, U256::from(2), U256::from(2))
+            .unwrap();
+        db.insert_account_info(b, info(0, 50));
+        let mut state = State::builder()
+            .with_database(db)
+            .with_bundle_update()
+            .build();
+        // accounts and slots are loaded before they are changed, as execution does
+        for address in [a, b] {
+            state.basic(address).unwrap();
+        }
+        for number in 1..=3 {
+            state.storage(a, U256::from(number)).unwrap();
+        }
+
+        // block 1 changes slot 1 of `a`, writes slot 2 with the value it held and pays `b`
+        state.commit(
+            [
+                (
+                    a,
+                    account(
+                        info(2, 90),
+                        AccountStatus::default(),
+                        &[(1, 1, 10), (2, 2, 2)],
+                    ),
+                ),
+                (b, account(info(0, 60), AccountStatus::default(), &[])),
+            ]
+            .into_iter()
+            .collect(),
+        );
+        state.merge_transitions(BundleRetention::Reverts);
+        // block 2 destroys `a` and creates it again with only slot 3
+        state.commit(
+            [(a, account(info(2, 0), AccountStatus::SelfDestructed, &[]))]
+                .into_iter()
+                .collect(),
+        );
+        state.commit(
+            [(a, account(info(0, 5), AccountStatus::Created, &[(3, 0, 3)]))]
+                .into_iter()
+                .collect(),
+        );
+        state.merge_transitions(BundleRetention::Reverts);
+        // block 3 destroys `a` for good
+        state.commit(
+            [(a, account(info(0, 5), AccountStatus::SelfDestructed, &[]))]
+                .into_iter()
+                .collect(),
+        );
+        state.merge_transitions(BundleRetention::Reverts);
+
+        let outcome = ExecutionOutcome {
+            bundle: state.take_bundle(),
+            first_block: 1,
+            ..Default::default()
+        };
+        let blocks = [block(1), block(2), block(3)];
+        let diffs = state_diffs(&blocks.iter().collect::<Vec<_>>(), &outcome).unwrap();
+        assert_eq!(diffs.len(), 3);
+
+        // the slot written with its own value is not part of the diff
+        let [diff_a, diff_b] = diffs[0].accounts.as_slice() else {
+            panic!("block 1 touched {:?}", diffs[0].accounts);
+        };
+        assert_eq!((diff_a.address, diff_a.nonce), (a, 2));
+        assert_eq!(diff_a.balance, U256::from(90));
+        assert!(!diff_a.wipe_storage && !diff_a.destroyed);
+        assert_eq!(diff_a.storage, vec![slot(1, 10)]);
+        assert_eq!((diff_b.address, diff_b.balance), (b, U256::from(60)));
+        assert!(diff_b.storage.is_empty());
+
+        // the recreated account starts from empty storage, the old slots are left zeroed
+        let [diff_a] = diffs[1].accounts.as_slice() else {
+            panic!("block 2 touched {:?}", diffs[1].accounts);
+        };
+        assert!(diff_a.wipe_storage && !diff_a.destroyed);
+        assert_eq!((diff_a.nonce, diff_a.balance), (0, U256::from(5)));
+        assert!(diff_a.storage.contains(&slot(3, 3)));
+        assert!(diff_a
+            .storage
+            .iter()
+            .all(|written| written.slot == U256::from(3) || written.value.is_zero()));
+
+        let [diff_a] = diffs[2].accounts.as_slice() else {
+            panic!("block 3 touched {:?}", diffs[2].accounts);
+        };
+        assert!(diff_a.destroyed && diff_a.wipe_storage);
+        assert!(diff_a.storage.is_empty());
+
+        // the outcome has to cover exactly the blocks given
+        assert!(state_diffs(&blocks[..2].iter().collect::<Vec<_>>(), &outcome).is_err());
+    }
+}
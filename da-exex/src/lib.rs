@@ -0,0 +1,12 @@
+pub mod args;
+pub mod batch;
+pub mod checkpoint;
+pub mod diff;
+pub mod nexus;
+pub mod orders;
+pub mod relay;
+pub mod replay;
+pub mod retry;
+pub mod rpc;
+pub mod status;
+pub mod verify;
@@ -1,55 +1,613 @@
-use alloy_rlp::Encodable;
+use alloy::{
+    eips::BlockNumHash,
+    primitives::{keccak256, Address, B256},
+    signers::{local::PrivateKeySigner, SignerSync},
+};
 use bytes::Bytes;
-use futures::{Future, TryStreamExt};
+use da_auth::{signing_digest, SIGNATURE_HEADER};
+use da_exex::{
+    args::{DAConfig, NodeArgs, RelayConfig, VerifyConfig},
+    batch::{encode_batch, encode_diffs, BatchBlock, Compression, PostingMode},
+    checkpoint::{Checkpoint, CheckpointFile},
+    diff::{state_diffs, BlockDiff},
+    nexus::NexusPoster,
+    orders::{block_orders, OrderIndex, OrderSnapshot},
+    relay::{Relayer, RollupRoots, RootsPublisher},
+    retry::{backoff, PERSISTENT_FAILURES},
+    rpc::{ReimannApiServer, ReimannRpc},
+    status::DAStatus,
+    verify::{nexus_events, NexusEvent, Verifier, VerifierSnapshot},
+};
+use eyre::eyre;
+use futures::{stream::FuturesOrdered, Future, StreamExt, TryStreamExt};
 use futures_util::FutureExt;
-use reth::revm::primitives::bytes::BytesMut;
-use reth_exex::{ExExContext, ExExEvent, ExExNotification};
+use parking_lot::RwLock;
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION};
+use reth::{
+    chainspec::{EthChainSpec, EthereumChainSpecParser},
+    primitives::SealedBlockWithSenders,
+    providers::{
+        BlockHashReader, BlockNumReader, BlockReader, ReceiptProvider, TransactionVariant,
+    },
+};
+use reth_execution_types::ExecutionOutcome;
+use reth_exex::{BackfillJobFactory, ExExContext, ExExEvent, ExExNotification};
 use reth_node_api::FullNodeComponents;
 use reth_node_ethereum::EthereumNode;
-use reth_tracing::tracing::info;
+use reth_tracing::tracing::{error, info, warn};
 use std::sync::Arc;
 use std::{
-    collections::VecDeque,
+    collections::{HashSet, VecDeque},
+    path::PathBuf,
     pin::Pin,
-    task::{ready, Context, Poll},
+    task::{Context, Poll},
     time::Duration,
 };
 
+type BoxedFuture<T> = Pin<Box<dyn Future<Output = T> + Send>>;
+
+/// A range of the committed chain, acknowledged to reth once it is on DA
+struct Batch {
+    /// Tells apart batches of the same blocks queued again after a revert
+    id: u64,
+    from_block: u64,
+    to_block: u64,
+    tip: BlockNumHash,
+    /// Encoded blocks, posted even when they hold no transactions so DA carries every header
+    data: Bytes,
+    /// Failed submissions, or Nexus posts once stored, in a row
+    failures: u32,
+}
+
+/// A batch stored on DA, waiting for its commitment to be posted to Nexus
+struct StoredBatch {
+    batch: Batch,
+    commitment: B256,
+}
+
 struct DAExEx<Node: FullNodeComponents> {
     /// The context of the ExEx
     ctx: ExExContext<Node>,
-    /// Execution outcome of the chain
+    /// Client of the DA server at `api_url`, sending the auth token if one is configured
     api_client: Arc<reqwest::Client>,
     api_url: String,
-    pending_request:
-        Option<Pin<Box<dyn Future<Output = reqwest::Result<reqwest::Response>> + Send>>>,
-    data_queue: VecDeque<Bytes>,
+    /// Posts batch commitments to the Nexus chain, if configured
+    nexus_poster: Option<NexusPoster>,
+    /// Signs batches so the DA server accepts them into this chain's namespace
+    sequencer_key: Option<PrivateKeySigner>,
+    chain_id: u64,
+    batch_size: u64,
+    mode: PostingMode,
+    compression: Compression,
+    max_in_flight: usize,
+    retry_delay: Duration,
+    /// Where the last acknowledged block is persisted
+    checkpoint: CheckpointFile,
+    /// The checkpoint of the last acknowledged batch, the next one keeps its block in its
+    /// history
+    last_checkpoint: Option<Checkpoint>,
+    /// Checkpoint write off the poll path, with the tips of the batches it covers, which are
+    /// only reported finished once it is on disk
+    saving: Option<BoxedFuture<(Vec<BlockNumHash>, eyre::Result<()>)>>,
+    /// Tips of the batches acknowledged while a checkpoint was being written, covered by the
+    /// next write
+    unsaved: Vec<BlockNumHash>,
+    /// First and last block left to queue from the database before taking notifications
+    backfill: Option<(u64, u64)>,
+    /// Where each batch is at, served over RPC
+    status: Arc<RwLock<DAStatus>>,
+    /// Highest block queued for submission, blocks of notifications up to it were backfilled
+    queued_height: u64,
+    /// Id of the next batch queued
+    next_batch_id: u64,
+    /// Batches waiting for a submission slot
+    data_queue: VecDeque<Batch>,
+    /// Id, first and last block of the batches taken off the queue and not acknowledged yet,
+    /// in chain order
+    unacknowledged: VecDeque<(u64, u64, u64)>,
+    /// Submissions and posts of reverted blocks, dropped instead of acknowledged once they
+    /// complete
+    reverted: HashSet<u64>,
+    /// Submissions to the DA server, which complete in the order they were started
+    in_flight: FuturesOrdered<BoxedFuture<(Batch, eyre::Result<B256>)>>,
+    /// Batches stored on DA, in chain order
+    stored: VecDeque<StoredBatch>,
+    /// Nexus posts are sent one at a time, so commitments are recorded in chain order and
+    /// transactions from the poster key do not race for nonces
+    posting: Option<BoxedFuture<(StoredBatch, eyre::Result<()>)>>,
+}
+
+/// Submits encoded blocks to the DA server and returns their commitment
+async fn submit_batch(
+    client: Arc<reqwest::Client>,
+    url: String,
+    sequencer_key: Option<PrivateKeySigner>,
+    chain_id: u64,
+    from_block: u64,
+    to_block: u64,
+    data: Bytes,
+) -> eyre::Result<B256> {
+    let mut request = client.post(format!("{}/submit/{}", url, chain_id));
+    if let Some(key) = sequencer_key {
+        let digest = signing_digest(chain_id, &keccak256(&data));
+        let signature = key.sign_hash_sync(&digest)?;
+        request = request.header(
+            SIGNATURE_HEADER,
+            format!("0x{}", alloy::hex::encode(signature.as_bytes())),
+        );
+    }
+    let response = request
+        .body(data)
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<serde_json::Value>()
+        .await?;
+    let commitment = response["commitment"]
+        .as_str()
+        .ok_or_else(|| eyre!("DA server response is missing the commitment"))?
+        .parse::<B256>()?;
+    info!(%commitment, sequence = %response["sequence"], from_block, to_block, "Batch stored on DA");
+    Ok(commitment)
+}
+
+/// Encodes blocks with their sealed headers into a batch, or their state diffs if given
+fn make_batch(
+    id: u64,
+    blocks: &[&SealedBlockWithSenders],
+    diffs: Option<&[BlockDiff]>,
+    compression: Compression,
+) -> eyre::Result<Batch> {
+    let first = blocks.first().expect("batches hold at least one block");
+    let last = blocks.last().expect("batches hold at least one block");
+    let data = match diffs {
+        Some(diffs) => {
+            let accounts = diffs.iter().map(|diff| diff.accounts.len()).sum::<usize>();
+            info!(accounts, "Processing committed state diffs");
+            encode_diffs(diffs, compression)?
+        }
+        None => {
+            let blocks = blocks
+                .iter()
+                .map(|block| BatchBlock::from_block(block))
+                .collect::<Vec<_>>();
+            let transactions = blocks
+                .iter()
+                .map(|block| block.transactions.len())
+                .sum::<usize>();
+            info!(transactions, "Processing committed blocks");
+            encode_batch(&blocks, compression)?
+        }
+    };
+    info!(bytes = data.len(), ?compression, "Encoded batch");
+    Ok(Batch {
+        id,
+        from_block: first.number,
+        to_block: last.number,
+        tip: last.num_hash(),
+        data,
+        failures: 0,
+    })
 }
 
 impl<Node: FullNodeComponents> DAExEx<Node> {
-    /// Create a new instance of the ExEx
-    fn new(ctx: ExExContext<Node>) -> Self {
-        dotenvy::dotenv().unwrap();
+    /// Create a new instance of the ExEx, which queues the blocks committed since the last
+    /// acknowledged one before taking notifications
+    fn new(
+        ctx: ExExContext<Node>,
+        config: DAConfig,
+        status: Arc<RwLock<DAStatus>>,
+        checkpoint: CheckpointFile,
+    ) -> eyre::Result<Self> {
+        let mut headers = HeaderMap::new();
+        if let Some(token) = &config.auth_token {
+            let mut value = HeaderValue::from_str(&format!("Bearer {}", token))
+                .map_err(|_| eyre!("--da.auth-token is not a valid header value"))?;
+            value.set_sensitive(true);
+            headers.insert(AUTHORIZATION, value);
+        }
         let client = reqwest::Client::builder()
-            .timeout(Duration::from_secs(30))
-            .build()
-            .unwrap();
-        Self {
+            .timeout(config.request_timeout)
+            .default_headers(headers)
+            .build()?;
+        let chain_id = ctx.config.chain.chain_id();
+        let mut exex = Self {
             ctx,
             api_client: Arc::new(client),
-            api_url: dotenvy::var("DA_SERVER_URL").unwrap(),
-            pending_request: None,
+            api_url: config.url,
+            nexus_poster: config.nexus_poster,
+            sequencer_key: config.sequencer_key,
+            chain_id,
+            batch_size: config.batch_size,
+            mode: config.mode,
+            compression: config.compression,
+            max_in_flight: config.max_in_flight,
+            retry_delay: config.retry_delay,
+            checkpoint,
+            last_checkpoint: None,
+            saving: None,
+            unsaved: Vec::new(),
+            backfill: None,
+            status,
+            queued_height: 0,
+            next_batch_id: 0,
             data_queue: VecDeque::new(),
+            unacknowledged: VecDeque::new(),
+            reverted: HashSet::new(),
+            in_flight: FuturesOrdered::new(),
+            stored: VecDeque::new(),
+            posting: None,
+        };
+        exex.start_backfill()?;
+        Ok(exex)
+    }
+
+    /// Finds the blocks between the last acknowledged one still on the canonical chain and the
+    /// database tip, which `poll` queues before taking notifications. Without a checkpoint, as
+    /// when DA was disabled until now, every block since genesis is submitted
+    fn start_backfill(&mut self) -> eyre::Result<()> {
+        let Some(checkpoint) = self.checkpoint.load()? else {
+            info!(path = %self.checkpoint.path().display(), "No DA checkpoint, starting from genesis");
+            return self.backfill_from(0);
+        };
+        let provider = self.ctx.provider().clone();
+        // blocks acknowledged on a fork reorged out while the node was down are replaced from
+        // where the chain forked off, as `rewind` does for live reorgs
+        let canonical = checkpoint
+            .last_canonical(|number, hash| Ok(provider.block_hash(number)? == Some(hash)))?;
+        let resume_from = match &canonical {
+            Some(canonical) if canonical.number == checkpoint.number => checkpoint.number,
+            Some(canonical) => {
+                warn!(number = checkpoint.number, hash = %checkpoint.hash, fork_point = canonical.number, "DA checkpoint is not on the canonical chain, resuming from the fork point");
+                canonical.number
+            }
+            None => {
+                warn!(number = checkpoint.number, hash = %checkpoint.hash, "No acknowledged block is on the canonical chain, resuming from genesis");
+                0
+            }
+        };
+        self.last_checkpoint = canonical;
+        self.status.write().start_from(resume_from);
+        self.backfill_from(resume_from)
+    }
+
+    /// Backfills the blocks after `resume_from` up to the database tip
+    fn backfill_from(&mut self, resume_from: u64) -> eyre::Result<()> {
+        self.queued_height = resume_from;
+        let tip = self.ctx.provider().best_block_number()?;
+        if tip <= resume_from {
+            return Ok(());
+        }
+        info!(
+            from_block = resume_from + 1,
+            to_block = tip,
+            mode = ?self.mode,
+            "Backfilling blocks that were not acknowledged by DA"
+        );
+        self.backfill = Some((resume_from + 1, tip));
+        Ok(())
+    }
+
+    /// Queues backfilled blocks a batch at a time while fewer than `max_in_flight` batches wait
+    /// for a submission slot, so a long outage is not read into memory at once
+    fn backfill_next(&mut self) -> eyre::Result<()> {
+        while let Some((from_block, to_block)) = self.backfill {
+            if self.data_queue.len() >= self.max_in_flight {
+                break;
+            }
+            let chunk_to_block = (from_block + self.batch_size - 1).min(to_block);
+            self.queue_range(from_block, chunk_to_block)?;
+            self.backfill = (chunk_to_block < to_block).then_some((chunk_to_block + 1, to_block));
+            if self.backfill.is_none() {
+                info!(to_block, "Backfill complete, taking notifications");
+            }
+        }
+        Ok(())
+    }
+
+    /// Queues canonical blocks `from_block..=to_block` from the database
+    fn queue_range(&mut self, from_block: u64, to_block: u64) -> eyre::Result<()> {
+        let provider = self.ctx.provider().clone();
+        match self.mode {
+            PostingMode::Blocks => {
+                let mut from_block = from_block;
+                while from_block <= to_block {
+                    let batch_to_block = (from_block + self.batch_size - 1).min(to_block);
+                    let blocks = (from_block..=batch_to_block)
+                        .map(|number| {
+                            provider
+                                .sealed_block_with_senders(
+                                    number.into(),
+                                    TransactionVariant::WithHash,
+                                )?
+                                .ok_or_else(|| {
+                                    eyre!("Block {} is missing from the database", number)
+                                })
+                        })
+                        .collect::<eyre::Result<Vec<_>>>()?;
+                    self.queue_blocks(&blocks.iter().collect::<Vec<_>>(), None)?;
+                    from_block = batch_to_block + 1;
+                }
+            }
+            PostingMode::StateDiffs => {
+                // the database only keeps the latest state, so the changes of each block are
+                // recovered by executing the blocks again
+                let job = BackfillJobFactory::new(self.ctx.block_executor().clone(), provider)
+                    .backfill(from_block..=to_block);
+                for chain in job {
+                    let chain = chain?;
+                    let blocks = chain.blocks().values().collect::<Vec<_>>();
+                    self.queue_blocks(&blocks, Some(chain.execution_outcome()))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Submits a batch to the DA server after `delay`
+    fn submission(
+        &self,
+        batch: Batch,
+        delay: Duration,
+    ) -> BoxedFuture<(Batch, eyre::Result<B256>)> {
+        let client = Arc::clone(&self.api_client);
+        let url = self.api_url.clone();
+        let sequencer_key = self.sequencer_key.clone();
+        let chain_id = self.chain_id;
+        Box::pin(async move {
+            tokio::time::sleep(delay).await;
+            let result = submit_batch(
+                client,
+                url,
+                sequencer_key,
+                chain_id,
+                batch.from_block,
+                batch.to_block,
+                batch.data.clone(),
+            )
+            .await;
+            (batch, result)
+        })
+    }
+
+    /// Posts the commitment of a stored batch to the Nexus chain after `delay`
+    fn post(
+        &self,
+        poster: NexusPoster,
+        stored: StoredBatch,
+        delay: Duration,
+    ) -> BoxedFuture<(StoredBatch, eyre::Result<()>)> {
+        let chain_id = self.chain_id;
+        let commitment = stored.commitment;
+        Box::pin(async move {
+            tokio::time::sleep(delay).await;
+            let result = poster
+                .post(
+                    chain_id,
+                    stored.batch.from_block,
+                    stored.batch.to_block,
+                    commitment,
+                )
+                .await
+                .map(|tx_hash| info!(%tx_hash, %commitment, "Posted DA commitment to Nexus"));
+            (stored, result)
+        })
+    }
+
+    /// Queues the blocks of a committed chain for submission, after dropping the queued blocks
+    /// of a reverted one
+    fn queue_chain(&mut self, notification: &ExExNotification) -> eyre::Result<()> {
+        if let Some(reverted_chain) = notification.reverted_chain() {
+            self.rewind(reverted_chain.first().number.saturating_sub(1))?;
+        }
+        let Some(committed_chain) = notification.committed_chain() else {
+            return Ok(());
+        };
+        let blocks = committed_chain.blocks().values().collect::<Vec<_>>();
+        self.queue_blocks(&blocks, Some(committed_chain.execution_outcome()))
+    }
+
+    /// Drops every batch holding blocks after `number` that is not acknowledged yet, so the
+    /// blocks replacing them are queued and reverted blocks are never acknowledged
+    ///
+    /// Queued and stored batches are dropped right away, submissions and Nexus posts in flight
+    /// once they complete. Batches already submitted stay on DA, readers find the canonical
+    /// blocks in the batches posted after them.
+    fn rewind(&mut self, number: u64) -> eyre::Result<()> {
+        let mut dropped_from = None;
+        while let Some(batch) = self.data_queue.back() {
+            if batch.to_block <= number {
+                break;
+            }
+            dropped_from = Some(batch.from_block);
+            self.status.write().unqueued(batch.from_block);
+            self.data_queue.pop_back();
+        }
+        while let Some(&(id, from_block, to_block)) = self.unacknowledged.back() {
+            if to_block <= number {
+                break;
+            }
+            dropped_from = Some(from_block);
+            self.status.write().unqueued(from_block);
+            self.unacknowledged.pop_back();
+            match self.stored.iter().position(|stored| stored.batch.id == id) {
+                Some(position) => {
+                    self.stored.remove(position);
+                }
+                None => {
+                    self.reverted.insert(id);
+                }
+            }
+            info!(from_block, to_block, "Dropped batch of reverted blocks");
         }
+        self.queued_height = self.queued_height.min(number);
+        // a dropped batch may also have held blocks that stay canonical
+        if let Some(from_block) = dropped_from.filter(|from_block| *from_block <= number) {
+            self.queued_height = from_block - 1;
+            self.queue_range(from_block, number)?;
+        }
+        Ok(())
     }
 
-    /// Try to start a new request if there isn't one pending
-    fn try_start_request(&mut self) {
-        if self.pending_request.is_none() && !self.data_queue.is_empty() {
-            if let Some(data) = self.data_queue.pop_front() {
-                let future = Box::pin(self.api_client.post(&self.api_url).body(data).send());
-                self.pending_request = Some(future);
+    /// Queues consecutive blocks for submission in batches of at most `batch_size` blocks,
+    /// skipping those already queued. Posting state diffs needs the execution outcome of
+    /// exactly these blocks.
+    fn queue_blocks(
+        &mut self,
+        blocks: &[&SealedBlockWithSenders],
+        outcome: Option<&ExecutionOutcome>,
+    ) -> eyre::Result<()> {
+        let diffs = match (self.mode, outcome) {
+            (PostingMode::Blocks, _) => None,
+            (PostingMode::StateDiffs, Some(outcome)) => Some(state_diffs(blocks, outcome)?),
+            (PostingMode::StateDiffs, None) => {
+                return Err(eyre!(
+                    "State diffs need the execution outcome of the blocks"
+                ))
             }
+        };
+        let skip = blocks
+            .iter()
+            .take_while(|block| block.number <= self.queued_height)
+            .count();
+        let batch_size = self.batch_size as usize;
+        for start in (skip..blocks.len()).step_by(batch_size) {
+            let end = (start + batch_size).min(blocks.len());
+            let id = self.next_batch_id;
+            self.next_batch_id += 1;
+            let batch = make_batch(
+                id,
+                &blocks[start..end],
+                diffs.as_deref().map(|diffs| &diffs[start..end]),
+                self.compression,
+            )?;
+            self.queued_height = batch.to_block;
+            self.status.write().queued(batch.from_block, batch.to_block);
+            self.data_queue.push_back(batch);
+        }
+        Ok(())
+    }
+
+    /// Tells reth everything up to the batch is on DA, so it may prune it
+    fn acknowledge(&mut self, batch: &Batch) -> eyre::Result<()> {
+        self.unacknowledged.retain(|(id, _, _)| *id != batch.id);
+        let checkpoint = Checkpoint::advance(
+            self.last_checkpoint.take(),
+            batch.tip.number,
+            batch.tip.hash,
+        );
+        self.last_checkpoint = Some(checkpoint);
+        self.unsaved.push(batch.tip);
+        self.status
+            .write()
+            .acknowledged(batch.from_block, batch.to_block);
+        info!(
+            from_block = batch.from_block,
+            to_block = batch.to_block,
+            "Acknowledged batch"
+        );
+        Ok(())
+    }
+
+    /// Writes the last checkpoint on a blocking thread, one write at a time, and reports the
+    /// batches it covers finished once it is on disk
+    fn poll_saving(&mut self, cx: &mut Context<'_>) -> eyre::Result<()> {
+        loop {
+            if let Some(saving) = self.saving.as_mut() {
+                let Poll::Ready((tips, result)) = saving.as_mut().poll(cx) else {
+                    return Ok(());
+                };
+                self.saving = None;
+                result?;
+                for tip in tips {
+                    self.ctx.events.send(ExExEvent::FinishedHeight(tip))?;
+                }
+            }
+            let Some(checkpoint) = self.last_checkpoint.clone() else {
+                return Ok(());
+            };
+            if self.unsaved.is_empty() {
+                return Ok(());
+            }
+            let tips = std::mem::take(&mut self.unsaved);
+            let file = self.checkpoint.clone();
+            self.saving = Some(Box::pin(async move {
+                let result = tokio::task::spawn_blocking(move || file.save(&checkpoint))
+                    .await
+                    .map_err(eyre::Error::from)
+                    .and_then(|result| result);
+                (tips, result)
+            }));
+        }
+    }
+
+    /// Posts stored batches to Nexus and acknowledges them, strictly in chain order
+    fn poll_posting(&mut self, cx: &mut Context<'_>) -> eyre::Result<()> {
+        loop {
+            if let Some(posting) = self.posting.as_mut() {
+                let Poll::Ready((mut stored, result)) = posting.as_mut().poll(cx) else {
+                    return Ok(());
+                };
+                self.posting = None;
+                if self.reverted.remove(&stored.batch.id) {
+                    continue;
+                }
+                match result {
+                    Ok(()) => self.acknowledge(&stored.batch)?,
+                    Err(e) => {
+                        let delay = self.failed(&mut stored.batch, "Nexus post", e);
+                        self.start_posting(stored, delay)?;
+                        continue;
+                    }
+                }
+            }
+            let Some(stored) = self.stored.pop_front() else {
+                return Ok(());
+            };
+            self.start_posting(stored, Duration::ZERO)?;
+        }
+    }
+
+    /// Records a failed submission or post of a batch and returns how long to back off before
+    /// retrying it, logging an error once it keeps failing
+    fn failed(&self, batch: &mut Batch, attempt: &str, e: eyre::Report) -> Duration {
+        batch.failures += 1;
+        let delay = backoff(self.retry_delay, batch.failures);
+        self.status
+            .write()
+            .failed(batch.from_block, batch.failures, format!("{:?}", e));
+        if batch.failures >= PERSISTENT_FAILURES {
+            error!(
+                from_block = batch.from_block,
+                to_block = batch.to_block,
+                failures = batch.failures,
+                ?delay,
+                "{} keeps failing, retrying: {:?}",
+                attempt,
+                e
+            );
+        } else {
+            warn!(
+                from_block = batch.from_block,
+                to_block = batch.to_block,
+                failures = batch.failures,
+                ?delay,
+                "{} failed, retrying: {:?}",
+                attempt,
+                e
+            );
+        }
+        delay
+    }
+
+    /// Starts posting a stored batch, or acknowledges it right away if Nexus posting is off
+    fn start_posting(&mut self, stored: StoredBatch, delay: Duration) -> eyre::Result<()> {
+        match self.nexus_poster.clone() {
+            Some(poster) => {
+                self.posting = Some(self.post(poster, stored, delay));
+                Ok(())
+            }
+            None => self.acknowledge(&stored.batch),
         }
     }
 }
@@ -61,82 +619,751 @@ impl<Node: FullNodeComponents + Unpin> Future for DAExEx<Node> {
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         let this = self.get_mut();
 
-        if let Some(request) = this.pending_request.as_mut() {
-            match request.as_mut().poll(cx) {
-                Poll::Ready(Ok(_)) => {
-                    info!("Request sent successfully");
-                    this.pending_request = None;
-                    // Try to start the next request if there's data in the queue
-                    this.try_start_request();
+        loop {
+            if let Err(e) = this.backfill_next() {
+                return Poll::Ready(Err(e));
+            }
+
+            // notifications wait in the channel until the backfill is queued, as they would
+            // otherwise be queued ahead of the blocks before them
+            while this.backfill.is_none() {
+                let Poll::Ready(notification) = this.ctx.notifications.try_next().poll_unpin(cx)
+                else {
+                    break;
+                };
+                let Some(notification) = notification? else {
+                    return Poll::Ready(Ok(()));
+                };
+                match &notification {
+                    ExExNotification::ChainCommitted { new } => {
+                        info!(committed_chain = ?new.range(), "Received commit");
+                    }
+                    ExExNotification::ChainReorged { old, new } => {
+                        info!(from_chain = ?old.range(), to_chain = ?new.range(), "Received reorg");
+                    }
+                    ExExNotification::ChainReverted { old } => {
+                        info!(reverted_chain = ?old.range(), "Received revert");
+                    }
+                };
+                if let Err(e) = this.queue_chain(&notification) {
+                    return Poll::Ready(Err(e));
+                }
+            }
+
+            while this.in_flight.len() < this.max_in_flight {
+                let Some(batch) = this.data_queue.pop_front() else {
+                    break;
+                };
+                this.unacknowledged
+                    .push_back((batch.id, batch.from_block, batch.to_block));
+                let submission = this.submission(batch, Duration::ZERO);
+                this.in_flight.push_back(submission);
+            }
+
+            // submissions complete in order, so a failed one holds back those behind it until
+            // its retry succeeds
+            let mut completed = false;
+            while let Poll::Ready(Some((mut batch, result))) = this.in_flight.poll_next_unpin(cx) {
+                if this.reverted.remove(&batch.id) {
+                    completed = true;
+                    continue;
                 }
-                Poll::Ready(Err(e)) => {
-                    info!("Request failed: {:?}", e);
-                    // On error, put the data back in the queue to retry
-                    // You might want to add retry limits and error handling here
-                    this.pending_request = None;
-                    this.try_start_request();
+                match result {
+                    Ok(commitment) => {
+                        completed = true;
+                        batch.failures = 0;
+                        this.status.write().stored(batch.from_block, commitment);
+                        this.stored.push_back(StoredBatch { batch, commitment });
+                    }
+                    Err(e) => {
+                        let delay = this.failed(&mut batch, "DA submission", e);
+                        let retry = this.submission(batch, delay);
+                        this.in_flight.push_front(retry);
+                    }
                 }
-                Poll::Pending => {}
+            }
+
+            if let Err(e) = this.poll_posting(cx) {
+                return Poll::Ready(Err(e));
+            }
+            if let Err(e) = this.poll_saving(cx) {
+                return Poll::Ready(Err(e));
+            }
+
+            // completed submissions free slots for queued and backfilled batches
+            if !completed || (this.data_queue.is_empty() && this.backfill.is_none()) {
+                return Poll::Pending;
             }
         }
+    }
+}
 
-        while let Some(notification) = ready!(this.ctx.notifications.try_next().poll_unpin(cx))? {
-            match &notification {
-                ExExNotification::ChainCommitted { new } => {
-                    info!(committed_chain = ?new.range(), "Received commit");
-                }
-                ExExNotification::ChainReorged { old, new } => {
-                    info!(from_chain = ?old.range(), to_chain = ?new.range(), "Received reorg");
+/// Blocks between snapshots of the order index
+const ORDER_SNAPSHOT_INTERVAL: u64 = 1000;
+
+/// Keeps the order and fill trees of the rollup's settler in step with the canonical chain,
+/// relaying their roots if configured
+///
+/// The trees live in memory and are snapshotted every `ORDER_SNAPSHOT_INTERVAL` blocks, so on
+/// start only the blocks after the last snapshot are read from the database. Since those can
+/// always be read again, blocks are acknowledged as soon as they are indexed.
+async fn order_tree_exex<Node: FullNodeComponents>(
+    mut ctx: ExExContext<Node>,
+    index: Arc<RwLock<OrderIndex>>,
+    settler: Address,
+    relay: Option<RelayConfig>,
+    snapshots: CheckpointFile<OrderSnapshot>,
+) -> eyre::Result<()> {
+    let mut publisher = relay.map(|config| {
+        let (publisher, roots) = RootsPublisher::new(config.confirmations);
+        let relayer = Relayer::new(
+            ctx.config.chain.chain_id(),
+            config.signer,
+            config.interval,
+            config.retry_delay,
+        );
+        info!(
+            targets = config.targets.len(),
+            confirmations = config.confirmations,
+            "Relaying rollup roots"
+        );
+        tokio::spawn(relayer.run(config.targets, roots));
+        publisher
+    });
+    let roots = |index: &OrderIndex, block: &SealedBlockWithSenders| RollupRoots {
+        block_number: block.number,
+        state_root: block.state_root,
+        order_root: index.order_root(),
+        fill_root: index.fill_root(),
+    };
+
+    let provider = ctx.provider().clone();
+    let tip = provider.best_block_number()?;
+    let mut checkpoint = None;
+    if let Some(snapshot) = snapshots.load()? {
+        // a snapshot reorged out while the node was down is rewound like a live reorg
+        match snapshot
+            .checkpoint
+            .last_canonical(|number, hash| Ok(provider.block_hash(number)? == Some(hash)))?
+        {
+            Some(canonical) => {
+                let mut restored = OrderIndex::restore(snapshot)?;
+                restored.revert_to(canonical.number);
+                *index.write() = restored;
+                checkpoint = Some(canonical);
+            }
+            None => {
+                warn!(path = %snapshots.path().display(), "No snapshotted block is on the canonical chain, indexing from genesis");
+            }
+        }
+    }
+    // the publisher confirms the tip's roots from the blocks before it, which are read again
+    // even if snapshotted, while older roots are never published
+    if let Some(publisher) = publisher.as_ref() {
+        let published_from = tip.saturating_sub(publisher.confirmations());
+        index.write().revert_to(published_from.saturating_sub(1));
+    }
+    let from_block = index.read().height() + 1;
+    for number in from_block..=tip {
+        let block = provider
+            .sealed_block_with_senders(number.into(), TransactionVariant::WithHash)?
+            .ok_or_else(|| eyre!("Block {} is missing from the database", number))?;
+        let receipts = provider
+            .receipts_by_block(number.into())?
+            .ok_or_else(|| eyre!("Receipts of block {} are missing from the database", number))?;
+        let orders = block_orders(&block, receipts.iter().map(Some), settler);
+        let mut index = index.write();
+        index.apply(number, block.timestamp, orders)?;
+        if let Some(publisher) = publisher.as_mut() {
+            if number + publisher.confirmations() >= tip {
+                publisher.push(roots(&index, &block));
+            }
+        }
+    }
+    {
+        let index = index.read();
+        info!(
+            %settler,
+            from_block,
+            to_block = tip,
+            orders = index.order_count(),
+            fills = index.fill_count(),
+            "Indexed settler orders"
+        );
+    }
+    if tip >= from_block + ORDER_SNAPSHOT_INTERVAL {
+        let hash = provider
+            .block_hash(tip)?
+            .ok_or_else(|| eyre!("Block {} is missing from the database", tip))?;
+        save_order_snapshot(
+            &snapshots,
+            &index,
+            &mut checkpoint,
+            BlockNumHash::new(tip, hash),
+        )
+        .await?;
+    }
+
+    while let Some(notification) = ctx.notifications.try_next().await? {
+        if let Some(reverted_chain) = notification.reverted_chain() {
+            let number = reverted_chain.first().number.saturating_sub(1);
+            index.write().revert_to(number);
+            if let Some(publisher) = publisher.as_mut() {
+                publisher.revert_to(number);
+            }
+            info!(reverted_chain = ?reverted_chain.range(), "Reverted settler orders");
+        }
+        let Some(committed_chain) = notification.committed_chain() else {
+            continue;
+        };
+        {
+            let mut index = index.write();
+            for (block, receipts) in committed_chain.blocks_and_receipts() {
+                // blocks already read from the database on start
+                if block.number <= index.height() {
+                    continue;
                 }
-                ExExNotification::ChainReverted { old } => {
-                    info!(reverted_chain = ?old.range(), "Received revert");
+                let orders = block_orders(block, receipts.iter().map(Option::as_ref), settler);
+                let (sent, filled) = (orders.sent.len(), orders.filled.len());
+                index.apply(block.number, block.timestamp, orders)?;
+                if sent > 0 || filled > 0 {
+                    info!(block_number = block.number, sent, filled, order_root = %index.order_root(), "Indexed settler orders");
                 }
-            };
-
-            if let Some(committed_chain) = notification.committed_chain() {
-                // extend the state with the new chain
-                let transactions = committed_chain
-                    .blocks()
-                    .values()
-                    .flat_map(|block| block.body.transactions())
-                    .collect::<Vec<_>>();
-                if !transactions.is_empty() {
-                    info!(
-                        transactions = transactions.len(),
-                        "Processing committed blocks"
-                    );
-                    let mut bytes_arr: BytesMut = BytesMut::new();
-                    for tx in transactions {
-                        let mut bytes = BytesMut::new();
-                        tx.encode(&mut bytes);
-                        bytes_arr.extend_from_slice(&bytes[..]);
-                    }
-                    // log bytes_arr using info!()
-                    info!(bytes_arr = ?bytes_arr, "Bytes array");
-                    let bytes = Bytes::from(bytes_arr);
-                    this.data_queue.push_back(bytes);
-                    // Try to start a new request if possible
-                    this.try_start_request();
+                if let Some(publisher) = publisher.as_mut() {
+                    publisher.push(roots(&index, block));
                 }
-                info!(first_block = %committed_chain.execution_outcome().first_block, "Processed committed blocks");
-                this.ctx
-                    .events
-                    .send(ExExEvent::FinishedHeight(committed_chain.tip().num_hash()))?;
             }
         }
+        let tip = committed_chain.tip().num_hash();
+        let snapshotted = checkpoint
+            .as_ref()
+            .map_or(0, |checkpoint| checkpoint.number);
+        if tip.number >= snapshotted + ORDER_SNAPSHOT_INTERVAL {
+            save_order_snapshot(&snapshots, &index, &mut checkpoint, tip).await?;
+        }
+        ctx.events.send(ExExEvent::FinishedHeight(tip))?;
+    }
+    Ok(())
+}
+
+/// Snapshots the order index at its tip, writing it on a blocking thread since it holds every
+/// leaf
+async fn save_order_snapshot(
+    file: &CheckpointFile<OrderSnapshot>,
+    index: &RwLock<OrderIndex>,
+    checkpoint: &mut Option<Checkpoint>,
+    tip: BlockNumHash,
+) -> eyre::Result<()> {
+    let next = Checkpoint::advance(checkpoint.clone(), tip.number, tip.hash);
+    let snapshot = index.read().snapshot(next.clone());
+    let file = file.clone();
+    tokio::task::spawn_blocking(move || file.save(&snapshot)).await??;
+    *checkpoint = Some(next);
+    Ok(())
+}
+
+/// Feeds what `NexusSettler` records on this chain to a verifier replaying the rollups
+///
+/// Verification runs in its own task, so blocks are acknowledged as soon as their events are
+/// handed over. The verifier snapshots itself every so many Nexus blocks, and on start the
+/// blocks after its snapshot are read from the database first.
+async fn root_verifier_exex<Node: FullNodeComponents>(
+    mut ctx: ExExContext<Node>,
+    config: VerifyConfig,
+    alerts: PathBuf,
+    snapshots: CheckpointFile<VerifierSnapshot>,
+) -> eyre::Result<()> {
+    let settler = config.settler;
+    let provider = ctx.provider().clone();
+    let snapshot = match snapshots.load()? {
+        Some(snapshot)
+            if provider.block_hash(snapshot.nexus_block)? == Some(snapshot.nexus_hash) =>
+        {
+            Some(snapshot)
+        }
+        Some(snapshot) => {
+            warn!(nexus_block = snapshot.nexus_block, hash = %snapshot.nexus_hash, "Verifier snapshot is not on the canonical chain, replaying every batch");
+            None
+        }
+        None => None,
+    };
+    let verifier = Verifier::new(config, alerts, snapshots, snapshot);
+    let from_block = verifier.resumed_from() + 1;
+    info!(%settler, rollups = ?verifier.chain_ids(), from_block, "Verifying rollup state roots");
+    let (events, receiver) = tokio::sync::mpsc::unbounded_channel();
+    tokio::spawn(verifier.run(receiver));
 
-        Poll::Ready(Ok(()))
+    let mut height = provider.best_block_number()?;
+    for number in from_block..=height {
+        let block = provider
+            .sealed_block_with_senders(number.into(), TransactionVariant::WithHash)?
+            .ok_or_else(|| eyre!("Block {} is missing from the database", number))?;
+        let receipts = provider
+            .receipts_by_block(number.into())?
+            .ok_or_else(|| eyre!("Receipts of block {} are missing from the database", number))?;
+        for event in nexus_events(&block, receipts.iter().map(Some), settler) {
+            events.send(event)?;
+        }
+        if number == height {
+            events.send(NexusEvent::Handled(block.num_hash()))?;
+        }
+    }
+
+    while let Some(notification) = ctx.notifications.try_next().await? {
+        if let Some(reverted_chain) = notification.reverted_chain() {
+            height = reverted_chain.first().number.saturating_sub(1);
+            events.send(NexusEvent::Reverted(height))?;
+        }
+        let Some(committed_chain) = notification.committed_chain() else {
+            continue;
+        };
+        for (block, receipts) in committed_chain.blocks_and_receipts() {
+            // blocks already read from the database on start
+            if block.number <= height {
+                continue;
+            }
+            for event in nexus_events(block, receipts.iter().map(Option::as_ref), settler) {
+                events.send(event)?;
+            }
+        }
+        height = height.max(committed_chain.tip().number);
+        events.send(NexusEvent::Handled(committed_chain.tip().num_hash()))?;
+        ctx.events
+            .send(ExExEvent::FinishedHeight(committed_chain.tip().num_hash()))?;
     }
+    Ok(())
 }
 
 fn main() -> eyre::Result<()> {
-    reth::cli::Cli::parse_args().run(|builder, _| async move {
-        let handle = builder
-            .node(EthereumNode::default())
-            .install_exex("DA", |ctx| async move { Ok(DAExEx::new(ctx)) })
-            .launch()
+    reth::cli::Cli::<EthereumChainSpecParser, NodeArgs>::parse_args().run(
+        |builder, args| async move {
+            // fail before the node starts rather than when the ExEx is first polled
+            let config = args.da.validate()?;
+            if config.is_none() {
+                warn!("DA submission is disabled");
+            }
+            let settler = args.orders.settler;
+            let relay = args.relay.validate()?;
+            if relay.is_some() && settler.is_none() {
+                return Err(eyre!("Relaying roots needs --orders.settler"));
+            }
+            let verify = args.verify.validate()?;
+            let index = Arc::new(RwLock::new(OrderIndex::default()));
+            let da_status = Arc::new(RwLock::new(DAStatus::default()));
+            let rpc = ReimannRpc::new(
+                settler.is_some().then(|| Arc::clone(&index)),
+                config.is_some().then(|| Arc::clone(&da_status)),
+            );
+            let handle = builder
+                .node(EthereumNode::default())
+                .install_exex_if(config.is_some(), "DA", |ctx| async move {
+                    let checkpoint =
+                        CheckpointFile::new(ctx.config.datadir().data_dir().join("da-exex.json"));
+                    DAExEx::new(
+                        ctx,
+                        config.expect("installed with a DA config"),
+                        da_status,
+                        checkpoint,
+                    )
+                })
+                .install_exex_if(settler.is_some(), "OrderTree", move |ctx| async move {
+                    let settler = settler.expect("installed with a settler");
+                    let snapshots = CheckpointFile::new(
+                        ctx.config.datadir().data_dir().join("order-index.json"),
+                    );
+                    Ok(order_tree_exex(ctx, index, settler, relay, snapshots))
+                })
+                .install_exex_if(verify.is_some(), "RootVerifier", move |ctx| async move {
+                    let config = verify.expect("installed with a verifier config");
+                    let alerts = ctx.config.datadir().data_dir().join("verify-alerts.jsonl");
+                    let snapshots =
+                        CheckpointFile::new(ctx.config.datadir().data_dir().join("verifier.json"));
+                    Ok(root_verifier_exex(ctx, config, alerts, snapshots))
+                })
+                .extend_rpc_modules(move |ctx| {
+                    ctx.modules.merge_configured(rpc.into_rpc())?;
+                    Ok(())
+                })
+                .launch()
+                .await?;
+
+            handle.wait_for_node_exit().await
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        extract::{Path, State},
+        http::{HeaderMap as RequestHeaders, StatusCode},
+        response::{IntoResponse, Response},
+        routing::post,
+        Json, Router,
+    };
+    use da_exex::batch::decode_batch;
+    use parking_lot::Mutex;
+    use reth_execution_types::Chain;
+    use reth_exex_test_utils::test_exex_context;
+    use reth_primitives::{
+        proofs::{calculate_transaction_root, calculate_withdrawals_root},
+        BlockBody, Header, SealedBlock, SealedHeader, TransactionSigned,
+    };
+    use tokio::sync::{mpsc::UnboundedReceiver, Semaphore};
+
+    /// A batch accepted by the mock DA server
+    struct Submitted {
+        chain_id: u64,
+        authorization: Option<String>,
+        data: Bytes,
+    }
+
+    /// Stands in for `da-server`, failing the first `failures` submissions
+    #[derive(Default)]
+    struct MockDA {
+        failures: usize,
+        attempts: usize,
+        submitted: Vec<Submitted>,
+        /// Holds every submission until a permit is added, if set
+        gate: Option<Arc<Semaphore>>,
+    }
+
+    async fn submit(
+        State(mock): State<Arc<Mutex<MockDA>>>,
+        Path(chain_id): Path<u64>,
+        headers: RequestHeaders,
+        data: Bytes,
+    ) -> Response {
+        let gate = {
+            let mut mock = mock.lock();
+            mock.attempts += 1;
+            mock.gate.clone()
+        };
+        if let Some(gate) = gate {
+            gate.acquire().await.unwrap().forget();
+        }
+        let mut mock = mock.lock();
+        if mock.failures > 0 {
+            mock.failures -= 1;
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to store data").into_response();
+        }
+        mock.submitted.push(Submitted {
+            chain_id,
+            authorization: headers
+                .get(AUTHORIZATION)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string),
+            data: data.clone(),
+        });
+        Json(serde_json::json!({
+            "commitment": keccak256(&data),
+            "sequence": mock.submitted.len(),
+        }))
+        .into_response()
+    }
+
+    async fn spawn_mock_da(failures: usize) -> (String, Arc<Mutex<MockDA>>) {
+        let mock = Arc::new(Mutex::new(MockDA {
+            failures,
+            ..Default::default()
+        }));
+        let app = Router::new()
+            .route("/submit/:chain_id", post(submit))
+            .with_state(Arc::clone(&mock));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (url, mock)
+    }
+
+    fn config(url: String, batch_size: u64) -> DAConfig {
+        DAConfig {
+            url,
+            auth_token: Some("secret".to_string()),
+            batch_size,
+            request_timeout: Duration::from_secs(5),
+            retry_delay: Duration::from_millis(10),
+            max_in_flight: 2,
+            mode: PostingMode::Blocks,
+            compression: Compression::None,
+            sequencer_key: None,
+            nexus_poster: None,
+        }
+    }
+
+    fn checkpoint(name: &str) -> CheckpointFile {
+        let dir = std::env::temp_dir().join(format!("da-exex-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        CheckpointFile::new(dir.join("da-exex.json"))
+    }
+
+    /// Builds empty blocks on top of `parent`, forks built with another `fork` byte get other
+    /// hashes
+    fn blocks(
+        parent: &SealedBlockWithSenders,
+        count: u64,
+        fork: u8,
+    ) -> Vec<SealedBlockWithSenders> {
+        let transactions: Vec<TransactionSigned> = Vec::new();
+        let mut parent = parent.num_hash();
+        (0..count)
+            .map(|_| {
+                let header = Header {
+                    parent_hash: parent.hash,
+                    number: parent.number + 1,
+                    timestamp: 1_700_000_000 + parent.number + 1,
+                    gas_limit: 1_000_000_000,
+                    base_fee_per_gas: Some(7),
+                    extra_data: vec![fork].into(),
+                    transactions_root: calculate_transaction_root(&transactions),
+                    withdrawals_root: Some(calculate_withdrawals_root(&[])),
+                    ..Default::default()
+                };
+                let hash = header.hash_slow();
+                let body = BlockBody {
+                    withdrawals: Some(Default::default()),
+                    ..Default::default()
+                };
+                let block = SealedBlock::new(SealedHeader::new(header, hash), body);
+                let block = SealedBlockWithSenders::new(block, Vec::new()).unwrap();
+                parent = block.num_hash();
+                block
+            })
+            .collect()
+    }
+
+    fn chain(blocks: &[SealedBlockWithSenders]) -> Chain {
+        Chain::new(blocks.to_vec(), ExecutionOutcome::default(), None)
+    }
+
+    async fn next_finished_height(events: &mut UnboundedReceiver<ExExEvent>) -> BlockNumHash {
+        let event = tokio::time::timeout(Duration::from_secs(10), events.recv())
+            .await
+            .expect("no FinishedHeight event in time")
+            .expect("events channel closed");
+        let ExExEvent::FinishedHeight(height) = event;
+        height
+    }
+
+    /// Numbers and hashes of the blocks in each submitted batch
+    fn submitted_blocks(mock: &Mutex<MockDA>) -> Vec<Vec<BlockNumHash>> {
+        mock.lock()
+            .submitted
+            .iter()
+            .map(|submitted| {
+                decode_batch(&submitted.data)
+                    .unwrap()
+                    .iter()
+                    .map(|block| BlockNumHash::new(block.number(), block.hash))
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn num_hashes(blocks: &[SealedBlockWithSenders]) -> Vec<BlockNumHash> {
+        blocks.iter().map(|block| block.num_hash()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_submits_batches_in_order() -> eyre::Result<()> {
+        let (url, mock) = spawn_mock_da(0).await;
+        let (ctx, mut handle) = test_exex_context().await?;
+        let chain_id = ctx.config.chain.chain_id();
+        let checkpoint = checkpoint("batches");
+        let status = Arc::new(RwLock::new(DAStatus::default()));
+        let exex = DAExEx::new(
+            ctx,
+            config(url, 2),
+            Arc::clone(&status),
+            CheckpointFile::new(checkpoint.path()),
+        )?;
+        tokio::spawn(exex);
+
+        let blocks = blocks(&handle.genesis, 3, 0);
+        handle
+            .send_notification_chain_committed(chain(&blocks))
             .await?;
+        assert_eq!(
+            next_finished_height(&mut handle.events_rx).await,
+            blocks[1].num_hash()
+        );
+        assert_eq!(
+            next_finished_height(&mut handle.events_rx).await,
+            blocks[2].num_hash()
+        );
 
-        handle.wait_for_node_exit().await
-    })
+        assert_eq!(
+            submitted_blocks(&mock),
+            vec![num_hashes(&blocks[..2]), num_hashes(&blocks[2..])]
+        );
+        for submitted in &mock.lock().submitted {
+            assert_eq!(submitted.chain_id, chain_id);
+            assert_eq!(submitted.authorization.as_deref(), Some("Bearer secret"));
+        }
+        assert_eq!(
+            checkpoint.load()?,
+            Some(Checkpoint::advance(
+                Some(Checkpoint::advance(None, 2, blocks[1].hash())),
+                3,
+                blocks[2].hash()
+            ))
+        );
+        let block = status.read().block(3).unwrap();
+        assert_eq!(block.batch.unwrap().from_block, 3);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_retries_failed_submissions() -> eyre::Result<()> {
+        // both batches in flight fail once, the retries must still land in chain order
+        let (url, mock) = spawn_mock_da(2).await;
+        let (ctx, mut handle) = test_exex_context().await?;
+        let exex = DAExEx::new(
+            ctx,
+            config(url, 1),
+            Arc::new(RwLock::new(DAStatus::default())),
+            checkpoint("retries"),
+        )?;
+        tokio::spawn(exex);
+
+        let blocks = blocks(&handle.genesis, 3, 0);
+        handle
+            .send_notification_chain_committed(chain(&blocks))
+            .await?;
+        for block in &blocks {
+            assert_eq!(
+                next_finished_height(&mut handle.events_rx).await,
+                block.num_hash()
+            );
+        }
+        assert_eq!(mock.lock().attempts, 5);
+        assert_eq!(
+            submitted_blocks(&mock),
+            blocks
+                .iter()
+                .map(|block| vec![block.num_hash()])
+                .collect::<Vec<_>>()
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reorg_and_revert() -> eyre::Result<()> {
+        let (url, mock) = spawn_mock_da(0).await;
+        let (ctx, mut handle) = test_exex_context().await?;
+        let exex = DAExEx::new(
+            ctx,
+            config(url, 10),
+            Arc::new(RwLock::new(DAStatus::default())),
+            checkpoint("reorg"),
+        )?;
+        tokio::spawn(exex);
+
+        let old = blocks(&handle.genesis, 2, 0);
+        handle
+            .send_notification_chain_committed(chain(&old))
+            .await?;
+        assert_eq!(
+            next_finished_height(&mut handle.events_rx).await,
+            old[1].num_hash()
+        );
+
+        // block 2 is replaced and the new chain grows past it
+        let new = blocks(&old[0], 2, 1);
+        handle
+            .send_notification_chain_reorged(chain(&old[1..]), chain(&new))
+            .await?;
+        assert_eq!(
+            next_finished_height(&mut handle.events_rx).await,
+            new[1].num_hash()
+        );
+
+        // block 3 is dropped and built again
+        handle
+            .send_notification_chain_reverted(chain(&new[1..]))
+            .await?;
+        let rebuilt = blocks(&new[0], 1, 2);
+        handle
+            .send_notification_chain_committed(chain(&rebuilt))
+            .await?;
+        assert_eq!(
+            next_finished_height(&mut handle.events_rx).await,
+            rebuilt[0].num_hash()
+        );
+
+        assert_eq!(
+            submitted_blocks(&mock),
+            vec![num_hashes(&old), num_hashes(&new), num_hashes(&rebuilt)]
+        );
+        handle.assert_events_empty();
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_revert_drops_batches_in_flight() -> eyre::Result<()> {
+        let (url, mock) = spawn_mock_da(0).await;
+        let gate = Arc::new(Semaphore::new(0));
+        mock.lock().gate = Some(Arc::clone(&gate));
+        let (ctx, mut handle) = test_exex_context().await?;
+        let checkpoint = checkpoint("in-flight");
+        let exex = DAExEx::new(
+            ctx,
+            config(url, 1),
+            Arc::new(RwLock::new(DAStatus::default())),
+            CheckpointFile::new(checkpoint.path()),
+        )?;
+        tokio::spawn(exex);
+
+        let old = blocks(&handle.genesis, 2, 0);
+        handle
+            .send_notification_chain_committed(chain(&old))
+            .await?;
+        tokio::time::timeout(Duration::from_secs(10), async {
+            while mock.lock().attempts < 2 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await?;
+
+        // block 2 is reverted while its batch is in flight, the batch is stored but never
+        // acknowledged
+        handle
+            .send_notification_chain_reverted(chain(&old[1..]))
+            .await?;
+        let new = blocks(&old[0], 1, 1);
+        handle
+            .send_notification_chain_committed(chain(&new))
+            .await?;
+        gate.add_permits(3);
+
+        assert_eq!(
+            next_finished_height(&mut handle.events_rx).await,
+            old[0].num_hash()
+        );
+        assert_eq!(
+            next_finished_height(&mut handle.events_rx).await,
+            new[0].num_hash()
+        );
+        // the gate may let the first two submissions through in either order
+        let mut submitted = submitted_blocks(&mock);
+        submitted.sort_by_key(|blocks| (blocks[0].number, blocks[0].hash));
+        let mut expected = vec![
+            num_hashes(&old[..1]),
+            num_hashes(&old[1..]),
+            num_hashes(&new),
+        ];
+        expected.sort_by_key(|blocks| (blocks[0].number, blocks[0].hash));
+        assert_eq!(submitted, expected);
+        assert_eq!(
+            checkpoint.load()?,
+            Some(Checkpoint::advance(
+                Some(Checkpoint::advance(None, 1, old[0].hash())),
+                2,
+                new[0].hash()
+            ))
+        );
+        handle.assert_events_empty();
+        Ok(())
+    }
 }
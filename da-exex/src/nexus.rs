@@ -0,0 +1,169 @@
+use alloy::{
+    network::EthereumWallet,
+    primitives::{keccak256, Address, B256, U256},
+    providers::ProviderBuilder,
+    signers::local::PrivateKeySigner,
+    sol,
+};
+use bytes::Bytes;
+use eyre::eyre;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+
+// The DA commitment functions of `NexusSettler`
+sol!(
+    #[sol(rpc)]
+    interface NexusDACommitments {
+        struct DACommitment {
+            uint64 fromBlock;
+            uint64 toBlock;
+            bytes32 commitment;
+            address poster;
+            uint256 timestamp;
+        }
+
+        event DACommitmentPosted(uint256 indexed chainId, uint256 index, uint64 fromBlock, uint64 toBlock, bytes32 commitment);
+
+        function postDACommitment(uint256 chainId, uint64 fromBlock, uint64 toBlock, bytes32 commitment) external;
+        function daCommitmentCount(uint256 chainId) external view returns (uint256 count);
+        function getDACommitment(uint256 chainId, uint256 index) external view returns (DACommitment memory commitment);
+    }
+);
+
+/// A DA batch commitment recorded on the Nexus chain for a rollup
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DACommitmentRecord {
+    pub index: u64,
+    pub from_block: u64,
+    pub to_block: u64,
+    pub commitment: B256,
+    /// Only the poster `NexusSettler` registered for the rollup may post
+    pub poster: Address,
+    pub timestamp: U256,
+}
+
+impl DACommitmentRecord {
+    pub fn covers(&self, block: u64) -> bool {
+        self.from_block <= block && block <= self.to_block
+    }
+}
+
+/// Posts DA batch commitments to `NexusSettler`
+#[derive(Clone)]
+pub struct NexusPoster {
+    rpc_url: Url,
+    settler: Address,
+    wallet: EthereumWallet,
+}
+
+impl NexusPoster {
+    pub fn new(rpc_url: Url, settler: Address, signer: PrivateKeySigner) -> Self {
+        Self {
+            rpc_url,
+            settler,
+            wallet: EthereumWallet::new(signer),
+        }
+    }
+
+    /// Records that `commitment` holds rollup blocks `from_block..=to_block`
+    pub async fn post(
+        &self,
+        chain_id: u64,
+        from_block: u64,
+        to_block: u64,
+        commitment: B256,
+    ) -> eyre::Result<B256> {
+        let provider = ProviderBuilder::new()
+            .with_recommended_fillers()
+            .wallet(&self.wallet)
+            .on_http(self.rpc_url.clone());
+        let settler = NexusDACommitments::new(self.settler, &provider);
+        let tx_hash = settler
+            .postDACommitment(U256::from(chain_id), from_block, to_block, commitment)
+            .send()
+            .await?
+            .with_required_confirmations(1)
+            .watch()
+            .await?;
+        Ok(tx_hash)
+    }
+}
+
+/// Reads every DA commitment posted for `chain_id`, in posting order
+pub async fn read_commitments(
+    rpc_url: Url,
+    settler: Address,
+    chain_id: u64,
+) -> eyre::Result<Vec<DACommitmentRecord>> {
+    let provider = ProviderBuilder::new().on_http(rpc_url);
+    let settler = NexusDACommitments::new(settler, &provider);
+    let count = settler
+        .daCommitmentCount(U256::from(chain_id))
+        .call()
+        .await?
+        .count;
+    let mut records = Vec::with_capacity(count.to::<usize>());
+    for index in 0..count.to::<u64>() {
+        let record = settler
+            .getDACommitment(U256::from(chain_id), U256::from(index))
+            .call()
+            .await?
+            .commitment;
+        records.push(DACommitmentRecord {
+            index,
+            from_block: record.fromBlock,
+            to_block: record.toBlock,
+            commitment: record.commitment,
+            poster: record.poster,
+            timestamp: record.timestamp,
+        });
+    }
+    Ok(records)
+}
+
+/// Finds the latest record from `poster` that covers `block`
+pub fn find_covering(
+    records: &[DACommitmentRecord],
+    poster: Address,
+    block: u64,
+) -> Option<&DACommitmentRecord> {
+    records
+        .iter()
+        .rev()
+        .find(|record| record.poster == poster && record.covers(block))
+}
+
+/// Fetches the batch stored under `commitment` in the namespace of `chain_id` from the DA
+/// server at `da_url` and checks that it hashes to the commitment
+pub async fn fetch_batch(
+    client: &reqwest::Client,
+    da_url: &str,
+    chain_id: u64,
+    commitment: B256,
+) -> eyre::Result<Bytes> {
+    let response = client
+        .get(format!(
+            "{}/chains/{}/blob/{}",
+            da_url, chain_id, commitment
+        ))
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        return Err(eyre!(
+            "DA server returned {} for commitment {}",
+            response.status(),
+            commitment
+        ));
+    }
+    let data = response.bytes().await?;
+    let actual = keccak256(&data);
+    if actual != commitment {
+        return Err(eyre!(
+            "DA batch hashes to {}, expected {}",
+            actual,
+            commitment
+        ));
+    }
+    Ok(data)
+}
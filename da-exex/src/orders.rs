@@ -0,0 +1,546 @@
+use crate::checkpoint::Checkpoint;
+use alloy::{
+    consensus::Transaction as _,
+    primitives::{Address, B256, U256},
+    sol,
+    sol_types::{SolCall, SolEvent},
+};
+use eyre::eyre;
+use reth_primitives::{Receipt, SealedBlockWithSenders};
+use serde::{Deserialize, Serialize};
+use smt_server::incremental_merkle_tree::MerkleTree;
+use std::collections::{BTreeMap, HashMap};
+
+sol!(
+    #[sol(rpc)]
+    RollupSettler,
+    "../cli/artifacts/RollupSettler.json"
+);
+
+/// Depth of the order and fill trees of `RollupSettler`
+pub const TREE_HEIGHT: usize = 32;
+
+/// The call that sent an order, when it was sent straight to the settler
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderDetails {
+    pub fill_deadline: u32,
+    pub from_token: Address,
+    pub to_token: Address,
+    pub sender: Address,
+    pub recipient: Address,
+    pub amount_in: U256,
+    pub min_amount_out: U256,
+    pub destination: U256,
+}
+
+/// An order sent from this rollup, a leaf of the order tree
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Order {
+    pub hash: B256,
+    /// Index of the leaf, which the settler uses as the order nonce
+    pub nonce: u32,
+    pub block_number: u64,
+    pub transaction_hash: B256,
+    #[serde(flatten)]
+    pub details: Option<OrderDetails>,
+}
+
+/// An order of another rollup filled on this one, a leaf of the fill tree
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Fill {
+    pub hash: B256,
+    pub index: u32,
+    pub block_number: u64,
+    pub transaction_hash: B256,
+}
+
+/// A leaf with its siblings from the bottom of the tree up
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TreeProof {
+    pub index: u32,
+    pub leaf: B256,
+    pub root: B256,
+    pub proof: Vec<B256>,
+}
+
+/// The leaves of an index as of a block, persisted so a restarted node only reads the blocks
+/// after it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderSnapshot {
+    /// The last applied block, with earlier snapshotted ones to rewind to after a reorg
+    pub checkpoint: Checkpoint,
+    pub timestamp: u64,
+    pub orders: Vec<Order>,
+    pub fills: Vec<Fill>,
+}
+
+/// Orders sent and filled by a block, in log order
+#[derive(Debug, Default)]
+pub struct BlockOrders {
+    pub sent: Vec<Order>,
+    pub filled: Vec<Fill>,
+}
+
+/// Reads the `OrderSent` and `OrderFilled` logs `settler` emitted in a block
+///
+/// Receipts are those of the block's transactions, in order. The nonces and fill indexes of
+/// the returned leaves are left for the index to assign.
+pub fn block_orders<'a>(
+    block: &SealedBlockWithSenders,
+    receipts: impl IntoIterator<Item = Option<&'a Receipt>>,
+    settler: Address,
+) -> BlockOrders {
+    let mut orders = BlockOrders::default();
+    for ((sender, transaction), receipt) in block.transactions_with_sender().zip(receipts) {
+        let Some(receipt) = receipt.filter(|receipt| receipt.success) else {
+            continue;
+        };
+        let logs = receipt
+            .logs
+            .iter()
+            .filter(|log| log.address == settler)
+            .collect::<Vec<_>>();
+        let sent = logs
+            .iter()
+            .filter_map(|log| RollupSettler::OrderSent::decode_log_data(&log.data, true).ok())
+            .collect::<Vec<_>>();
+        // the order can only be read from the call if the transaction sent exactly it
+        let details = (sent.len() == 1 && transaction.to() == Some(settler))
+            .then(|| RollupSettler::sendCall::abi_decode(transaction.input(), true).ok())
+            .flatten()
+            .map(|call| OrderDetails {
+                fill_deadline: call.fillDeadline,
+                from_token: call.fromToken,
+                to_token: call.toToken,
+                sender: *sender,
+                recipient: call.recipient,
+                amount_in: call.amountIn,
+                min_amount_out: call.minAmountOut,
+                destination: call.destination,
+            });
+        orders.sent.extend(sent.into_iter().map(|event| Order {
+            hash: event.orderHash,
+            nonce: 0,
+            block_number: block.number,
+            transaction_hash: transaction.hash(),
+            details: details.clone(),
+        }));
+        orders.filled.extend(
+            logs.iter()
+                .filter_map(|log| RollupSettler::OrderFilled::decode_log_data(&log.data, true).ok())
+                .map(|event| Fill {
+                    hash: event.orderHash,
+                    index: 0,
+                    block_number: block.number,
+                    transaction_hash: transaction.hash(),
+                }),
+        );
+    }
+    orders
+}
+
+/// The order and fill trees of a rollup's settler, kept in step with the canonical chain
+///
+/// Blocks are applied in order. For every block that added leaves, the leaf counts before it
+/// are kept so a reorg can truncate the trees back to the last block that stays canonical.
+pub struct OrderIndex {
+    order_tree: MerkleTree,
+    fill_tree: MerkleTree,
+    /// Orders by nonce
+    orders: Vec<Order>,
+    order_nonces: HashMap<B256, u32>,
+    /// Fills by index
+    fills: Vec<Fill>,
+    fill_indexes: HashMap<B256, u32>,
+    /// Order and fill counts before each block that added leaves
+    checkpoints: BTreeMap<u64, (usize, usize)>,
+    /// Number of the last applied block
+    height: u64,
+    /// Timestamp of the last applied block
+    timestamp: u64,
+}
+
+impl Default for OrderIndex {
+    fn default() -> Self {
+        Self {
+            order_tree: MerkleTree::new(TREE_HEIGHT),
+            fill_tree: MerkleTree::new(TREE_HEIGHT),
+            orders: Vec::new(),
+            order_nonces: HashMap::new(),
+            fills: Vec::new(),
+            fill_indexes: HashMap::new(),
+            checkpoints: BTreeMap::new(),
+            height: 0,
+            timestamp: 0,
+        }
+    }
+}
+
+impl OrderIndex {
+    pub fn height(&self) -> u64 {
+        self.height
+    }
+
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    /// Rebuilds the index from a snapshot, appending its leaves block by block
+    pub fn restore(snapshot: OrderSnapshot) -> eyre::Result<Self> {
+        let mut blocks = BTreeMap::<u64, BlockOrders>::new();
+        for order in snapshot.orders {
+            blocks
+                .entry(order.block_number)
+                .or_default()
+                .sent
+                .push(order);
+        }
+        for fill in snapshot.fills {
+            blocks
+                .entry(fill.block_number)
+                .or_default()
+                .filled
+                .push(fill);
+        }
+        let mut index = Self::default();
+        for (number, block) in blocks {
+            index.apply(number, snapshot.timestamp, block)?;
+        }
+        if index.height > snapshot.checkpoint.number {
+            return Err(eyre!(
+                "Order snapshot of block {} has leaves of block {}",
+                snapshot.checkpoint.number,
+                index.height
+            ));
+        }
+        index.height = snapshot.checkpoint.number;
+        index.timestamp = snapshot.timestamp;
+        Ok(index)
+    }
+
+    /// The leaves applied so far, `checkpoint` being the last applied block
+    pub fn snapshot(&self, checkpoint: Checkpoint) -> OrderSnapshot {
+        OrderSnapshot {
+            checkpoint,
+            timestamp: self.timestamp,
+            orders: self.orders.clone(),
+            fills: self.fills.clone(),
+        }
+    }
+
+    /// Appends the leaves of a block past the last applied one
+    pub fn apply(&mut self, number: u64, timestamp: u64, block: BlockOrders) -> eyre::Result<()> {
+        if number <= self.height {
+            return Err(eyre!(
+                "Block {} is not past the indexed height {}",
+                number,
+                self.height
+            ));
+        }
+        if !block.sent.is_empty() || !block.filled.is_empty() {
+            self.checkpoints
+                .insert(number, (self.orders.len(), self.fills.len()));
+        }
+        for mut order in block.sent {
+            order.nonce = self.orders.len() as u32;
+            self.order_tree.append(order.hash);
+            self.order_nonces.insert(order.hash, order.nonce);
+            self.orders.push(order);
+        }
+        for mut fill in block.filled {
+            fill.index = self.fills.len() as u32;
+            self.fill_tree.append(fill.hash);
+            self.fill_indexes.insert(fill.hash, fill.index);
+            self.fills.push(fill);
+        }
+        self.height = number;
+        self.timestamp = timestamp;
+        Ok(())
+    }
+
+    /// Drops everything applied after block `number`
+    pub fn revert_to(&mut self, number: u64) {
+        if let Some((_, (orders, fills))) = self.checkpoints.range(number + 1..).next() {
+            let (orders, fills) = (*orders, *fills);
+            for order in self.orders.drain(orders..) {
+                self.order_nonces.remove(&order.hash);
+            }
+            for fill in self.fills.drain(fills..) {
+                self.fill_indexes.remove(&fill.hash);
+            }
+            self.order_tree.truncate(orders);
+            self.fill_tree.truncate(fills);
+            self.checkpoints.split_off(&(number + 1));
+        }
+        self.height = self.height.min(number);
+    }
+
+    pub fn order(&self, hash: &B256) -> Option<&Order> {
+        self.order_nonces
+            .get(hash)
+            .map(|nonce| &self.orders[*nonce as usize])
+    }
+
+    pub fn fill(&self, hash: &B256) -> Option<&Fill> {
+        self.fill_indexes
+            .get(hash)
+            .map(|index| &self.fills[*index as usize])
+    }
+
+    /// Orders to `destination` that have not expired as of the last applied block
+    ///
+    /// Fills happen on the destination, so this index cannot tell which of them were filled.
+    /// Orders whose details could not be read from the sending call are left out.
+    pub fn unexpired_orders(&self, destination: U256) -> Vec<Order> {
+        self.orders
+            .iter()
+            .filter(|order| {
+                order.details.as_ref().is_some_and(|details| {
+                    details.destination == destination
+                        && u64::from(details.fill_deadline) >= self.timestamp
+                })
+            })
+            .cloned()
+            .collect()
+    }
+
+    pub fn order_count(&self) -> usize {
+        self.orders.len()
+    }
+
+    pub fn fill_count(&self) -> usize {
+        self.fills.len()
+    }
+
+    pub fn order_root(&self) -> B256 {
+        self.order_tree.root()
+    }
+
+    pub fn fill_root(&self) -> B256 {
+        self.fill_tree.root()
+    }
+
+    /// Proof of the order with `nonce` against the current order root
+    pub fn order_proof(&self, nonce: u32) -> Option<TreeProof> {
+        let order = self.orders.get(nonce as usize)?;
+        Some(TreeProof {
+            index: nonce,
+            leaf: order.hash,
+            root: self.order_tree.root(),
+            proof: self.order_tree.generate_proof(nonce),
+        })
+    }
+
+    /// Proof of the fill with `index` against the current fill root
+    pub fn fill_proof(&self, index: u32) -> Option<TreeProof> {
+        let fill = self.fills.get(index as usize)?;
+        Some(TreeProof {
+            index,
+            leaf: fill.hash,
+            root: self.fill_tree.root(),
+            proof: self.fill_tree.generate_proof(index),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order(hash: u8, block_number: u64) -> Order {
+        Order {
+            hash: B256::with_last_byte(hash),
+            nonce: 0,
+            block_number,
+            transaction_hash: B256::ZERO,
+            details: None,
+        }
+    }
+
+    fn fill(hash: u8, block_number: u64) -> Fill {
+        Fill {
+            hash: B256::with_last_byte(hash),
+            index: 0,
+            block_number,
+            transaction_hash: B256::ZERO,
+        }
+    }
+
+    #[test]
+    fn test_apply_and_prove() {
+        let mut index = OrderIndex::default();
+        index
+            .apply(
+                1,
+                10,
+                BlockOrders {
+                    sent: vec![order(1, 1), order(2, 1)],
+                    filled: vec![fill(9, 1)],
+                },
+            )
+            .unwrap();
+        assert_eq!(index.order(&B256::with_last_byte(2)).unwrap().nonce, 1);
+        assert_eq!(index.fill(&B256::with_last_byte(9)).unwrap().index, 0);
+
+        let proof = index.order_proof(1).unwrap();
+        assert_eq!(proof.root, index.order_root());
+        assert!(MerkleTree::verify_proof(
+            proof.leaf,
+            proof.index,
+            proof.root,
+            proof.proof
+        ));
+        assert!(index.order_proof(2).is_none());
+        assert!(index.fill_proof(0).is_some());
+
+        // blocks must come in order
+        assert!(index.apply(1, 10, BlockOrders::default()).is_err());
+    }
+
+    #[test]
+    fn test_unexpired_orders() {
+        let mut index = OrderIndex::default();
+        let with_details = |hash, destination, fill_deadline| Order {
+            details: Some(OrderDetails {
+                fill_deadline,
+                from_token: Address::ZERO,
+                to_token: Address::ZERO,
+                sender: Address::ZERO,
+                recipient: Address::ZERO,
+                amount_in: U256::from(1),
+                min_amount_out: U256::from(1),
+                destination: U256::from(destination),
+            }),
+            ..order(hash, 1)
+        };
+        index
+            .apply(
+                1,
+                100,
+                BlockOrders {
+                    sent: vec![
+                        with_details(1, 31339, 110),
+                        with_details(2, 31339, 150),
+                        with_details(3, 31340, 150),
+                        order(4, 1),
+                    ],
+                    filled: vec![],
+                },
+            )
+            .unwrap();
+        let unexpired = |index: &OrderIndex| {
+            index
+                .unexpired_orders(U256::from(31339))
+                .iter()
+                .map(|order| order.nonce)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(unexpired(&index), vec![0, 1]);
+        // the first order expires
+        index.apply(2, 120, BlockOrders::default()).unwrap();
+        assert_eq!(unexpired(&index), vec![1]);
+    }
+
+    #[test]
+    fn test_restore() {
+        let mut index = OrderIndex::default();
+        index
+            .apply(
+                2,
+                10,
+                BlockOrders {
+                    sent: vec![order(1, 2), order(2, 2)],
+                    filled: vec![fill(9, 2)],
+                },
+            )
+            .unwrap();
+        index
+            .apply(
+                4,
+                12,
+                BlockOrders {
+                    sent: vec![order(3, 4)],
+                    filled: vec![],
+                },
+            )
+            .unwrap();
+        index.apply(5, 13, BlockOrders::default()).unwrap();
+        let checkpoint = Checkpoint::advance(None, 5, B256::with_last_byte(5));
+        let snapshot: OrderSnapshot =
+            serde_json::from_slice(&serde_json::to_vec(&index.snapshot(checkpoint)).unwrap())
+                .unwrap();
+
+        let mut restored = OrderIndex::restore(snapshot.clone()).unwrap();
+        assert_eq!(restored.height(), 5);
+        assert_eq!(restored.timestamp(), 13);
+        assert_eq!(restored.order_root(), index.order_root());
+        assert_eq!(restored.fill_root(), index.fill_root());
+        assert_eq!(restored.order(&B256::with_last_byte(3)).unwrap().nonce, 2);
+        // reorgs can still rewind past the snapshot
+        restored.revert_to(3);
+        assert_eq!(restored.order_count(), 2);
+
+        let checkpoint = Checkpoint::advance(None, 3, B256::with_last_byte(3));
+        assert!(OrderIndex::restore(OrderSnapshot {
+            checkpoint,
+            ..snapshot
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn test_revert() {
+        let mut index = OrderIndex::default();
+        index
+            .apply(
+                1,
+                10,
+                BlockOrders {
+                    sent: vec![order(1, 1)],
+                    filled: vec![],
+                },
+            )
+            .unwrap();
+        index.apply(2, 11, BlockOrders::default()).unwrap();
+        let root = index.order_root();
+        let fill_root = index.fill_root();
+        index
+            .apply(
+                3,
+                12,
+                BlockOrders {
+                    sent: vec![order(2, 3)],
+                    filled: vec![fill(9, 3)],
+                },
+            )
+            .unwrap();
+        assert_ne!(index.order_root(), root);
+
+        index.revert_to(2);
+        assert_eq!(index.height(), 2);
+        assert_eq!(index.order_root(), root);
+        assert_eq!(index.fill_root(), fill_root);
+        assert!(index.order(&B256::with_last_byte(2)).is_none());
+        assert!(index.fill(&B256::with_last_byte(9)).is_none());
+
+        // the replacing block takes over the freed nonces
+        index
+            .apply(
+                3,
+                12,
+                BlockOrders {
+                    sent: vec![order(3, 3)],
+                    filled: vec![],
+                },
+            )
+            .unwrap();
+        assert_eq!(index.order(&B256::with_last_byte(3)).unwrap().nonce, 1);
+        index.revert_to(0);
+        assert_eq!(index.order_count(), 0);
+        assert_eq!(index.order_root(), OrderIndex::default().order_root());
+    }
+}
@@ -0,0 +1,260 @@
+use alloy::{
+    network::EthereumWallet,
+    primitives::{Address, B256, U256},
+    providers::{Provider, ProviderBuilder},
+    signers::local::PrivateKeySigner,
+    sol,
+};
+use reqwest::Url;
+use reth_tracing::tracing::{info, warn};
+use std::{collections::VecDeque, time::Duration};
+use tokio::sync::watch;
+
+// The root functions shared by `NexusSettler` and `RollupSettler`
+sol!(
+    #[sol(rpc)]
+    interface RootsSettler {
+        function orderRoots(uint256 chainId) external view returns (bytes32);
+        function fillRoots(uint256 chainId) external view returns (bytes32);
+        function updateRollupOrderRoot(uint256 chainId, bytes32 root) external;
+        function updateRollupFillRoot(uint256 chainId, bytes32 root) external;
+        function updateRollupRoot(uint256 chainId) external;
+    }
+);
+
+/// Roots of the rollup as of a block
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RollupRoots {
+    pub block_number: u64,
+    pub state_root: B256,
+    pub order_root: B256,
+    pub fill_root: B256,
+}
+
+impl RollupRoots {
+    fn same_roots(&self, other: &Self) -> bool {
+        (self.state_root, self.order_root, self.fill_root)
+            == (other.state_root, other.order_root, other.fill_root)
+    }
+}
+
+/// Publishes the roots of the block `confirmations` below the tip, once they change
+pub struct RootsPublisher {
+    sender: watch::Sender<RollupRoots>,
+    confirmations: u64,
+    /// Roots of the latest blocks, down to the published one
+    recent: VecDeque<RollupRoots>,
+}
+
+impl RootsPublisher {
+    pub fn new(confirmations: u64) -> (Self, watch::Receiver<RollupRoots>) {
+        let (sender, receiver) = watch::channel(RollupRoots::default());
+        let publisher = Self {
+            sender,
+            confirmations,
+            recent: VecDeque::new(),
+        };
+        (publisher, receiver)
+    }
+
+    /// Blocks a root waits for on top of its own before it is published
+    pub fn confirmations(&self) -> u64 {
+        self.confirmations
+    }
+
+    /// Records the roots after a new tip
+    pub fn push(&mut self, roots: RollupRoots) {
+        self.recent.push_back(roots);
+        while self.recent.len() as u64 > self.confirmations + 1 {
+            self.recent.pop_front();
+        }
+        if self.recent.len() as u64 == self.confirmations + 1 {
+            let confirmed = self.recent[0];
+            self.sender.send_if_modified(|published| {
+                let modified = !published.same_roots(&confirmed);
+                *published = confirmed;
+                modified
+            });
+        }
+    }
+
+    /// Forgets the roots of blocks after `number`
+    pub fn revert_to(&mut self, number: u64) {
+        while self
+            .recent
+            .back()
+            .is_some_and(|roots| roots.block_number > number)
+        {
+            self.recent.pop_back();
+        }
+    }
+}
+
+/// A settler the rollup's roots are relayed to
+#[derive(Debug, Clone)]
+pub struct RelayTarget {
+    pub rpc_url: Url,
+    pub settler: Address,
+    /// Also have the settler record the state root, only `NexusSettler` does
+    pub state_root: bool,
+}
+
+/// Keeps the roots of a rollup up to date on Nexus and its peers
+///
+/// Every target is updated by its own task, so a chain that is down does not hold back the
+/// others. Roots published while an update is in flight or during `interval` are folded into
+/// the next update, which only sends the roots the settler does not hold yet.
+#[derive(Clone)]
+pub struct Relayer {
+    chain_id: U256,
+    address: Address,
+    wallet: EthereumWallet,
+    interval: Duration,
+    retry_delay: Duration,
+}
+
+impl Relayer {
+    pub fn new(
+        chain_id: u64,
+        signer: PrivateKeySigner,
+        interval: Duration,
+        retry_delay: Duration,
+    ) -> Self {
+        Self {
+            chain_id: U256::from(chain_id),
+            address: signer.address(),
+            wallet: EthereumWallet::new(signer),
+            interval,
+            retry_delay,
+        }
+    }
+
+    /// Relays published roots to the targets until the publisher is dropped
+    pub async fn run(self, targets: Vec<RelayTarget>, roots: watch::Receiver<RollupRoots>) {
+        let tasks = targets
+            .into_iter()
+            .map(|target| self.clone().relay_to(target, roots.clone()));
+        futures::future::join_all(tasks).await;
+    }
+
+    async fn relay_to(self, target: RelayTarget, mut roots: watch::Receiver<RollupRoots>) {
+        let provider = ProviderBuilder::new()
+            .with_recommended_fillers()
+            .wallet(&self.wallet)
+            .on_http(target.rpc_url.clone());
+        // nonces are assigned here so the updates of a round go out together, and fetched
+        // again after a failure in case a transaction never made it to the pool
+        let mut nonce = None;
+        let mut state_root = None;
+        while roots.changed().await.is_ok() {
+            tokio::time::sleep(self.interval).await;
+            loop {
+                let latest = *roots.borrow_and_update();
+                match self
+                    .update(&provider, &target, &latest, &mut nonce, &mut state_root)
+                    .await
+                {
+                    Ok(0) => break,
+                    Ok(sent) => {
+                        info!(settler = %target.settler, rpc = %target.rpc_url, block_number = latest.block_number, order_root = %latest.order_root, fill_root = %latest.fill_root, sent, "Relayed rollup roots");
+                        break;
+                    }
+                    Err(e) => {
+                        nonce = None;
+                        warn!(settler = %target.settler, rpc = %target.rpc_url, "Relaying rollup roots failed, retrying: {:?}", e);
+                        tokio::time::sleep(self.retry_delay).await;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Sends the updates `target` needs to hold `roots` and waits for them, returning how many
+    /// were sent
+    async fn update<P: Provider<alloy::transports::http::Http<reqwest::Client>>>(
+        &self,
+        provider: &P,
+        target: &RelayTarget,
+        roots: &RollupRoots,
+        nonce: &mut Option<u64>,
+        state_root: &mut Option<B256>,
+    ) -> eyre::Result<usize> {
+        let settler = RootsSettler::new(target.settler, provider);
+        let mut next_nonce = match *nonce {
+            Some(nonce) => nonce,
+            None => {
+                provider
+                    .get_transaction_count(self.address)
+                    .pending()
+                    .await?
+            }
+        };
+        let mut pending = Vec::new();
+        if settler.orderRoots(self.chain_id).call().await?._0 != roots.order_root {
+            let call = settler.updateRollupOrderRoot(self.chain_id, roots.order_root);
+            pending.push(call.nonce(next_nonce).send().await?);
+            next_nonce += 1;
+        }
+        if settler.fillRoots(self.chain_id).call().await?._0 != roots.fill_root {
+            let call = settler.updateRollupFillRoot(self.chain_id, roots.fill_root);
+            pending.push(call.nonce(next_nonce).send().await?);
+            next_nonce += 1;
+        }
+        let record_state_root = target.state_root && *state_root != Some(roots.state_root);
+        if record_state_root {
+            let call = settler.updateRollupRoot(self.chain_id);
+            pending.push(call.nonce(next_nonce).send().await?);
+            next_nonce += 1;
+        }
+        *nonce = Some(next_nonce);
+        let sent = pending.len();
+        for transaction in pending {
+            transaction.with_required_confirmations(1).watch().await?;
+        }
+        if record_state_root {
+            *state_root = Some(roots.state_root);
+        }
+        Ok(sent)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roots(block_number: u64, order_root: u8) -> RollupRoots {
+        RollupRoots {
+            block_number,
+            order_root: B256::with_last_byte(order_root),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_publish_confirmed_roots() {
+        let (mut publisher, mut receiver) = RootsPublisher::new(2);
+        publisher.push(roots(1, 1));
+        publisher.push(roots(2, 2));
+        assert!(!receiver.has_changed().unwrap());
+        publisher.push(roots(3, 3));
+        assert!(receiver.has_changed().unwrap());
+        assert_eq!(*receiver.borrow_and_update(), roots(1, 1));
+
+        publisher.push(roots(4, 1));
+        publisher.push(roots(5, 1));
+        assert_eq!(*receiver.borrow_and_update(), roots(3, 3));
+        publisher.push(roots(6, 1));
+        assert_eq!(*receiver.borrow_and_update(), roots(4, 1));
+        // a block that does not change the roots is not published
+        publisher.push(roots(7, 1));
+        assert!(!receiver.has_changed().unwrap());
+
+        // reverted blocks have to be confirmed again
+        publisher.revert_to(5);
+        publisher.push(roots(6, 6));
+        publisher.push(roots(7, 7));
+        assert!(!receiver.has_changed().unwrap());
+        publisher.push(roots(8, 8));
+        assert_eq!(*receiver.borrow_and_update(), roots(6, 6));
+    }
+}
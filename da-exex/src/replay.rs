@@ -0,0 +1,630 @@
+use crate::{
+    batch::{batch_mode, decode_batch, decode_diffs, BatchBlock, PostingMode},
+    diff::BlockDiff,
+};
+use alloy::{
+    genesis::{Genesis, GenesisAccount},
+    primitives::{keccak256, Address, B256, U256},
+};
+use eyre::{eyre, WrapErr};
+use reth::revm::{
+    db::{AccountState, CacheDB, DbAccount, EmptyDB},
+    primitives::{AccountInfo, Bytecode, EnvWithHandlerCfg, ResultAndState, KECCAK_EMPTY},
+    DatabaseCommit,
+};
+use reth_chainspec::ChainSpec;
+use reth_evm::{ConfigureEvm, ConfigureEvmEnv};
+use reth_node_ethereum::EthEvmConfig;
+use reth_primitives::Header;
+use reth_trie_common::{root, TrieAccount};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    path::Path,
+    sync::Arc,
+};
+
+/// Most recent blocks whose changes are kept, so a batch replacing them can be replayed
+const REORG_DEPTH: usize = 256;
+
+/// A block replayed from a batch
+#[derive(Debug, Clone)]
+pub struct ReplayedBlock {
+    pub number: u64,
+    pub hash: B256,
+    pub timestamp: u64,
+    /// State root in the block's header, which the replayed state should have
+    pub posted_root: B256,
+    /// The block's header, batches of state diffs only carry its hash and state root
+    pub header: Option<Header>,
+}
+
+/// The state of a [`Replayer`], persisted so a restarted verifier does not replay every batch
+/// again
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayerSnapshot {
+    pub head: u64,
+    pub head_hash: B256,
+    pub accounts: BTreeMap<Address, GenesisAccount>,
+}
+
+/// How to take a block back out of the state
+struct BlockUndo {
+    number: u64,
+    hash: B256,
+    parent_hash: B256,
+    /// Accounts the block changed as they were before it, `None` if they were not loaded
+    accounts: HashMap<Address, Option<DbAccount>>,
+}
+
+/// Rebuilds rollup state from DA batches on top of a genesis, entirely in memory
+///
+/// Batches of blocks are executed again. Only transactions and withdrawals are applied: the
+/// rollups run post-merge Shanghai chains, so there are no block rewards or pre-block system
+/// calls to replay. Batches of state diffs are applied as they are.
+///
+/// The accounts each of the last [`REORG_DEPTH`] blocks changed are kept as they were before
+/// it, so when a rollup reorgs and posts the blocks replacing ones already replayed, the state
+/// is rewound to their parent and replayed from there.
+///
+/// The trie leaves of the accounts are kept between state roots, and only those of the accounts
+/// changed since the last one are computed again, storage root included.
+pub struct Replayer {
+    evm_config: EthEvmConfig,
+    db: CacheDB<EmptyDB>,
+    /// Leaves of the account trie as of the last state root, by hashed address
+    leaves: BTreeMap<B256, TrieAccount>,
+    /// Accounts changed since the last state root
+    changed: HashSet<Address>,
+    /// Number of the last executed block
+    head: u64,
+    /// Hash of the last executed block, which the next block must name as its parent
+    head_hash: B256,
+    /// Undo data of the latest blocks, oldest first
+    undo: VecDeque<BlockUndo>,
+}
+
+impl Replayer {
+    /// Loads the genesis file the rollup was started with
+    pub fn from_genesis_file(path: &Path) -> eyre::Result<Self> {
+        let genesis = std::fs::read_to_string(path)
+            .wrap_err_with(|| format!("Failed to read genesis file {}", path.display()))?;
+        let genesis: Genesis = serde_json::from_str(&genesis)?;
+        Ok(Self::new(genesis))
+    }
+
+    pub fn new(genesis: Genesis) -> Self {
+        let alloc = genesis.alloc.clone();
+        let chain_spec = Arc::new(ChainSpec::from(genesis));
+        let head_hash = chain_spec.genesis_hash();
+        Self::with_state(chain_spec, &alloc, 0, head_hash)
+    }
+
+    /// Picks up from a snapshot of a replayer started with `genesis`
+    ///
+    /// The undo data is not snapshotted, so blocks replayed before the snapshot cannot be
+    /// replaced anymore.
+    pub fn restore(genesis: Genesis, snapshot: &ReplayerSnapshot) -> Self {
+        let chain_spec = Arc::new(ChainSpec::from(genesis));
+        Self::with_state(
+            chain_spec,
+            &snapshot.accounts,
+            snapshot.head,
+            snapshot.head_hash,
+        )
+    }
+
+    fn with_state(
+        chain_spec: Arc<ChainSpec>,
+        accounts: &BTreeMap<Address, GenesisAccount>,
+        head: u64,
+        head_hash: B256,
+    ) -> Self {
+        let mut db = CacheDB::new(EmptyDB::default());
+        for (address, account) in accounts {
+            let code = account.code.clone().map(Bytecode::new_raw);
+            db.insert_account_info(
+                *address,
+                AccountInfo {
+                    balance: account.balance,
+                    nonce: account.nonce.unwrap_or_default(),
+                    code_hash: code.as_ref().map_or(KECCAK_EMPTY, |code| code.hash_slow()),
+                    code,
+                },
+            );
+            for (slot, value) in account.storage.iter().flatten() {
+                db.insert_account_storage(
+                    *address,
+                    U256::from_be_bytes(slot.0),
+                    U256::from_be_bytes(value.0),
+                )
+                .expect("in-memory storage insert is infallible");
+            }
+        }
+        Self {
+            evm_config: EthEvmConfig::new(chain_spec),
+            db,
+            leaves: BTreeMap::new(),
+            changed: accounts.keys().copied().collect(),
+            head,
+            head_hash,
+            undo: VecDeque::new(),
+        }
+    }
+
+    /// The accounts as of the last replayed block, in the shape of a genesis alloc
+    pub fn snapshot(&self) -> ReplayerSnapshot {
+        let accounts = self
+            .db
+            .accounts
+            .iter()
+            .filter(|(_, account)| {
+                !matches!(account.account_state, AccountState::NotExisting)
+                    && !account.info.is_empty()
+            })
+            .map(|(address, account)| {
+                let code = account
+                    .info
+                    .code
+                    .clone()
+                    .or_else(|| self.db.contracts.get(&account.info.code_hash).cloned());
+                let storage = account
+                    .storage
+                    .iter()
+                    .filter(|(_, value)| !value.is_zero())
+                    .map(|(slot, value)| {
+                        (
+                            B256::from(slot.to_be_bytes()),
+                            B256::from(value.to_be_bytes()),
+                        )
+                    })
+                    .collect::<BTreeMap<_, _>>();
+                let account = GenesisAccount {
+                    nonce: Some(account.info.nonce),
+                    balance: account.info.balance,
+                    code: code
+                        .filter(|code| !code.is_empty())
+                        .map(|code| code.original_bytes()),
+                    storage: Some(storage),
+                    private_key: None,
+                };
+                (*address, account)
+            })
+            .collect();
+        ReplayerSnapshot {
+            head: self.head,
+            head_hash: self.head_hash,
+            accounts,
+        }
+    }
+
+    pub fn head(&self) -> u64 {
+        self.head
+    }
+
+    /// Executes the transactions and withdrawals of `block`, which must be the child of the
+    /// last one
+    pub fn execute(&mut self, block: &BatchBlock) -> eyre::Result<()> {
+        self.check_parent(block.number(), block.header.parent_hash)?;
+        let mut accounts = HashMap::new();
+        if let Err(e) = self.execute_transactions(block, &mut accounts) {
+            self.restore(accounts);
+            return Err(e);
+        }
+        for withdrawal in &block.withdrawals {
+            accounts
+                .entry(withdrawal.address)
+                .or_insert_with(|| self.db.accounts.get(&withdrawal.address).cloned());
+            let account = self
+                .db
+                .load_account(withdrawal.address)
+                .expect("in-memory account load is infallible");
+            account.info.balance = account.info.balance.saturating_add(withdrawal.amount_wei());
+            if matches!(account.account_state, AccountState::NotExisting) {
+                account.account_state = AccountState::Touched;
+            }
+        }
+        self.advance(
+            block.number(),
+            block.hash,
+            block.header.parent_hash,
+            accounts,
+        );
+        Ok(())
+    }
+
+    /// Executes the transactions of a block, recording the accounts they change as they were
+    /// before it
+    fn execute_transactions(
+        &mut self,
+        block: &BatchBlock,
+        accounts: &mut HashMap<Address, Option<DbAccount>>,
+    ) -> eyre::Result<()> {
+        let (cfg, block_env) = self.evm_config.cfg_and_block_env(&block.header, U256::ZERO);
+        let env = EnvWithHandlerCfg::new_with_cfg_env(cfg, block_env, Default::default());
+        let mut evm = self.evm_config.evm_with_env(&mut self.db, env);
+        for transaction in &block.transactions {
+            let sender = transaction
+                .recover_signer()
+                .ok_or_else(|| eyre!("Invalid signature on {}", transaction.hash()))?;
+            self.evm_config
+                .fill_tx_env(evm.tx_mut(), transaction, sender);
+            let ResultAndState { state, .. } = evm
+                .transact()
+                .map_err(|e| eyre!("Failed to execute {}: {:?}", transaction.hash(), e))?;
+            for address in state.keys() {
+                accounts
+                    .entry(*address)
+                    .or_insert_with(|| evm.db().accounts.get(address).cloned());
+            }
+            evm.db_mut().commit(state);
+        }
+        Ok(())
+    }
+
+    /// Applies the state changes of a block, which must be the child of the last one
+    pub fn apply_diff(&mut self, diff: &BlockDiff) -> eyre::Result<()> {
+        self.check_parent(diff.number, diff.parent_hash)?;
+        let mut accounts = HashMap::new();
+        for account in &diff.accounts {
+            accounts
+                .entry(account.address)
+                .or_insert_with(|| self.db.accounts.get(&account.address).cloned());
+            let entry = self.db.accounts.entry(account.address).or_default();
+            if account.destroyed {
+                entry.info = AccountInfo::default();
+                entry.storage.clear();
+                entry.account_state = AccountState::NotExisting;
+                continue;
+            }
+            if account.wipe_storage {
+                entry.storage.clear();
+            }
+            entry.account_state = AccountState::Touched;
+            entry.info = AccountInfo {
+                balance: account.balance,
+                nonce: account.nonce,
+                code_hash: account.code_hash,
+                code: None,
+            };
+            for slot in &account.storage {
+                entry.storage.insert(slot.slot, slot.value);
+            }
+            if !account.code.is_empty() {
+                self.db
+                    .contracts
+                    .insert(account.code_hash, Bytecode::new_raw(account.code.clone()));
+            }
+        }
+        self.advance(diff.number, diff.hash, diff.parent_hash, accounts);
+        Ok(())
+    }
+
+    /// Makes a block the head, keeping what it changed so it can be rewound
+    fn advance(
+        &mut self,
+        number: u64,
+        hash: B256,
+        parent_hash: B256,
+        accounts: HashMap<Address, Option<DbAccount>>,
+    ) {
+        self.head = number;
+        self.head_hash = hash;
+        self.changed.extend(accounts.keys().copied());
+        self.undo.push_back(BlockUndo {
+            number,
+            hash,
+            parent_hash,
+            accounts,
+        });
+        if self.undo.len() > REORG_DEPTH {
+            self.undo.pop_front();
+        }
+    }
+
+    /// Puts accounts back as they were before a block
+    fn restore(&mut self, accounts: HashMap<Address, Option<DbAccount>>) {
+        self.changed.extend(accounts.keys().copied());
+        for (address, account) in accounts {
+            match account {
+                Some(account) => self.db.accounts.insert(address, account),
+                None => self.db.accounts.remove(&address),
+            };
+        }
+    }
+
+    /// Takes the blocks after `number` back out of the state
+    fn rewind(&mut self, number: u64) {
+        while self.undo.back().is_some_and(|undo| undo.number > number) {
+            let undo = self.undo.pop_back().expect("checked above");
+            self.head = undo.number - 1;
+            self.head_hash = undo.parent_hash;
+            self.restore(undo.accounts);
+        }
+    }
+
+    /// Whether a block of a batch is still to be replayed, rewinding to its parent if it
+    /// replaces a block already replayed
+    ///
+    /// A block whose parent was not replayed is left to fail the parent check without touching
+    /// the state. Blocks older than the kept undo data are taken to be the ones already
+    /// replayed, as when a batch resubmitted after a restart overlaps the ones before it.
+    fn is_new(&mut self, number: u64, hash: B256, parent_hash: B256) -> bool {
+        if number > self.head {
+            return true;
+        }
+        match self.undo.iter().find(|undo| undo.number == number) {
+            Some(undo) if undo.hash == hash => false,
+            Some(undo) => {
+                if undo.parent_hash == parent_hash {
+                    self.rewind(number - 1);
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Executes or applies the blocks of a batch not replayed yet, calling `replayed` after
+    /// each one with the state it left
+    ///
+    /// Blocks already replayed are skipped, and blocks replacing them are replayed on top of
+    /// their parent.
+    pub fn apply_batch(
+        &mut self,
+        data: &[u8],
+        mut replayed: impl FnMut(&mut Self, ReplayedBlock) -> eyre::Result<()>,
+    ) -> eyre::Result<()> {
+        match batch_mode(data)? {
+            PostingMode::Blocks => {
+                for block in decode_batch(data)? {
+                    if !self.is_new(block.number(), block.hash, block.header.parent_hash) {
+                        continue;
+                    }
+                    self.execute(&block)?;
+                    let block = ReplayedBlock {
+                        number: block.number(),
+                        hash: block.hash,
+                        timestamp: block.header.timestamp,
+                        posted_root: block.header.state_root,
+                        header: Some(block.header),
+                    };
+                    replayed(self, block)?;
+                }
+            }
+            PostingMode::StateDiffs => {
+                for diff in decode_diffs(data)? {
+                    if !self.is_new(diff.number, diff.hash, diff.parent_hash) {
+                        continue;
+                    }
+                    self.apply_diff(&diff)?;
+                    let block = ReplayedBlock {
+                        number: diff.number,
+                        hash: diff.hash,
+                        timestamp: diff.timestamp,
+                        posted_root: diff.state_root,
+                        header: None,
+                    };
+                    replayed(self, block)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn check_parent(&self, number: u64, parent_hash: B256) -> eyre::Result<()> {
+        if number != self.head + 1 || parent_hash != self.head_hash {
+            return Err(eyre!(
+                "Expected a child of block {} ({}), got block {} with parent {}",
+                self.head,
+                self.head_hash,
+                number,
+                parent_hash
+            ));
+        }
+        Ok(())
+    }
+
+    /// Computes the state root of everything executed so far
+    pub fn state_root(&mut self) -> B256 {
+        for address in std::mem::take(&mut self.changed) {
+            let hashed_address = keccak256(address);
+            let account = self.db.accounts.get(&address).filter(|account| {
+                !matches!(account.account_state, AccountState::NotExisting)
+                    && !account.info.is_empty()
+            });
+            let Some(account) = account else {
+                self.leaves.remove(&hashed_address);
+                continue;
+            };
+            let storage_root = root::storage_root_unhashed(
+                account
+                    .storage
+                    .iter()
+                    .filter(|(_, value)| !value.is_zero())
+                    .map(|(slot, value)| (B256::from(slot.to_be_bytes()), *value)),
+            );
+            self.leaves.insert(
+                hashed_address,
+                TrieAccount {
+                    nonce: account.info.nonce,
+                    balance: account.info.balance,
+                    storage_root,
+                    code_hash: account.info.code_hash,
+                },
+            );
+        }
+        root::state_root(
+            self.leaves
+                .iter()
+                .map(|(hashed_address, account)| (*hashed_address, *account)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        batch::{encode_diffs, Compression},
+        diff::{AccountDiff, SlotDiff},
+    };
+
+    fn genesis(accounts: Vec<(Address, GenesisAccount)>) -> Genesis {
+        Genesis::default().extend_accounts(accounts)
+    }
+
+    fn account(balance: u64) -> GenesisAccount {
+        GenesisAccount::default().with_balance(U256::from(balance))
+    }
+
+    #[test]
+    fn test_apply_diffs() {
+        let (a, b, c) = (
+            Address::with_last_byte(1),
+            Address::with_last_byte(2),
+            Address::with_last_byte(3),
+        );
+        let mut replayer = Replayer::new(genesis(vec![(a, account(100)), (c, account(5))]));
+        let code = alloy::primitives::Bytes::from_static(&[0x60, 0x00]);
+        let diff = BlockDiff {
+            number: 1,
+            hash: B256::with_last_byte(1),
+            parent_hash: replayer.head_hash,
+            timestamp: 1,
+            state_root: B256::ZERO,
+            accounts: vec![
+                AccountDiff {
+                    address: a,
+                    destroyed: false,
+                    wipe_storage: false,
+                    nonce: 1,
+                    balance: U256::from(60),
+                    code_hash: KECCAK_EMPTY,
+                    code: Default::default(),
+                    storage: Vec::new(),
+                },
+                AccountDiff {
+                    address: b,
+                    destroyed: false,
+                    wipe_storage: false,
+                    nonce: 1,
+                    balance: U256::from(40),
+                    code_hash: keccak256(&code),
+                    code: code.clone(),
+                    storage: vec![SlotDiff {
+                        slot: U256::from(1),
+                        value: U256::from(7),
+                    }],
+                },
+                AccountDiff {
+                    address: c,
+                    destroyed: true,
+                    wipe_storage: true,
+                    nonce: 0,
+                    balance: U256::ZERO,
+                    code_hash: B256::ZERO,
+                    code: Default::default(),
+                    storage: Vec::new(),
+                },
+            ],
+        };
+        replayer.apply_diff(&diff).unwrap();
+        assert_eq!(replayer.head(), 1);
+
+        // the same state written straight into a genesis has the same root
+        let mut expected = Replayer::new(genesis(vec![
+            (a, account(60).with_nonce(Some(1))),
+            (
+                b,
+                account(40)
+                    .with_nonce(Some(1))
+                    .with_code(Some(code))
+                    .with_storage(Some(
+                        [(B256::with_last_byte(1), B256::with_last_byte(7))].into(),
+                    )),
+            ),
+        ]));
+        assert_eq!(replayer.state_root(), expected.state_root());
+
+        // diffs must follow the last applied block
+        assert!(replayer.apply_diff(&diff).is_err());
+
+        // a replayer restored from a snapshot has the same state and head
+        let snapshot: ReplayerSnapshot =
+            serde_json::from_slice(&serde_json::to_vec(&replayer.snapshot()).unwrap()).unwrap();
+        let mut restored = Replayer::restore(genesis(vec![]), &snapshot);
+        assert_eq!(restored.head(), 1);
+        assert_eq!(restored.state_root(), replayer.state_root());
+        assert!(restored.apply_diff(&diff).is_err());
+    }
+
+    /// A block that only sets the balance of `address`
+    fn balance_diff(
+        number: u64,
+        fork: u8,
+        parent_hash: B256,
+        address: Address,
+        balance: u64,
+    ) -> BlockDiff {
+        BlockDiff {
+            number,
+            hash: {
+                let mut hash = [fork; 32];
+                hash[31] = number as u8;
+                B256::from(hash)
+            },
+            parent_hash,
+            timestamp: number,
+            state_root: B256::ZERO,
+            accounts: vec![AccountDiff {
+                address,
+                destroyed: false,
+                wipe_storage: false,
+                nonce: 0,
+                balance: U256::from(balance),
+                code_hash: KECCAK_EMPTY,
+                code: Default::default(),
+                storage: Vec::new(),
+            }],
+        }
+    }
+
+    fn replay(replayer: &mut Replayer, diffs: &[BlockDiff]) -> eyre::Result<Vec<u64>> {
+        let data = encode_diffs(diffs, Compression::None)?;
+        let mut replayed = Vec::new();
+        replayer.apply_batch(&data, |_, block| {
+            replayed.push(block.number);
+            Ok(())
+        })?;
+        Ok(replayed)
+    }
+
+    #[test]
+    fn test_replay_reorged_batch() {
+        let (a, b) = (Address::with_last_byte(1), Address::with_last_byte(2));
+        let mut replayer = Replayer::new(genesis(vec![(a, account(100))]));
+        let one = balance_diff(1, 0, replayer.head_hash, a, 90);
+        let two = balance_diff(2, 0, one.hash, b, 10);
+        assert_eq!(replay(&mut replayer, &[one.clone(), two]).unwrap(), [1, 2]);
+
+        // the rollup reorged block 2 away and posts the blocks replacing it, after block 1 again
+        let new_two = balance_diff(2, 1, one.hash, a, 50);
+        let new_three = balance_diff(3, 1, new_two.hash, a, 40);
+        let batch = [one.clone(), new_two, new_three];
+        assert_eq!(replay(&mut replayer, &batch).unwrap(), [2, 3]);
+        assert_eq!(replayer.head(), 3);
+        // `b` was only funded by the reorged block
+        let mut expected = Replayer::new(genesis(vec![(a, account(40))]));
+        assert_eq!(replayer.state_root(), expected.state_root());
+
+        // posting the same blocks again changes nothing
+        assert!(replay(&mut replayer, &batch).unwrap().is_empty());
+        // a block whose parent was never replayed is rejected without rewinding
+        let orphan = balance_diff(3, 2, B256::with_last_byte(9), a, 1);
+        assert!(replay(&mut replayer, &[orphan]).is_err());
+        assert_eq!(replayer.head(), 3);
+        assert_eq!(replayer.state_root(), expected.state_root());
+    }
+}
@@ -0,0 +1,29 @@
+use std::time::Duration;
+
+/// Longest wait between two attempts of a failing request
+pub const MAX_RETRY_DELAY: Duration = Duration::from_secs(300);
+
+/// Failures in a row after which a request that is still retried is reported as failing
+pub const PERSISTENT_FAILURES: u32 = 5;
+
+/// Wait before the next attempt of a request that failed `failures` times in a row, doubling
+/// with every failure up to [`MAX_RETRY_DELAY`]
+pub fn backoff(retry_delay: Duration, failures: u32) -> Duration {
+    retry_delay
+        .saturating_mul(2u32.saturating_pow(failures.saturating_sub(1)))
+        .min(MAX_RETRY_DELAY)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff() {
+        let retry_delay = Duration::from_secs(2);
+        assert_eq!(backoff(retry_delay, 1), retry_delay);
+        assert_eq!(backoff(retry_delay, 3), Duration::from_secs(8));
+        assert_eq!(backoff(retry_delay, 9), MAX_RETRY_DELAY);
+        assert_eq!(backoff(retry_delay, u32::MAX), MAX_RETRY_DELAY);
+    }
+}
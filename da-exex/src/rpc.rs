@@ -0,0 +1,122 @@
+use crate::{
+    orders::{Fill, Order, OrderIndex, TreeProof},
+    status::{BlockDAStatus, DAStatus},
+};
+use alloy::primitives::{B256, U256};
+use jsonrpsee::{core::RpcResult, proc_macros::rpc, types::ErrorObjectOwned};
+use parking_lot::RwLock;
+use serde::Serialize;
+use std::sync::Arc;
+
+/// Error code of methods whose data the node was not started to index
+const NOT_INDEXED: i32 = -32001;
+
+/// Roots of the order and fill trees as of an indexed block
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderRoots {
+    pub block_number: u64,
+    pub order_root: B256,
+    pub order_count: usize,
+    pub fill_root: B256,
+    pub fill_count: usize,
+}
+
+/// The `reimann_` namespace, served by rollup nodes next to `eth_`
+#[rpc(server, namespace = "reimann")]
+pub trait ReimannApi {
+    /// The order sent with `hash` from this rollup
+    #[method(name = "getOrder")]
+    fn get_order(&self, hash: B256) -> RpcResult<Option<Order>>;
+
+    /// The fill of the order with `hash` on this rollup
+    #[method(name = "getFill")]
+    fn get_fill(&self, hash: B256) -> RpcResult<Option<Fill>>;
+
+    /// Orders sent to the rollup with chain id `destination` that have not expired, filled or
+    /// not, which only `getFill` on the destination tells
+    #[method(name = "unexpiredOrders")]
+    fn unexpired_orders(&self, destination: U256) -> RpcResult<Vec<Order>>;
+
+    /// Proof of the order with `nonce` against the current order root
+    #[method(name = "orderProof")]
+    fn order_proof(&self, nonce: u32) -> RpcResult<Option<TreeProof>>;
+
+    /// Proof of the fill with `index` against the current fill root
+    #[method(name = "fillProof")]
+    fn fill_proof(&self, index: u32) -> RpcResult<Option<TreeProof>>;
+
+    /// Current roots of the order and fill trees
+    #[method(name = "orderRoots")]
+    fn order_roots(&self) -> RpcResult<OrderRoots>;
+
+    /// How far block `number` has made it to DA, `null` if it was not queued yet
+    #[method(name = "daStatus")]
+    fn da_status(&self, number: u64) -> RpcResult<Option<BlockDAStatus>>;
+}
+
+/// Serves the `reimann_` namespace from the data kept by the node's ExExes, either of which
+/// may be off
+pub struct ReimannRpc {
+    orders: Option<Arc<RwLock<OrderIndex>>>,
+    da_status: Option<Arc<RwLock<DAStatus>>>,
+}
+
+impl ReimannRpc {
+    pub fn new(
+        orders: Option<Arc<RwLock<OrderIndex>>>,
+        da_status: Option<Arc<RwLock<DAStatus>>>,
+    ) -> Self {
+        Self { orders, da_status }
+    }
+
+    fn orders(&self) -> RpcResult<&RwLock<OrderIndex>> {
+        self.orders.as_deref().ok_or_else(|| {
+            ErrorObjectOwned::owned(
+                NOT_INDEXED,
+                "Orders are not indexed, start the node with --orders.settler",
+                None::<()>,
+            )
+        })
+    }
+}
+
+impl ReimannApiServer for ReimannRpc {
+    fn get_order(&self, hash: B256) -> RpcResult<Option<Order>> {
+        Ok(self.orders()?.read().order(&hash).cloned())
+    }
+
+    fn get_fill(&self, hash: B256) -> RpcResult<Option<Fill>> {
+        Ok(self.orders()?.read().fill(&hash).cloned())
+    }
+
+    fn unexpired_orders(&self, destination: U256) -> RpcResult<Vec<Order>> {
+        Ok(self.orders()?.read().unexpired_orders(destination))
+    }
+
+    fn order_proof(&self, nonce: u32) -> RpcResult<Option<TreeProof>> {
+        Ok(self.orders()?.read().order_proof(nonce))
+    }
+
+    fn fill_proof(&self, index: u32) -> RpcResult<Option<TreeProof>> {
+        Ok(self.orders()?.read().fill_proof(index))
+    }
+
+    fn order_roots(&self) -> RpcResult<OrderRoots> {
+        let orders = self.orders()?.read();
+        Ok(OrderRoots {
+            block_number: orders.height(),
+            order_root: orders.order_root(),
+            order_count: orders.order_count(),
+            fill_root: orders.fill_root(),
+            fill_count: orders.fill_count(),
+        })
+    }
+
+    fn da_status(&self, number: u64) -> RpcResult<Option<BlockDAStatus>> {
+        let da_status = self.da_status.as_ref().ok_or_else(|| {
+            ErrorObjectOwned::owned(NOT_INDEXED, "DA submission is disabled", None::<()>)
+        })?;
+        Ok(da_status.read().block(number))
+    }
+}
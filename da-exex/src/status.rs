@@ -0,0 +1,190 @@
+use alloy::primitives::B256;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Batches whose status is kept once acknowledged, older ones only count as acknowledged
+const ACKNOWLEDGED_HISTORY: usize = 1024;
+
+/// How far a block has made it to DA
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DAStage {
+    /// In a batch waiting to be submitted or in flight
+    Queued,
+    /// Stored by the DA server, waiting for its commitment to be posted to Nexus
+    Stored,
+    /// On DA and, when Nexus posting is on, on Nexus, reth may prune it
+    Acknowledged,
+}
+
+/// The batch a block was submitted in
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchStatus {
+    pub from_block: u64,
+    pub to_block: u64,
+    pub stage: DAStage,
+    pub commitment: Option<B256>,
+    /// Failed submissions or Nexus posts in a row at the current stage, which are retried
+    pub failures: u32,
+    /// Error of the last failed attempt, cleared once the batch moves on
+    pub error: Option<String>,
+}
+
+/// The DA status of a block
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockDAStatus {
+    pub block_number: u64,
+    pub stage: DAStage,
+    /// The batch holding the block, unknown for blocks acknowledged before the node started
+    /// or long enough ago
+    pub batch: Option<BatchStatus>,
+}
+
+/// Tracks the batches of the DA ExEx so the node can report where blocks are at
+#[derive(Debug, Default)]
+pub struct DAStatus {
+    /// Batches by first block
+    batches: BTreeMap<u64, BatchStatus>,
+    /// Every block up to this one is acknowledged
+    acknowledged: Option<u64>,
+}
+
+impl DAStatus {
+    /// Records the checkpoint the ExEx started from
+    pub fn start_from(&mut self, acknowledged: u64) {
+        self.acknowledged = Some(acknowledged);
+    }
+
+    pub fn queued(&mut self, from_block: u64, to_block: u64) {
+        self.batches.insert(
+            from_block,
+            BatchStatus {
+                from_block,
+                to_block,
+                stage: DAStage::Queued,
+                commitment: None,
+                failures: 0,
+                error: None,
+            },
+        );
+    }
+
+    /// Forgets a batch dropped before it was acknowledged
+    pub fn unqueued(&mut self, from_block: u64) {
+        self.batches.remove(&from_block);
+    }
+
+    /// Records a failed attempt to move a batch to its next stage
+    pub fn failed(&mut self, from_block: u64, failures: u32, error: String) {
+        if let Some(batch) = self.batches.get_mut(&from_block) {
+            batch.failures = failures;
+            batch.error = Some(error);
+        }
+    }
+
+    pub fn stored(&mut self, from_block: u64, commitment: B256) {
+        if let Some(batch) = self.batches.get_mut(&from_block) {
+            batch.stage = DAStage::Stored;
+            batch.commitment = Some(commitment);
+            batch.failures = 0;
+            batch.error = None;
+        }
+    }
+
+    pub fn acknowledged(&mut self, from_block: u64, to_block: u64) {
+        if let Some(batch) = self.batches.get_mut(&from_block) {
+            batch.stage = DAStage::Acknowledged;
+            batch.failures = 0;
+            batch.error = None;
+        }
+        self.acknowledged = Some(to_block);
+        // batches are acknowledged in order, so everything before them is too
+        while self.batches.len() > ACKNOWLEDGED_HISTORY {
+            match self.batches.first_entry() {
+                Some(batch) if batch.get().stage == DAStage::Acknowledged => {
+                    batch.remove();
+                }
+                _ => break,
+            }
+        }
+    }
+
+    /// Where block `number` is at, `None` if it was not queued yet
+    pub fn block(&self, number: u64) -> Option<BlockDAStatus> {
+        let batch = self
+            .batches
+            .range(..=number)
+            .next_back()
+            .map(|(_, batch)| batch)
+            .filter(|batch| batch.to_block >= number);
+        match batch {
+            Some(batch) => Some(BlockDAStatus {
+                block_number: number,
+                stage: batch.stage,
+                batch: Some(batch.clone()),
+            }),
+            None if self
+                .acknowledged
+                .is_some_and(|acknowledged| number <= acknowledged) =>
+            {
+                Some(BlockDAStatus {
+                    block_number: number,
+                    stage: DAStage::Acknowledged,
+                    batch: None,
+                })
+            }
+            None => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_block_status() {
+        let mut status = DAStatus::default();
+        status.start_from(10);
+        status.queued(11, 20);
+        status.queued(21, 25);
+        assert_eq!(status.block(5).unwrap().stage, DAStage::Acknowledged);
+        assert!(status.block(5).unwrap().batch.is_none());
+        assert_eq!(status.block(11).unwrap().stage, DAStage::Queued);
+        assert!(status.block(26).is_none());
+
+        status.failed(11, 2, "connection refused".into());
+        let batch = status.block(11).unwrap().batch.unwrap();
+        assert_eq!(batch.failures, 2);
+        assert_eq!(batch.error.as_deref(), Some("connection refused"));
+
+        let commitment = B256::with_last_byte(1);
+        status.stored(11, commitment);
+        let block = status.block(20).unwrap();
+        assert_eq!(block.stage, DAStage::Stored);
+        let batch = block.batch.unwrap();
+        assert_eq!(batch.commitment, Some(commitment));
+        assert_eq!(batch.failures, 0);
+        assert_eq!(status.block(21).unwrap().stage, DAStage::Queued);
+
+        status.acknowledged(11, 20);
+        assert_eq!(status.block(15).unwrap().stage, DAStage::Acknowledged);
+        assert_eq!(status.block(25).unwrap().stage, DAStage::Queued);
+    }
+
+    #[test]
+    fn test_history_is_bounded() {
+        let mut status = DAStatus::default();
+        for number in 1..=ACKNOWLEDGED_HISTORY as u64 + 10 {
+            status.queued(number, number);
+            status.acknowledged(number, number);
+        }
+        assert_eq!(status.batches.len(), ACKNOWLEDGED_HISTORY);
+        // pruned batches are still acknowledged
+        let block = status.block(1).unwrap();
+        assert_eq!(block.stage, DAStage::Acknowledged);
+        assert!(block.batch.is_none());
+    }
+}
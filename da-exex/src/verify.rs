@@ -0,0 +1,816 @@
+use crate::{
+    args::VerifyConfig,
+    checkpoint::CheckpointFile,
+    nexus::{fetch_batch, DACommitmentRecord, NexusDACommitments},
+    replay::{Replayer, ReplayerSnapshot},
+    retry::{backoff, PERSISTENT_FAILURES},
+};
+use alloy::{
+    consensus::Transaction as _,
+    eips::BlockNumHash,
+    primitives::{Address, Bytes, B256, U256},
+    sol,
+    sol_types::{SolCall, SolEvent},
+};
+use parking_lot::Mutex;
+use reqwest::Url;
+use reth_primitives::{Receipt, SealedBlockWithSenders};
+use reth_tracing::tracing::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{hash_map::Entry, BTreeMap, BTreeSet, HashMap, VecDeque},
+    io::Write,
+    path::PathBuf,
+    sync::Arc,
+    time::Duration,
+};
+use tokio::{sync::mpsc, time::Instant};
+
+// The state root event of `NexusSettler`
+sol!(
+    interface NexusRoots {
+        event StateRootUpdated(uint256 indexed chainId, bytes32 stateRoot);
+    }
+);
+
+/// Nexus blocks between snapshots of the verifier
+const SNAPSHOT_INTERVAL: u64 = 1000;
+
+/// Distinct replayed states whose roots recorded roots are matched against, the oldest are
+/// forgotten past it
+const ROOT_HISTORY: usize = 65536;
+
+/// A state root `NexusSettler` recorded for a rollup
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RootClaim {
+    pub chain_id: u64,
+    pub state_root: B256,
+    pub nexus_block: u64,
+    /// Timestamp of the Nexus block, the root is the state of a rollup block no later than it
+    pub timestamp: u64,
+}
+
+/// What a Nexus block recorded about the rollups, or a reorg of Nexus
+#[derive(Debug, Clone)]
+pub enum NexusEvent {
+    Commitment {
+        chain_id: u64,
+        nexus_block: u64,
+        record: DACommitmentRecord,
+    },
+    StateRoot(RootClaim),
+    /// Blocks after this one were reverted
+    Reverted(u64),
+    /// Every event of the blocks up to this one was sent
+    Handled(BlockNumHash),
+}
+
+/// Reads the `DACommitmentPosted` and `StateRootUpdated` logs `settler` emitted in a block
+///
+/// Receipts are those of the block's transactions, in order. Commitments are only read from
+/// transactions sent straight to the settler, whose sender is the poster the settler recorded.
+pub fn nexus_events<'a>(
+    block: &SealedBlockWithSenders,
+    receipts: impl IntoIterator<Item = Option<&'a Receipt>>,
+    settler: Address,
+) -> Vec<NexusEvent> {
+    let mut events = Vec::new();
+    for ((sender, transaction), receipt) in block.transactions_with_sender().zip(receipts) {
+        let Some(receipt) = receipt.filter(|receipt| receipt.success) else {
+            continue;
+        };
+        let direct = transaction.to() == Some(settler)
+            && transaction
+                .input()
+                .starts_with(&NexusDACommitments::postDACommitmentCall::SELECTOR);
+        for log in receipt.logs.iter().filter(|log| log.address == settler) {
+            if let Ok(event) = NexusRoots::StateRootUpdated::decode_log_data(&log.data, true) {
+                let Ok(chain_id) = u64::try_from(event.chainId) else {
+                    continue;
+                };
+                events.push(NexusEvent::StateRoot(RootClaim {
+                    chain_id,
+                    state_root: event.stateRoot,
+                    nexus_block: block.number,
+                    timestamp: block.timestamp,
+                }));
+            } else if let Ok(event) =
+                NexusDACommitments::DACommitmentPosted::decode_log_data(&log.data, true)
+            {
+                let Ok(chain_id) = u64::try_from(event.chainId) else {
+                    continue;
+                };
+                if !direct {
+                    continue;
+                }
+                events.push(NexusEvent::Commitment {
+                    chain_id,
+                    nexus_block: block.number,
+                    record: DACommitmentRecord {
+                        index: event.index.saturating_to(),
+                        from_block: event.fromBlock,
+                        to_block: event.toBlock,
+                        commitment: event.commitment,
+                        poster: *sender,
+                        timestamp: U256::from(block.timestamp),
+                    },
+                });
+            }
+        }
+    }
+    events
+}
+
+/// What checking a recorded root found
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Settled {
+    /// A replayed block left the root
+    Verified { claim: RootClaim, block_number: u64 },
+    /// DA carries blocks past the claim and none of them left the root
+    Unknown {
+        claim: RootClaim,
+        replayed_block: u64,
+    },
+}
+
+/// Matches the state roots a rollup's DA batches produce with the ones Nexus recorded
+///
+/// A recorded root is the state of a rollup block built before the Nexus block recording it,
+/// so once DA carries a rollup block timestamped after that Nexus block, every candidate block
+/// was replayed and a root none of them left is not the rollup's. Only the last
+/// [`ROOT_HISTORY`] roots are kept, so an older one recorded that late is unknown too.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RootLedger {
+    /// First block that left each state root, the genesis included
+    computed: HashMap<B256, u64>,
+    /// The keys of `computed`, oldest first
+    computed_order: VecDeque<B256>,
+    /// Number and timestamp of the last replayed block
+    replayed: (u64, u64),
+    /// Recorded roots no replayed block left yet
+    pending: Vec<RootClaim>,
+}
+
+impl RootLedger {
+    /// Records the state root recomputed after a block
+    pub fn replayed(&mut self, number: u64, timestamp: u64, state_root: B256) {
+        if let Entry::Vacant(entry) = self.computed.entry(state_root) {
+            entry.insert(number);
+            self.computed_order.push_back(state_root);
+        }
+        while self.computed_order.len() > ROOT_HISTORY {
+            let oldest = self
+                .computed_order
+                .pop_front()
+                .expect("longer than the history");
+            self.computed.remove(&oldest);
+        }
+        self.replayed = (number, timestamp);
+    }
+
+    pub fn claimed(&mut self, claim: RootClaim) {
+        self.pending.push(claim);
+    }
+
+    /// Forgets the roots recorded by reverted Nexus blocks
+    pub fn revert_claims(&mut self, nexus_block: u64) {
+        self.pending
+            .retain(|claim| claim.nexus_block <= nexus_block);
+    }
+
+    /// Checks the pending roots against the replayed blocks, returning those that are settled
+    pub fn settle(&mut self) -> Vec<Settled> {
+        let mut settled = Vec::new();
+        self.pending.retain(|claim| {
+            if let Some(&block_number) = self.computed.get(&claim.state_root) {
+                settled.push(Settled::Verified {
+                    claim: *claim,
+                    block_number,
+                });
+                return false;
+            }
+            let (replayed_block, replayed_timestamp) = self.replayed;
+            if replayed_timestamp > claim.timestamp {
+                settled.push(Settled::Unknown {
+                    claim: *claim,
+                    replayed_block,
+                });
+                return false;
+            }
+            true
+        });
+        settled
+    }
+}
+
+/// Something the verifier found wrong with a rollup
+#[derive(Debug, Clone, Serialize)]
+#[serde(
+    tag = "kind",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum Alert {
+    /// Nexus recorded a state root that no block of the rollup's DA batches leaves
+    UnknownStateRoot {
+        chain_id: u64,
+        state_root: B256,
+        nexus_block: u64,
+        /// Last block replayed when the root was given up on
+        replayed_block: u64,
+    },
+    /// Blocks of a DA batch name state roots that replaying them does not give
+    ///
+    /// Carries the batch as posted, which hashes to the commitment recorded on Nexus, and the
+    /// header and both roots of each wrong block, for an operator to check. Nothing on Nexus
+    /// takes them as a dispute.
+    HeaderMismatch {
+        chain_id: u64,
+        index: u64,
+        from_block: u64,
+        to_block: u64,
+        commitment: B256,
+        batch: Bytes,
+        blocks: Vec<Mismatch>,
+    },
+    /// A posted batch could not be fetched or replayed
+    ///
+    /// A batch that cannot be fetched is retried until it can, and the batches posted after it
+    /// wait for it. One that cannot be replayed is skipped.
+    BadBatch {
+        chain_id: u64,
+        index: u64,
+        commitment: B256,
+        error: String,
+    },
+}
+
+/// A block whose header names a state root replaying it does not give
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Mismatch {
+    pub block_number: u64,
+    pub block_hash: B256,
+    /// RLP encoded header, batches of state diffs carry none
+    pub header: Option<Bytes>,
+    pub posted_root: B256,
+    pub computed_root: B256,
+}
+
+/// What the verifier knows as of a Nexus block, persisted so a restarted node only reads the
+/// Nexus blocks after it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifierSnapshot {
+    pub nexus_block: u64,
+    pub nexus_hash: B256,
+    rollups: BTreeMap<u64, RollupSnapshot>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RollupSnapshot {
+    replayer: ReplayerSnapshot,
+    ledger: RootLedger,
+    waiting: VecDeque<(u64, DACommitmentRecord)>,
+}
+
+/// Where alerts go besides the log: a JSON line each in a file and, if set, a webhook
+struct Alerts {
+    path: PathBuf,
+    webhook: Option<Url>,
+    client: reqwest::Client,
+}
+
+impl Alerts {
+    async fn raise(&self, alert: Alert) {
+        error!(?alert, "Rollup verification failed");
+        let line = serde_json::to_string(&alert).expect("alerts serialize to JSON");
+        let written = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut file| writeln!(file, "{}", line));
+        if let Err(e) = written {
+            warn!(path = %self.path.display(), "Failed to record alert: {:?}", e);
+        }
+        if let Some(webhook) = &self.webhook {
+            let sent = self
+                .client
+                .post(webhook.clone())
+                .json(&alert)
+                .send()
+                .await
+                .and_then(|response| response.error_for_status());
+            if let Err(e) = sent {
+                warn!(%webhook, "Failed to post alert: {:?}", e);
+            }
+        }
+    }
+}
+
+/// A rollup replayed from the batches posted for it
+struct RollupVerifier {
+    /// Shared with the blocking thread replaying a batch
+    replayer: Arc<Mutex<Replayer>>,
+    ledger: RootLedger,
+    /// Only batches posted by this address are replayed
+    poster: Address,
+    /// Batches posted but not replayed yet, with the Nexus block that posted them, in posting
+    /// order
+    waiting: VecDeque<(u64, DACommitmentRecord)>,
+    /// Set while the first waiting batch cannot be fetched
+    retry: Option<Retry>,
+}
+
+impl RollupVerifier {
+    /// Forgets the roots and batches recorded by Nexus blocks after `nexus_block`
+    ///
+    /// Replayed batches stay, their data is on DA whether or not Nexus says so.
+    fn revert(&mut self, nexus_block: u64) {
+        self.ledger.revert_claims(nexus_block);
+        self.waiting
+            .retain(|(posted_in, _)| *posted_in <= nexus_block);
+        // batches are posted in Nexus block order, so a failing first one only goes with the rest
+        if self.waiting.is_empty() {
+            self.retry = None;
+        }
+    }
+}
+
+/// When the batch that could not be fetched is tried again
+#[derive(Debug, Clone, Copy)]
+struct Retry {
+    failures: u32,
+    at: Instant,
+}
+
+/// Replays the DA batches posted to Nexus for each rollup and checks the state roots posted
+/// in their headers and recorded by `NexusSettler` against the replayed state
+pub struct Verifier {
+    client: reqwest::Client,
+    da_url: String,
+    retry_delay: Duration,
+    rollups: BTreeMap<u64, RollupVerifier>,
+    alerts: Alerts,
+    snapshots: CheckpointFile<VerifierSnapshot>,
+    /// Nexus block of the last snapshot, taken or resumed from
+    snapshotted: u64,
+}
+
+impl Verifier {
+    /// Sets up a verifier for the configured rollups, resuming from `snapshot` if it was taken
+    /// of the same rollups
+    pub fn new(
+        config: VerifyConfig,
+        alerts_path: PathBuf,
+        snapshots: CheckpointFile<VerifierSnapshot>,
+        snapshot: Option<VerifierSnapshot>,
+    ) -> Self {
+        let mut snapshot = snapshot.filter(|snapshot| {
+            let chain_ids = config
+                .rollups
+                .iter()
+                .map(|genesis| genesis.config.chain_id)
+                .collect::<BTreeSet<_>>();
+            let same_rollups = snapshot.rollups.keys().copied().eq(chain_ids);
+            if !same_rollups {
+                warn!(
+                    nexus_block = snapshot.nexus_block,
+                    "Verifier snapshot is of other rollups, replaying every batch"
+                );
+            }
+            same_rollups
+        });
+        let rollups = config
+            .rollups
+            .into_iter()
+            .map(|genesis| {
+                let chain_id = genesis.config.chain_id;
+                let saved = snapshot
+                    .as_mut()
+                    .and_then(|snapshot| snapshot.rollups.remove(&chain_id));
+                let (replayer, ledger, waiting) = match saved {
+                    Some(saved) => (
+                        Replayer::restore(genesis, &saved.replayer),
+                        saved.ledger,
+                        saved.waiting,
+                    ),
+                    None => {
+                        let mut replayer = Replayer::new(genesis);
+                        let mut ledger = RootLedger::default();
+                        ledger.replayed(0, 0, replayer.state_root());
+                        (replayer, ledger, VecDeque::new())
+                    }
+                };
+                let rollup = RollupVerifier {
+                    replayer: Arc::new(Mutex::new(replayer)),
+                    ledger,
+                    poster: config.posters[&chain_id],
+                    waiting,
+                    retry: None,
+                };
+                (chain_id, rollup)
+            })
+            .collect();
+        let client = reqwest::Client::new();
+        Self {
+            client: client.clone(),
+            da_url: config.da_url,
+            retry_delay: config.retry_delay,
+            rollups,
+            alerts: Alerts {
+                path: alerts_path,
+                webhook: config.webhook,
+                client,
+            },
+            snapshots,
+            snapshotted: snapshot.map_or(0, |snapshot| snapshot.nexus_block),
+        }
+    }
+
+    /// The Nexus block the verifier resumed from, whose events it must not be sent again
+    pub fn resumed_from(&self) -> u64 {
+        self.snapshotted
+    }
+
+    pub fn chain_ids(&self) -> Vec<u64> {
+        self.rollups.keys().copied().collect()
+    }
+
+    /// Handles Nexus events in order until the sender is dropped, retrying batches that could
+    /// not be fetched in between
+    pub async fn run(mut self, mut events: mpsc::UnboundedReceiver<NexusEvent>) {
+        loop {
+            let next_retry = self
+                .rollups
+                .values()
+                .filter_map(|rollup| rollup.retry.map(|retry| retry.at))
+                .min();
+            let retry_due = tokio::time::sleep_until(next_retry.unwrap_or_else(Instant::now));
+            let event = tokio::select! {
+                event = events.recv() => event,
+                _ = retry_due, if next_retry.is_some() => {
+                    for chain_id in self.chain_ids() {
+                        self.replay_waiting(chain_id).await;
+                        self.settle(chain_id).await;
+                    }
+                    continue;
+                }
+            };
+            let Some(event) = event else {
+                break;
+            };
+            match event {
+                NexusEvent::Commitment {
+                    chain_id,
+                    nexus_block,
+                    record,
+                } => {
+                    let Some(rollup) = self.rollups.get_mut(&chain_id) else {
+                        continue;
+                    };
+                    if rollup.poster != record.poster {
+                        warn!(chain_id, index = record.index, poster = %record.poster, "Ignoring DA commitment from an untrusted poster");
+                        continue;
+                    }
+                    rollup.waiting.push_back((nexus_block, record));
+                    self.replay_waiting(chain_id).await;
+                    self.settle(chain_id).await;
+                }
+                NexusEvent::StateRoot(claim) => {
+                    if let Some(rollup) = self.rollups.get_mut(&claim.chain_id) {
+                        rollup.ledger.claimed(claim);
+                        self.settle(claim.chain_id).await;
+                    }
+                }
+                NexusEvent::Reverted(nexus_block) => {
+                    for rollup in self.rollups.values_mut() {
+                        rollup.revert(nexus_block);
+                    }
+                }
+                NexusEvent::Handled(nexus_block) => {
+                    if nexus_block.number >= self.snapshotted + SNAPSHOT_INTERVAL {
+                        self.save_snapshot(nexus_block).await;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Replays the waiting batches of a rollup in order, stopping at one that cannot be
+    /// fetched until its retry is due
+    async fn replay_waiting(&mut self, chain_id: u64) {
+        let Some(rollup) = self.rollups.get_mut(&chain_id) else {
+            return;
+        };
+        while let Some((_, record)) = rollup.waiting.front().cloned() {
+            if rollup.retry.is_some_and(|retry| retry.at > Instant::now()) {
+                return;
+            }
+            let data = match fetch_batch(&self.client, &self.da_url, chain_id, record.commitment)
+                .await
+            {
+                Ok(data) => data,
+                Err(e) => {
+                    let failures = rollup.retry.map_or(1, |retry| retry.failures + 1);
+                    let delay = backoff(self.retry_delay, failures);
+                    warn!(chain_id, index = record.index, commitment = %record.commitment, failures, ?delay, "Fetching DA batch failed, retrying: {:?}", e);
+                    rollup.retry = Some(Retry {
+                        failures,
+                        at: Instant::now() + delay,
+                    });
+                    if failures == PERSISTENT_FAILURES {
+                        self.alerts
+                            .raise(Alert::BadBatch {
+                                chain_id,
+                                index: record.index,
+                                commitment: record.commitment,
+                                error: format!("{:?}", e),
+                            })
+                            .await;
+                    }
+                    return;
+                }
+            };
+            if let Some(retry) = rollup.retry.take() {
+                info!(
+                    chain_id,
+                    index = record.index,
+                    failures = retry.failures,
+                    "Fetched DA batch, resuming verification"
+                );
+            }
+            rollup.waiting.pop_front();
+
+            // recomputing state roots is CPU bound, so it runs on a blocking thread
+            let replayer = Arc::clone(&rollup.replayer);
+            let replay = tokio::task::spawn_blocking(move || {
+                let mut replayer = replayer.lock();
+                let mut roots = Vec::new();
+                let mut blocks = Vec::new();
+                let replayed = replayer.apply_batch(&data, |replayer, block| {
+                    let computed_root = replayer.state_root();
+                    roots.push((block.number, block.timestamp, computed_root));
+                    if computed_root != block.posted_root {
+                        blocks.push(Mismatch {
+                            block_number: block.number,
+                            block_hash: block.hash,
+                            header: block
+                                .header
+                                .as_ref()
+                                .map(|header| alloy_rlp::encode(header).into()),
+                            posted_root: block.posted_root,
+                            computed_root,
+                        });
+                    }
+                    Ok(())
+                });
+                (data, replayer.head(), roots, blocks, replayed)
+            })
+            .await;
+            let (data, head, roots, blocks, replayed) = match replay {
+                Ok(replay) => replay,
+                Err(e) => {
+                    self.alerts
+                        .raise(Alert::BadBatch {
+                            chain_id,
+                            index: record.index,
+                            commitment: record.commitment,
+                            error: format!("Replay panicked: {:?}", e),
+                        })
+                        .await;
+                    continue;
+                }
+            };
+            for (number, timestamp, state_root) in roots {
+                rollup.ledger.replayed(number, timestamp, state_root);
+            }
+            let mut alerts = Vec::new();
+            if !blocks.is_empty() {
+                alerts.push(Alert::HeaderMismatch {
+                    chain_id,
+                    index: record.index,
+                    from_block: record.from_block,
+                    to_block: record.to_block,
+                    commitment: record.commitment,
+                    batch: Bytes::from(data),
+                    blocks,
+                });
+            }
+            if let Err(e) = replayed {
+                // later batches may replace the blocks, as after a reorg of the rollup
+                alerts.push(Alert::BadBatch {
+                    chain_id,
+                    index: record.index,
+                    commitment: record.commitment,
+                    error: format!("{:?}", e),
+                });
+            } else if alerts.is_empty() {
+                info!(
+                    chain_id,
+                    index = record.index,
+                    block_number = head,
+                    "Replayed DA batch"
+                );
+            }
+            for alert in alerts {
+                self.alerts.raise(alert).await;
+            }
+        }
+    }
+
+    /// Snapshots every rollup as of `nexus_block`, on a blocking thread since it holds their
+    /// whole state
+    async fn save_snapshot(&mut self, nexus_block: BlockNumHash) {
+        let rollups = self
+            .rollups
+            .iter()
+            .map(|(chain_id, rollup)| {
+                let saved = (
+                    Arc::clone(&rollup.replayer),
+                    rollup.ledger.clone(),
+                    rollup.waiting.clone(),
+                );
+                (*chain_id, saved)
+            })
+            .collect::<Vec<_>>();
+        let file = self.snapshots.clone();
+        let saved = tokio::task::spawn_blocking(move || {
+            let rollups = rollups
+                .into_iter()
+                .map(|(chain_id, (replayer, ledger, waiting))| {
+                    let replayer = replayer.lock().snapshot();
+                    let rollup = RollupSnapshot {
+                        replayer,
+                        ledger,
+                        waiting,
+                    };
+                    (chain_id, rollup)
+                })
+                .collect();
+            file.save(&VerifierSnapshot {
+                nexus_block: nexus_block.number,
+                nexus_hash: nexus_block.hash,
+                rollups,
+            })
+        })
+        .await
+        .map_err(eyre::Error::from)
+        .and_then(|saved| saved);
+        match saved {
+            Ok(()) => self.snapshotted = nexus_block.number,
+            Err(e) => warn!(
+                nexus_block = nexus_block.number,
+                "Failed to save verifier snapshot: {:?}", e
+            ),
+        }
+    }
+
+    async fn settle(&mut self, chain_id: u64) {
+        let Some(rollup) = self.rollups.get_mut(&chain_id) else {
+            return;
+        };
+        for settled in rollup.ledger.settle() {
+            match settled {
+                Settled::Verified {
+                    claim,
+                    block_number,
+                } => {
+                    info!(chain_id, state_root = %claim.state_root, nexus_block = claim.nexus_block, block_number, "Verified recorded state root");
+                }
+                Settled::Unknown {
+                    claim,
+                    replayed_block,
+                } => {
+                    self.alerts
+                        .raise(Alert::UnknownStateRoot {
+                            chain_id,
+                            state_root: claim.state_root,
+                            nexus_block: claim.nexus_block,
+                            replayed_block,
+                        })
+                        .await;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn claim(state_root: u8, nexus_block: u64, timestamp: u64) -> RootClaim {
+        RootClaim {
+            chain_id: 31338,
+            state_root: B256::with_last_byte(state_root),
+            nexus_block,
+            timestamp,
+        }
+    }
+
+    #[test]
+    fn test_settle_claims() {
+        let mut ledger = RootLedger::default();
+        ledger.replayed(0, 0, B256::with_last_byte(1));
+        ledger.replayed(1, 10, B256::with_last_byte(2));
+
+        // the genesis root and a root DA has not reached yet
+        ledger.claimed(claim(1, 5, 12));
+        ledger.claimed(claim(3, 6, 14));
+        assert_eq!(
+            ledger.settle(),
+            vec![Settled::Verified {
+                claim: claim(1, 5, 12),
+                block_number: 0
+            }]
+        );
+
+        // a block at the claim's time may still leave it
+        ledger.replayed(2, 14, B256::with_last_byte(4));
+        assert!(ledger.settle().is_empty());
+        ledger.replayed(3, 16, B256::with_last_byte(3));
+        assert_eq!(
+            ledger.settle(),
+            vec![Settled::Verified {
+                claim: claim(3, 6, 14),
+                block_number: 3
+            }]
+        );
+
+        // once DA is past the claim, a root no block left is unknown
+        ledger.claimed(claim(9, 7, 15));
+        assert_eq!(
+            ledger.settle(),
+            vec![Settled::Unknown {
+                claim: claim(9, 7, 15),
+                replayed_block: 3
+            }]
+        );
+        assert!(ledger.settle().is_empty());
+    }
+
+    #[test]
+    fn test_revert_waiting_batches() {
+        let record = |index| DACommitmentRecord {
+            index,
+            from_block: index,
+            to_block: index,
+            commitment: B256::with_last_byte(index as u8),
+            poster: Address::ZERO,
+            timestamp: U256::ZERO,
+        };
+        let mut rollup = RollupVerifier {
+            replayer: Arc::new(Mutex::new(Replayer::new(Default::default()))),
+            ledger: RootLedger::default(),
+            poster: Address::ZERO,
+            waiting: VecDeque::from([(5, record(0)), (6, record(1))]),
+            retry: Some(Retry {
+                failures: 1,
+                at: Instant::now(),
+            }),
+        };
+        rollup.revert(5);
+        assert_eq!(rollup.waiting.len(), 1);
+        assert!(rollup.retry.is_some());
+        // the batch that failed to fetch was reverted too
+        rollup.revert(4);
+        assert!(rollup.waiting.is_empty());
+        assert!(rollup.retry.is_none());
+    }
+
+    #[test]
+    fn test_root_history_is_bounded() {
+        let mut ledger = RootLedger::default();
+        for number in 0..ROOT_HISTORY as u64 + 10 {
+            ledger.replayed(number, number, B256::from(U256::from(number)));
+        }
+        assert_eq!(ledger.computed.len(), ROOT_HISTORY);
+        // the first roots were forgotten, so recording them now gives them up
+        ledger.claimed(claim(0, 7, 5));
+        assert!(matches!(
+            ledger.settle().as_slice(),
+            [Settled::Unknown { .. }]
+        ));
+    }
+
+    #[test]
+    fn test_revert_claims() {
+        let mut ledger = RootLedger::default();
+        ledger.replayed(0, 0, B256::with_last_byte(1));
+        ledger.claimed(claim(5, 7, 20));
+        ledger.claimed(claim(6, 8, 22));
+        ledger.revert_claims(7);
+        ledger.replayed(1, 30, B256::with_last_byte(2));
+        assert_eq!(
+            ledger.settle(),
+            vec![Settled::Unknown {
+                claim: claim(5, 7, 20),
+                replayed_block: 1
+            }]
+        );
+    }
+}
@@ -0,0 +1,98 @@
+use alloy_primitives::{Address, PrimitiveSignature};
+pub use da_auth::{signing_digest, SIGNATURE_HEADER};
+use std::collections::{HashMap, HashSet};
+
+/// Sequencers allowed to submit blobs, per rollup chain id
+#[derive(Debug, Default)]
+pub struct Sequencers(HashMap<u64, HashSet<Address>>);
+
+impl Sequencers {
+    /// Parses a comma separated list of `<chain id>=<address>` entries, a chain id may be
+    /// repeated to allow several sequencers
+    pub fn parse(entries: &str) -> Result<Self, String> {
+        let mut sequencers = Self::default();
+        for entry in entries.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (chain_id, address) = entry
+                .split_once('=')
+                .ok_or_else(|| format!("Expected <chain id>=<address>, got {}", entry))?;
+            let chain_id = chain_id
+                .trim()
+                .parse()
+                .map_err(|_| format!("Invalid chain id in {}", entry))?;
+            let address = address
+                .trim()
+                .parse()
+                .map_err(|_| format!("Invalid address in {}", entry))?;
+            sequencers.0.entry(chain_id).or_default().insert(address);
+        }
+        Ok(sequencers)
+    }
+
+    pub fn is_allowed(&self, chain_id: u64, signer: &Address) -> bool {
+        self.0
+            .get(&chain_id)
+            .is_some_and(|signers| signers.contains(signer))
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.values().map(HashSet::len).sum()
+    }
+}
+
+/// Recovers the address that signed `commitment` for `chain_id`
+pub fn recover_signer(
+    chain_id: u64,
+    commitment: &[u8; 32],
+    signature: &str,
+) -> Result<Address, String> {
+    let signature = hex::decode(signature.strip_prefix("0x").unwrap_or(signature))
+        .map_err(|_| "Signature is not valid hex".to_string())?;
+    PrimitiveSignature::from_raw(&signature)
+        .and_then(|signature| {
+            signature.recover_address_from_prehash(&signing_digest(chain_id, commitment))
+        })
+        .map_err(|e| format!("Invalid signature: {}", e))
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use k256::ecdsa::SigningKey;
+
+    /// Signs `commitment` for `chain_id` the way a sequencer does
+    pub fn sign(key: &SigningKey, chain_id: u64, commitment: &[u8; 32]) -> (Address, String) {
+        let (signature, recovery_id) = key
+            .sign_prehash_recoverable(signing_digest(chain_id, commitment).as_slice())
+            .unwrap();
+        let mut bytes = signature.to_bytes().to_vec();
+        bytes.push(27 + recovery_id.to_byte());
+        (
+            Address::from_public_key(key.verifying_key()),
+            format!("0x{}", hex::encode(bytes)),
+        )
+    }
+
+    #[test]
+    fn test_parse_sequencers() {
+        let a = Address::with_last_byte(1);
+        let b = Address::with_last_byte(2);
+        let sequencers =
+            Sequencers::parse(&format!("31338={}, 31338={},31339={}", a, b, a)).unwrap();
+        assert_eq!(sequencers.len(), 3);
+        assert!(sequencers.is_allowed(31338, &b));
+        assert!(!sequencers.is_allowed(31339, &b));
+        assert!(Sequencers::parse("31338").is_err());
+        assert!(Sequencers::parse("chain=0x00").is_err());
+    }
+
+    #[test]
+    fn test_recover_signer() {
+        let key = SigningKey::from_slice(&[7u8; 32]).unwrap();
+        let commitment = [1u8; 32];
+        let (address, signature) = sign(&key, 31338, &commitment);
+        assert_eq!(recover_signer(31338, &commitment, &signature), Ok(address));
+        // the chain id is part of what is signed
+        assert_ne!(recover_signer(31339, &commitment, &signature), Ok(address));
+        assert!(recover_signer(31338, &commitment, "0x1234").is_err());
+    }
+}
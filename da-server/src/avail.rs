@@ -0,0 +1,408 @@
+use crate::store::{BlobStore, Receipt, StoreError};
+use async_trait::async_trait;
+use axum::body::Bytes;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::time::{Duration, Instant};
+
+const HEADER_POLL_INTERVAL: Duration = Duration::from_millis(500);
+const FINALITY_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Inclusion details of a blob submitted to Avail
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AvailReceipt {
+    pub block_number: u32,
+    pub block_hash: String,
+    pub extrinsic_index: u32,
+    pub data_root: String,
+}
+
+/// What the index records for each blob so it can be found again on Avail
+#[derive(Serialize, Deserialize)]
+struct Location {
+    #[serde(flatten)]
+    receipt: AvailReceipt,
+    commitment: String,
+}
+
+#[derive(Deserialize)]
+struct StatusResponse {
+    app_id: Option<u32>,
+    blocks: BlocksStatus,
+}
+
+#[derive(Deserialize)]
+struct BlocksStatus {
+    /// Latest finalized block the light client processed
+    latest: u32,
+}
+
+#[derive(Deserialize)]
+struct SubmitResponse {
+    block_number: u32,
+    block_hash: String,
+    index: u32,
+}
+
+#[derive(Deserialize)]
+struct HeaderResponse {
+    hash: String,
+    extension: HeaderExtension,
+}
+
+#[derive(Deserialize)]
+struct HeaderExtension {
+    data_root: String,
+}
+
+#[derive(Deserialize)]
+struct BlockDataResponse {
+    data_transactions: Vec<DataTransaction>,
+}
+
+#[derive(Deserialize)]
+struct DataTransaction {
+    data: Option<String>,
+}
+
+/// Submits blobs as `submit_data` extrinsics through an Avail light client
+///
+/// The light client signs and submits for the app id it is configured with, and its
+/// `/v2/submit` call only returns once the extrinsic is included in a block. That block may
+/// still be reorged out until it is finalized, which the light client tells as it only
+/// follows finalized blocks.
+pub struct AvailStore {
+    client: reqwest::Client,
+    url: String,
+    /// Records which Avail block each blob went into
+    index: Box<dyn BlobStore>,
+    inclusion_timeout: Duration,
+    finality_timeout: Duration,
+}
+
+impl AvailStore {
+    /// Connects to the light client at `url` and checks it submits for `app_id`
+    pub async fn new(
+        url: String,
+        app_id: u32,
+        index: Box<dyn BlobStore>,
+        inclusion_timeout: Duration,
+        finality_timeout: Duration,
+    ) -> Result<Self, StoreError> {
+        let client = reqwest::Client::builder()
+            .timeout(inclusion_timeout)
+            .build()?;
+        let status = client
+            .get(format!("{}/v2/status", url))
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<StatusResponse>()
+            .await?;
+        if status.app_id != Some(app_id) {
+            return Err(format!(
+                "Avail light client is configured for app id {:?}, expected {}",
+                status.app_id, app_id
+            )
+            .into());
+        }
+        Ok(Self {
+            client,
+            url,
+            index,
+            inclusion_timeout,
+            finality_timeout,
+        })
+    }
+
+    async fn status(&self) -> Result<StatusResponse, StoreError> {
+        Ok(self
+            .client
+            .get(format!("{}/v2/status", self.url))
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<StatusResponse>()
+            .await?)
+    }
+
+    async fn header(&self, block_number: u32) -> Result<HeaderResponse, StoreError> {
+        Ok(self
+            .client
+            .get(format!("{}/v2/blocks/{}/header", self.url, block_number))
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<HeaderResponse>()
+            .await?)
+    }
+
+    /// Waits for the light client to verify the block and returns its data root
+    async fn data_root(&self, block_number: u32) -> Result<String, StoreError> {
+        let deadline = Instant::now() + self.inclusion_timeout;
+        loop {
+            let response = self
+                .client
+                .get(format!("{}/v2/blocks/{}/header", self.url, block_number))
+                .send()
+                .await?;
+            if response.status().is_success() {
+                let header = response.json::<HeaderResponse>().await?;
+                return Ok(header.extension.data_root);
+            }
+            if Instant::now() >= deadline {
+                return Err(format!("Timed out waiting for Avail block {}", block_number).into());
+            }
+            tokio::time::sleep(HEADER_POLL_INTERVAL).await;
+        }
+    }
+}
+
+#[async_trait]
+impl BlobStore for AvailStore {
+    fn name(&self) -> &'static str {
+        "Avail"
+    }
+
+    fn erasure_codes(&self) -> bool {
+        true
+    }
+
+    async fn put(&self, key: &str, data: Bytes) -> Result<Receipt, StoreError> {
+        let commitment = crate::commitment(&data);
+        let submitted = self
+            .client
+            .post(format!("{}/v2/submit", self.url))
+            .json(&json!({ "data": STANDARD.encode(&data) }))
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<SubmitResponse>()
+            .await?;
+        let receipt = AvailReceipt {
+            block_number: submitted.block_number,
+            block_hash: submitted.block_hash,
+            extrinsic_index: submitted.index,
+            data_root: self.data_root(submitted.block_number).await?,
+        };
+        let location = Location {
+            receipt: receipt.clone(),
+            commitment: format!("0x{}", hex::encode(commitment)),
+        };
+        self.index
+            .put(key, Bytes::from(serde_json::to_vec(&location)?))
+            .await?;
+        Ok(Receipt::Avail(receipt))
+    }
+
+    /// Waits for the light client to finalize the block the blob was included in, and checks
+    /// that the finalized block at that height is the same one
+    async fn wait_finalized(&self, receipt: &Receipt) -> Result<(), StoreError> {
+        let Receipt::Avail(receipt) = receipt else {
+            return Ok(());
+        };
+        let deadline = Instant::now() + self.finality_timeout;
+        while self.status().await?.blocks.latest < receipt.block_number {
+            if Instant::now() >= deadline {
+                return Err(format!(
+                    "Timed out waiting for Avail block {} to be finalized",
+                    receipt.block_number
+                )
+                .into());
+            }
+            tokio::time::sleep(FINALITY_POLL_INTERVAL).await;
+        }
+        let finalized = self.header(receipt.block_number).await?.hash;
+        if !finalized.eq_ignore_ascii_case(&receipt.block_hash) {
+            return Err(format!(
+                "Avail block {} was finalized as {}, not as {} the blob was included in",
+                receipt.block_number, finalized, receipt.block_hash
+            )
+            .into());
+        }
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Bytes>, StoreError> {
+        let Some(location) = self.index.get(key).await? else {
+            return Ok(None);
+        };
+        let location: Location = serde_json::from_slice(&location)?;
+        let block_number = location.receipt.block_number;
+        let block = self
+            .client
+            .get(format!(
+                "{}/v2/blocks/{}/data?fields=data",
+                self.url, block_number
+            ))
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<BlockDataResponse>()
+            .await?;
+        // the light client only returns data for its app id, find ours by commitment
+        for transaction in block.data_transactions {
+            let Some(data) = transaction.data else {
+                continue;
+            };
+            let data = STANDARD.decode(data)?;
+            if format!("0x{}", hex::encode(crate::commitment(&data))) == location.commitment {
+                return Ok(Some(Bytes::from(data)));
+            }
+        }
+        Err(format!("{} is missing from Avail block {}", key, block_number).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::MemoryStore;
+    use axum::{
+        extract::{Path, State},
+        routing::{get, post},
+        Json, Router,
+    };
+    use parking_lot::Mutex;
+    use serde_json::Value;
+    use std::sync::Arc;
+
+    /// Each submission to the mock light client lands in a block of its own
+    #[derive(Default)]
+    struct Chain {
+        blocks: Vec<Vec<u8>>,
+        finalized: usize,
+    }
+
+    type Blocks = Arc<Mutex<Chain>>;
+
+    async fn mock_light_client(app_id: u32) -> (String, Blocks) {
+        let blocks = Blocks::default();
+        let app = Router::new()
+            .route(
+                "/v2/status",
+                get(move |State(blocks): State<Blocks>| async move {
+                    let latest = blocks.lock().finalized;
+                    Json(json!({ "app_id": app_id, "blocks": { "latest": latest } }))
+                }),
+            )
+            .route(
+                "/v2/submit",
+                post(
+                    |State(blocks): State<Blocks>, Json(body): Json<Value>| async move {
+                        let data = STANDARD.decode(body["data"].as_str().unwrap()).unwrap();
+                        let blocks = &mut blocks.lock().blocks;
+                        blocks.push(data);
+                        Json(json!({
+                            "block_number": blocks.len(),
+                            "block_hash": format!("0x{:064x}", blocks.len()),
+                            "hash": format!("0x{:064x}", 0),
+                            "index": 1,
+                        }))
+                    },
+                ),
+            )
+            .route(
+                "/v2/blocks/:block_number/header",
+                get(|Path(block_number): Path<usize>| async move {
+                    Json(json!({
+                        "hash": format!("0x{:064x}", block_number),
+                        "number": block_number,
+                        "extension": { "data_root": format!("0x{:064x}", block_number) },
+                    }))
+                }),
+            )
+            .route(
+                "/v2/blocks/:block_number/data",
+                get(
+                    |State(blocks): State<Blocks>, Path(block_number): Path<usize>| async move {
+                        let data = blocks.lock().blocks[block_number - 1].clone();
+                        Json(json!({
+                            "block_number": block_number,
+                            "data_transactions": [{ "data": STANDARD.encode(data) }],
+                        }))
+                    },
+                ),
+            )
+            .with_state(Arc::clone(&blocks));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        (url, blocks)
+    }
+
+    async fn avail_store(url: String, app_id: u32) -> Result<AvailStore, StoreError> {
+        AvailStore::new(
+            url,
+            app_id,
+            Box::<MemoryStore>::default(),
+            Duration::from_secs(5),
+            Duration::from_secs(1),
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_submit_and_retrieve() {
+        let (url, _) = mock_light_client(7).await;
+        let store = avail_store(url, 7).await.unwrap();
+        store
+            .put("blobs/a", Bytes::from_static(b"first"))
+            .await
+            .unwrap();
+        let receipt = match store
+            .put("blobs/b", Bytes::from_static(b"second"))
+            .await
+            .unwrap()
+        {
+            Receipt::Avail(receipt) => receipt,
+            Receipt::Stored => panic!("expected an Avail receipt"),
+        };
+        assert_eq!(receipt.block_number, 2);
+        assert_eq!(receipt.extrinsic_index, 1);
+        assert_eq!(receipt.data_root, format!("0x{:064x}", 2));
+
+        assert_eq!(
+            store.get("blobs/a").await.unwrap(),
+            Some(Bytes::from_static(b"first"))
+        );
+        assert_eq!(
+            store.get("blobs/b").await.unwrap(),
+            Some(Bytes::from_static(b"second"))
+        );
+        assert!(store.get("blobs/c").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_wait_finalized() {
+        let (url, blocks) = mock_light_client(7).await;
+        let store = avail_store(url, 7).await.unwrap();
+        let receipt = store
+            .put("blobs/a", Bytes::from_static(b"first"))
+            .await
+            .unwrap();
+        // included but not finalized
+        assert!(store.wait_finalized(&receipt).await.is_err());
+
+        blocks.lock().finalized = 1;
+        store.wait_finalized(&receipt).await.unwrap();
+
+        // finalized, but not as the block the blob went into
+        let Receipt::Avail(mut replaced) = receipt else {
+            panic!("expected an Avail receipt");
+        };
+        replaced.block_hash = format!("0x{:064x}", 9);
+        assert!(store
+            .wait_finalized(&Receipt::Avail(replaced))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_rejects_wrong_app_id() {
+        let (url, _) = mock_light_client(7).await;
+        assert!(avail_store(url, 8).await.is_err());
+    }
+}
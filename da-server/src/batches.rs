@@ -0,0 +1,190 @@
+use crate::{
+    avail::AvailReceipt,
+    store::{BlobStore, StoreError},
+};
+use alloy_primitives::Address;
+use axum::body::Bytes;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tokio::sync::Mutex;
+
+/// A blob submitted to a chain's namespace, in submission order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchRecord {
+    pub sequence: u64,
+    pub commitment: String,
+    pub length: usize,
+    /// Unix time the submission was stored at
+    pub timestamp: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signer: Option<Address>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub avail: Option<AvailReceipt>,
+}
+
+pub fn blob_key(chain_id: u64, commitment: &[u8; 32]) -> String {
+    format!("chains/{}/blobs/0x{}", chain_id, hex::encode(commitment))
+}
+
+pub fn manifest_key(chain_id: u64, commitment: &[u8; 32]) -> String {
+    format!(
+        "chains/{}/das/0x{}/manifest",
+        chain_id,
+        hex::encode(commitment)
+    )
+}
+
+pub fn chunks_key(chain_id: u64, commitment: &[u8; 32], blob: usize) -> String {
+    format!(
+        "chains/{}/das/0x{}/{}",
+        chain_id,
+        hex::encode(commitment),
+        blob
+    )
+}
+
+/// Record of a blob, looked up by commitment
+pub fn record_key(chain_id: u64, commitment: &[u8; 32]) -> String {
+    format!(
+        "chains/{}/commitments/0x{}",
+        chain_id,
+        hex::encode(commitment)
+    )
+}
+
+/// Record of a blob, looked up by sequence number, zero-padded so keys sort in order
+pub fn sequence_key(chain_id: u64, sequence: u64) -> String {
+    format!("chains/{}/batches/{:020}", chain_id, sequence)
+}
+
+/// Next sequence number of a chain
+fn head_key(chain_id: u64) -> String {
+    format!("chains/{}/head", chain_id)
+}
+
+/// Hands out per-chain sequence numbers and records batches under them
+///
+/// Assignment is serialized so concurrent submissions to a chain get distinct, gapless
+/// sequence numbers. Heads are cached after being read from the index once.
+#[derive(Default)]
+pub struct Sequences {
+    heads: Mutex<HashMap<u64, u64>>,
+}
+
+impl Sequences {
+    /// Number of batches recorded for `chain_id`
+    pub async fn head(&self, index: &dyn BlobStore, chain_id: u64) -> Result<u64, StoreError> {
+        let mut heads = self.heads.lock().await;
+        load_head(&mut heads, index, chain_id).await
+    }
+
+    /// Records a batch under the next sequence number of `chain_id`, or returns the existing
+    /// record if the same blob was already submitted to the chain
+    pub async fn record(
+        &self,
+        index: &dyn BlobStore,
+        chain_id: u64,
+        commitment: &[u8; 32],
+        mut record: BatchRecord,
+    ) -> Result<BatchRecord, StoreError> {
+        let mut heads = self.heads.lock().await;
+        if let Some(existing) = load_record(index, &record_key(chain_id, commitment)).await? {
+            return Ok(existing);
+        }
+        let sequence = load_head(&mut heads, index, chain_id).await?;
+        record.sequence = sequence;
+        let encoded = Bytes::from(serde_json::to_vec(&record)?);
+        index
+            .put(&sequence_key(chain_id, sequence), encoded.clone())
+            .await?;
+        index
+            .put(&record_key(chain_id, commitment), encoded)
+            .await?;
+        index
+            .put(&head_key(chain_id), Bytes::from((sequence + 1).to_string()))
+            .await?;
+        heads.insert(chain_id, sequence + 1);
+        Ok(record)
+    }
+}
+
+async fn load_head(
+    heads: &mut HashMap<u64, u64>,
+    index: &dyn BlobStore,
+    chain_id: u64,
+) -> Result<u64, StoreError> {
+    if let Some(head) = heads.get(&chain_id) {
+        return Ok(*head);
+    }
+    let head = match index.get(&head_key(chain_id)).await? {
+        Some(head) => std::str::from_utf8(&head)?.parse()?,
+        None => 0,
+    };
+    heads.insert(chain_id, head);
+    Ok(head)
+}
+
+pub async fn load_record(
+    index: &dyn BlobStore,
+    key: &str,
+) -> Result<Option<BatchRecord>, StoreError> {
+    match index.get(key).await? {
+        Some(record) => Ok(Some(serde_json::from_slice(&record)?)),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::MemoryStore;
+
+    fn record(commitment: &[u8; 32]) -> BatchRecord {
+        BatchRecord {
+            sequence: 0,
+            commitment: format!("0x{}", hex::encode(commitment)),
+            length: 1,
+            timestamp: 0,
+            signer: None,
+            signature: None,
+            avail: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sequences() {
+        let index = MemoryStore::default();
+        let sequences = Sequences::default();
+        for (i, commitment) in [[1u8; 32], [2u8; 32]].iter().enumerate() {
+            let recorded = sequences
+                .record(&index, 31338, commitment, record(commitment))
+                .await
+                .unwrap();
+            assert_eq!(recorded.sequence, i as u64);
+        }
+        // resubmitting keeps the original sequence number
+        let recorded = sequences
+            .record(&index, 31338, &[1u8; 32], record(&[1u8; 32]))
+            .await
+            .unwrap();
+        assert_eq!(recorded.sequence, 0);
+        // namespaces are numbered independently
+        let recorded = sequences
+            .record(&index, 31339, &[1u8; 32], record(&[1u8; 32]))
+            .await
+            .unwrap();
+        assert_eq!(recorded.sequence, 0);
+
+        // a restarted server picks up where the index left off
+        let sequences = Sequences::default();
+        assert_eq!(sequences.head(&index, 31338).await.unwrap(), 2);
+        let stored = load_record(&index, &sequence_key(31338, 1))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.commitment, record(&[2u8; 32]).commitment);
+    }
+}
@@ -0,0 +1,158 @@
+use c_kzg::{
+    Blob, Bytes48, Cell, KzgProof, KzgSettings, BYTES_PER_BLOB, BYTES_PER_CELL,
+    BYTES_PER_FIELD_ELEMENT, BYTES_PER_PROOF, CELLS_PER_EXT_BLOB, FIELD_ELEMENTS_PER_BLOB,
+};
+use serde::{Deserialize, Serialize};
+
+/// Payload bytes per field element, the leading byte stays zero to keep elements canonical
+const PAYLOAD_BYTES_PER_FIELD_ELEMENT: usize = BYTES_PER_FIELD_ELEMENT - 1;
+/// Payload bytes that fit in one EIP-4844 blob
+pub const PAYLOAD_BYTES_PER_BLOB: usize = FIELD_ELEMENTS_PER_BLOB * PAYLOAD_BYTES_PER_FIELD_ELEMENT;
+/// A stored chunk is the cell followed by its proof
+pub const BYTES_PER_CHUNK: usize = BYTES_PER_CELL + BYTES_PER_PROOF;
+
+/// Describes how a submission was split into erasure-coded blobs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Manifest {
+    /// Length of the original data, before padding
+    pub length: usize,
+    /// KZG commitment of each blob, in order
+    pub commitments: Vec<String>,
+    /// Number of chunks each blob is extended to, any half of which recovers it
+    pub chunks_per_blob: usize,
+}
+
+/// An erasure-coded blob, its commitment and the chunks sampled against it
+pub struct EncodedBlob {
+    pub commitment: Bytes48,
+    /// `CELLS_PER_EXT_BLOB` chunks of `BYTES_PER_CHUNK` each
+    pub chunks: Vec<u8>,
+}
+
+/// Packs `data` into EIP-4844 blobs, padding the last one with zeroes
+fn to_blobs(data: &[u8]) -> Vec<Blob> {
+    data.chunks(PAYLOAD_BYTES_PER_BLOB)
+        .map(|payload| {
+            let mut blob = [0u8; BYTES_PER_BLOB];
+            for (element, bytes) in blob
+                .chunks_exact_mut(BYTES_PER_FIELD_ELEMENT)
+                .zip(payload.chunks(PAYLOAD_BYTES_PER_FIELD_ELEMENT))
+            {
+                element[1..1 + bytes.len()].copy_from_slice(bytes);
+            }
+            Blob::new(blob)
+        })
+        .collect()
+}
+
+/// Erasure-codes `data` and computes the KZG commitment and per-chunk proofs of each blob
+pub fn encode(settings: &KzgSettings, data: &[u8]) -> Result<Vec<EncodedBlob>, c_kzg::Error> {
+    to_blobs(data)
+        .iter()
+        .map(|blob| {
+            let commitment = settings.blob_to_kzg_commitment(blob)?;
+            let (cells, proofs) = settings.compute_cells_and_kzg_proofs(blob)?;
+            let mut chunks = Vec::with_capacity(CELLS_PER_EXT_BLOB * BYTES_PER_CHUNK);
+            for (cell, proof) in cells.iter().zip(proofs.iter()) {
+                chunks.extend_from_slice(&cell.to_bytes());
+                chunks.extend_from_slice(proof.to_bytes().as_slice());
+            }
+            Ok(EncodedBlob {
+                commitment: commitment.to_bytes(),
+                chunks,
+            })
+        })
+        .collect()
+}
+
+/// Splits a stored chunk back into its cell and proof
+pub fn split_chunk(chunk: &[u8]) -> Result<(Cell, Bytes48), c_kzg::Error> {
+    let (cell, proof) = chunk.split_at(BYTES_PER_CELL);
+    Ok((
+        Cell::from_bytes(cell)?,
+        KzgProof::from_bytes(proof)?.to_bytes(),
+    ))
+}
+
+/// Checks sampled chunks of a blob against its commitment
+pub fn verify_chunks(
+    settings: &KzgSettings,
+    commitment: &Bytes48,
+    indices: &[u64],
+    chunks: &[Vec<u8>],
+) -> Result<bool, c_kzg::Error> {
+    let mut cells = Vec::with_capacity(chunks.len());
+    let mut proofs = Vec::with_capacity(chunks.len());
+    for chunk in chunks {
+        let (cell, proof) = split_chunk(chunk)?;
+        cells.push(cell);
+        proofs.push(proof);
+    }
+    let commitments = vec![*commitment; chunks.len()];
+    settings.verify_cell_kzg_proof_batch(&commitments, indices, &cells, &proofs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Recovers the payload of a blob from at least half of its chunks, as a client that
+    /// sampled them would
+    fn recover(
+        settings: &KzgSettings,
+        indices: &[u64],
+        chunks: &[Vec<u8>],
+    ) -> Result<Vec<u8>, c_kzg::Error> {
+        let cells = chunks
+            .iter()
+            .map(|chunk| split_chunk(chunk).map(|(cell, _)| cell))
+            .collect::<Result<Vec<_>, _>>()?;
+        let (cells, _) = settings.recover_cells_and_kzg_proofs(indices, &cells)?;
+        // the extension is systematic, the first half of the cells is the original blob
+        let mut payload = Vec::with_capacity(PAYLOAD_BYTES_PER_BLOB);
+        for cell in &cells[..CELLS_PER_EXT_BLOB / 2] {
+            for element in cell.to_bytes().chunks_exact(BYTES_PER_FIELD_ELEMENT) {
+                payload.extend_from_slice(&element[1..]);
+            }
+        }
+        Ok(payload)
+    }
+
+    fn chunk(blob: &EncodedBlob, index: usize) -> Vec<u8> {
+        blob.chunks[index * BYTES_PER_CHUNK..(index + 1) * BYTES_PER_CHUNK].to_vec()
+    }
+
+    #[test]
+    fn test_to_blobs_padding() {
+        assert_eq!(to_blobs(&[1u8; PAYLOAD_BYTES_PER_BLOB]).len(), 1);
+        let mut blobs = to_blobs(&[1u8; PAYLOAD_BYTES_PER_BLOB + 1]);
+        assert_eq!(blobs.len(), 2);
+        let last = blobs.pop().unwrap().into_inner();
+        assert_eq!(last[..2], [0, 1]);
+        assert!(last[2..].iter().all(|byte| *byte == 0));
+    }
+
+    #[test]
+    fn test_sample_and_recover() {
+        let settings = c_kzg::ethereum_kzg_settings(0);
+        let data: Vec<u8> = (0..1000u32).map(|i| (i % 251) as u8).collect();
+        let blobs = encode(settings, &data).unwrap();
+        assert_eq!(blobs.len(), 1);
+        let blob = &blobs[0];
+
+        // every chunk verifies against the commitment, and a tampered one does not
+        let indices: Vec<u64> = (0..CELLS_PER_EXT_BLOB as u64).step_by(7).collect();
+        let mut chunks: Vec<Vec<u8>> = indices.iter().map(|i| chunk(blob, *i as usize)).collect();
+        assert!(verify_chunks(settings, &blob.commitment, &indices, &chunks).unwrap());
+        chunks[0][1] ^= 1;
+        assert!(!verify_chunks(settings, &blob.commitment, &indices, &chunks).unwrap_or(false));
+
+        // any half of the chunks is enough to recover the blob
+        let indices: Vec<u64> = (1..CELLS_PER_EXT_BLOB as u64).step_by(2).collect();
+        let chunks: Vec<Vec<u8>> = indices.iter().map(|i| chunk(blob, *i as usize)).collect();
+        let payload = recover(settings, &indices, &chunks).unwrap();
+        assert_eq!(&payload[..data.len()], &data[..]);
+        assert!(payload[data.len()..].iter().all(|byte| *byte == 0));
+    }
+}
@@ -1,20 +1,104 @@
 use aws_config::meta::region::RegionProviderChain;
 use aws_sdk_s3::{config::Region, Client};
 use axum::{
-    body::Bytes,
-    extract::State,
-    http::StatusCode,
-    response::{IntoResponse, Json},
+    body::{Body, Bytes},
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
     routing::{get, post},
     Router,
 };
+use futures::TryStreamExt;
+use serde::Deserialize;
 use serde_json::json;
-use std::sync::Arc;
-use uuid::Uuid;
+use sha3::{Digest, Keccak256};
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+mod auth;
+mod avail;
+mod batches;
+mod das;
+mod status;
+mod store;
+use alloy_primitives::Address;
+use auth::Sequencers;
+use avail::AvailStore;
+use batches::{BatchRecord, Sequences};
+use c_kzg::KzgSettings;
+use status::{Submission, SubmissionStatus, Submissions};
+use store::{BlobStore, LocalStore, MemoryStore, Receipt, S3Store, Upload};
+
+/// Most batch records returned by a single listing
+const MAX_LISTED_BATCHES: u64 = 1000;
+/// Largest blob accepted when `DA_MAX_BLOB_SIZE` is not set
+const DEFAULT_MAX_BLOB_SIZE: usize = 64 * 1024 * 1024;
 
 struct AppState {
-    s3_client: Client,
-    s3_bucket_name: String,
+    store: Box<dyn BlobStore>,
+    /// Holds batch records and sequence numbers, which should not go to the DA backend itself
+    index: Box<dyn BlobStore>,
+    sequences: Sequences,
+    /// Asynchronous submissions, by id
+    submissions: Submissions,
+    /// Erasure-code blobs and compute KZG commitments when set
+    kzg: Option<&'static KzgSettings>,
+    /// Only accept submissions signed by these sequencers when set
+    sequencers: Option<Sequencers>,
+    /// Only accept submissions carrying this bearer token when set
+    auth_token: Option<String>,
+    /// Largest blob accepted, in bytes
+    max_blob_size: usize,
+}
+
+#[derive(Deserialize)]
+struct SubmitQuery {
+    /// Return a submission id right away instead of waiting for the blob to be stored
+    #[serde(rename = "async", default)]
+    asynchronous: bool,
+}
+
+/// A blob that was stored and recorded in its chain's sequence
+struct StoredBatch {
+    record: BatchRecord,
+    kzg_commitments: Option<Vec<String>>,
+}
+
+#[derive(Deserialize)]
+struct BatchesQuery {
+    from: Option<u64>,
+    to: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct RangeQuery {
+    start: usize,
+    end: Option<usize>,
+}
+
+/// Keccak256 commitment of a blob, used as its content address
+fn commitment(data: &[u8]) -> [u8; 32] {
+    Keccak256::digest(data).into()
+}
+
+/// Parses a 0x-prefixed or bare 32-byte hex commitment
+fn parse_commitment(commitment: &str) -> Option<[u8; 32]> {
+    let commitment = commitment.strip_prefix("0x").unwrap_or(commitment);
+    let mut bytes = [0u8; 32];
+    hex::decode_to_slice(commitment, &mut bytes).ok()?;
+    Some(bytes)
+}
+
+fn error_response(status: StatusCode, error: &str) -> Response {
+    (
+        status,
+        Json(json!({
+            "success": "false",
+            "error": error,
+        })),
+    )
+        .into_response()
 }
 
 async fn root() -> impl IntoResponse {
@@ -27,68 +111,1249 @@ async fn root() -> impl IntoResponse {
     )
 }
 
+/// Checks the bearer token of a submission, which is required when one is configured
+fn authenticate(state: &AppState, headers: &HeaderMap) -> Result<(), (StatusCode, String)> {
+    let Some(expected) = &state.auth_token else {
+        return Ok(());
+    };
+    let token = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or_else(|| (StatusCode::UNAUTHORIZED, "Missing auth token".to_string()))?;
+    // compared without returning early, so the time taken does not tell how much matched
+    let matches = token.len() == expected.len()
+        && token
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0;
+    if !matches {
+        return Err((StatusCode::UNAUTHORIZED, "Invalid auth token".to_string()));
+    }
+    Ok(())
+}
+
+/// Checks the sequencer signature of a submission, which is required when an allowlist is
+/// configured, and returns who signed it
+fn authorize(
+    state: &AppState,
+    chain_id: u64,
+    headers: &HeaderMap,
+    commitment: &[u8; 32],
+) -> Result<Option<(Address, String)>, (StatusCode, String)> {
+    let signature = match headers.get(auth::SIGNATURE_HEADER) {
+        Some(signature) => signature,
+        None if state.sequencers.is_none() => return Ok(None),
+        None => {
+            return Err((
+                StatusCode::UNAUTHORIZED,
+                "Missing sequencer signature".to_string(),
+            ))
+        }
+    };
+    let signature = signature
+        .to_str()
+        .map_err(|_| (StatusCode::UNAUTHORIZED, "Invalid signature".to_string()))?;
+    let signer = auth::recover_signer(chain_id, commitment, signature)
+        .map_err(|e| (StatusCode::UNAUTHORIZED, e))?;
+    if let Some(sequencers) = &state.sequencers {
+        if !sequencers.is_allowed(chain_id, &signer) {
+            println!(
+                "🚫 Rejected submission from {} for chain {}",
+                signer, chain_id
+            );
+            return Err((
+                StatusCode::FORBIDDEN,
+                "Signer is not a sequencer of this chain".to_string(),
+            ));
+        }
+    }
+    Ok(Some((signer, signature.to_string())))
+}
+
+/// A submission written to the blob store as it streamed in
+struct Received {
+    upload: Box<dyn Upload>,
+    commitment: [u8; 32],
+    length: usize,
+    /// The whole blob, only kept when it is erasure-coded
+    data: Option<Bytes>,
+}
+
+/// Writes a submitted body to the blob store as it streams in, hashing it on the way and
+/// rejecting it as soon as it exceeds the maximum blob size
+async fn receive_body(
+    state: &AppState,
+    headers: &HeaderMap,
+    body: Body,
+) -> Result<Received, (StatusCode, String)> {
+    let max_size = state.max_blob_size;
+    let too_large = || {
+        (
+            StatusCode::PAYLOAD_TOO_LARGE,
+            format!("Blob exceeds the maximum size of {} bytes", max_size),
+        )
+    };
+    let length = headers
+        .get(header::CONTENT_LENGTH)
+        .and_then(|length| length.to_str().ok()?.parse::<usize>().ok());
+    if length.is_some_and(|length| length > max_size) {
+        return Err(too_large());
+    }
+    let mut upload = state.store.upload();
+    let mut hasher = Keccak256::new();
+    let mut length = 0;
+    let mut data = state.kzg.map(|_| Vec::new());
+    let mut stream = body.into_data_stream();
+    let received = loop {
+        let chunk = match stream.try_next().await {
+            Ok(Some(chunk)) => chunk,
+            Ok(None) => break Ok(()),
+            Err(e) => {
+                break Err((
+                    StatusCode::BAD_REQUEST,
+                    format!("Failed to read body: {}", e),
+                ))
+            }
+        };
+        length += chunk.len();
+        if length > max_size {
+            break Err(too_large());
+        }
+        hasher.update(&chunk);
+        if let Some(data) = &mut data {
+            data.extend_from_slice(&chunk);
+        }
+        if let Err(e) = upload.write(chunk).await {
+            eprintln!("❌ Failed to upload to {}: {:?}", state.store.name(), e);
+            break Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to store data".to_string(),
+            ));
+        }
+    };
+    if let Err(error) = received {
+        upload.abort().await;
+        return Err(error);
+    }
+    Ok(Received {
+        upload,
+        commitment: hasher.finalize().into(),
+        length,
+        data: data.map(Bytes::from),
+    })
+}
+
+/// Response to a submission, the batch record along with where it was stored
+fn submission_response(
+    status: StatusCode,
+    chain_id: u64,
+    record: &BatchRecord,
+    key: &str,
+) -> serde_json::Value {
+    let mut response = json!(record);
+    response["success"] = json!(status.is_success().to_string());
+    response["chainId"] = json!(chain_id);
+    response["key"] = json!(key);
+    response
+}
+
+/// Finishes storing a received blob, erasure-codes it if enabled and records it under the
+/// chain's next sequence number, logging failures and returning the error to report
+async fn store_batch(
+    state: &AppState,
+    chain_id: u64,
+    received: Received,
+    signed: Option<(Address, String)>,
+) -> Result<StoredBatch, &'static str> {
+    let start = std::time::Instant::now();
+    let Received {
+        upload,
+        commitment,
+        length,
+        data,
+    } = received;
+    let key = batches::blob_key(chain_id, &commitment);
+    let receipt = upload
+        .finish(state.store.as_ref(), &key)
+        .await
+        .map_err(|e| {
+            eprintln!(
+                "❌ Failed to upload {} to {}: {:?}",
+                key,
+                state.store.name(),
+                e
+            );
+            "Failed to store data"
+        })?;
+    println!(
+        "📤 Uploaded to {} with key: {} in {}s",
+        state.store.name(),
+        key,
+        start.elapsed().as_secs_f64()
+    );
+    let kzg_commitments = match (state.kzg, data) {
+        (Some(kzg), Some(data)) => {
+            let manifest = store_chunks(state, kzg, chain_id, &commitment, data)
+                .await
+                .map_err(|e| {
+                    eprintln!("❌ Failed to erasure-code {}: {:?}", key, e);
+                    "Failed to erasure-code data"
+                })?;
+            Some(manifest.commitments)
+        }
+        _ => None,
+    };
+    let (signer, signature) = signed.unzip();
+    let record = BatchRecord {
+        sequence: 0,
+        commitment: format!("0x{}", hex::encode(commitment)),
+        length,
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs(),
+        signer,
+        signature,
+        avail: match receipt {
+            Receipt::Avail(receipt) => Some(receipt),
+            Receipt::Stored => None,
+        },
+    };
+    let record = state
+        .sequences
+        .record(state.index.as_ref(), chain_id, &commitment, record)
+        .await
+        .map_err(|e| {
+            eprintln!("❌ Failed to record batch {}: {:?}", key, e);
+            "Failed to record batch"
+        })?;
+    println!(
+        "🔢 Recorded {} as batch {} of chain {}",
+        key, record.sequence, chain_id
+    );
+    Ok(StoredBatch {
+        record,
+        kzg_commitments,
+    })
+}
+
+/// Stores an accepted submission in the background and settles its status
+///
+/// A blob the Avail backend included is reported as included right away, and as finalized
+/// once its block is.
+async fn store_submission(
+    state: Arc<AppState>,
+    mut submission: Submission,
+    received: Received,
+    signed: Option<(Address, String)>,
+) {
+    let included = match store_batch(&state, submission.chain_id, received, signed).await {
+        Ok(stored) => {
+            let included = stored.record.avail.clone().map(Receipt::Avail);
+            submission.status = match included {
+                Some(_) => SubmissionStatus::Included,
+                None => SubmissionStatus::Stored,
+            };
+            submission.record = Some(stored.record);
+            submission.kzg_commitments = stored.kzg_commitments;
+            included
+        }
+        Err(error) => {
+            submission.status = SubmissionStatus::Failed;
+            submission.error = Some(error.to_string());
+            None
+        }
+    };
+    settle_submission(&state, submission.clone()).await;
+    let Some(receipt) = included else {
+        return;
+    };
+    match state.store.wait_finalized(&receipt).await {
+        Ok(()) => submission.status = SubmissionStatus::Finalized,
+        Err(e) => {
+            eprintln!("❌ Submission {} was not finalized: {:?}", submission.id, e);
+            submission.status = SubmissionStatus::Failed;
+            submission.error = Some(format!("Failed to finalize data: {}", e));
+        }
+    }
+    settle_submission(&state, submission).await;
+}
+
+async fn settle_submission(state: &AppState, submission: Submission) {
+    let id = submission.id.clone();
+    if let Err(e) = state
+        .submissions
+        .settle(state.index.as_ref(), submission)
+        .await
+    {
+        eprintln!("❌ Failed to persist status of submission {}: {:?}", id, e);
+    }
+}
+
 #[inline]
-async fn submit(state: State<Arc<AppState>>, data: Bytes) -> impl IntoResponse {
+async fn submit(
+    state: State<Arc<AppState>>,
+    Path(chain_id): Path<u64>,
+    Query(query): Query<SubmitQuery>,
+    headers: HeaderMap,
+    body: Body,
+) -> Response {
+    // checked before the body is read, so unauthenticated uploads are not stored at all
+    if let Err((status, error)) = authenticate(&state, &headers) {
+        println!("🚫 Rejected submission for chain {}: {}", chain_id, error);
+        return error_response(status, &error);
+    }
+    let received = match receive_body(&state, &headers, body).await {
+        Ok(received) => received,
+        Err((status, error)) => {
+            println!("🚫 Rejected submission for chain {}: {}", chain_id, error);
+            return error_response(status, &error);
+        }
+    };
+    println!(
+        "Received data of length: {} for chain {}",
+        received.length, chain_id
+    );
+    if received.length == 0 {
+        received.upload.abort().await;
+        return error_response(StatusCode::BAD_REQUEST, "Empty data");
+    }
+    let commitment = received.commitment;
+    let signed = match authorize(&state, chain_id, &headers, &commitment) {
+        Ok(signed) => signed,
+        Err((status, error)) => {
+            received.upload.abort().await;
+            return error_response(status, &error);
+        }
+    };
+    let key = batches::blob_key(chain_id, &commitment);
+    // a resubmitted blob keeps its place in the chain's sequence
+    let record_key = batches::record_key(chain_id, &commitment);
+    match batches::load_record(state.index.as_ref(), &record_key).await {
+        Ok(Some(record)) => {
+            received.upload.abort().await;
+            println!(
+                "♻️ Blob {} was already stored as batch {}",
+                key, record.sequence
+            );
+            let response = submission_response(StatusCode::OK, chain_id, &record, &key);
+            return (StatusCode::OK, Json(response)).into_response();
+        }
+        Ok(None) => {}
+        Err(e) => {
+            received.upload.abort().await;
+            eprintln!("❌ Failed to look up {}: {:?}", record_key, e);
+            return error_response(StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch data");
+        }
+    }
+    if query.asynchronous {
+        let submission = state
+            .submissions
+            .start(chain_id, format!("0x{}", hex::encode(commitment)));
+        println!("⏳ Accepted {} as submission {}", key, submission.id);
+        let response = json!({
+            "success": "true",
+            "id": submission.id,
+            "chainId": chain_id,
+            "commitment": submission.commitment,
+            "status": submission.status,
+        });
+        tokio::spawn(store_submission(
+            Arc::clone(&state),
+            submission,
+            received,
+            signed,
+        ));
+        return (StatusCode::ACCEPTED, Json(response)).into_response();
+    }
+    match store_batch(&state, chain_id, received, signed).await {
+        Ok(stored) => {
+            let mut response =
+                submission_response(StatusCode::CREATED, chain_id, &stored.record, &key);
+            if let Some(kzg_commitments) = stored.kzg_commitments {
+                response["kzgCommitments"] = json!(kzg_commitments);
+            }
+            (StatusCode::CREATED, Json(response)).into_response()
+        }
+        Err(error) => error_response(StatusCode::INTERNAL_SERVER_ERROR, error),
+    }
+}
+
+#[inline]
+async fn get_status(state: State<Arc<AppState>>, Path(id): Path<String>) -> Response {
+    let Ok(id) = id.parse::<uuid::Uuid>() else {
+        return error_response(StatusCode::BAD_REQUEST, "Invalid submission id");
+    };
+    match state.submissions.get(state.index.as_ref(), &id).await {
+        Ok(Some(submission)) => (StatusCode::OK, Json(json!(submission))).into_response(),
+        Ok(None) => error_response(StatusCode::NOT_FOUND, "Submission not found"),
+        Err(e) => {
+            eprintln!("❌ Failed to fetch submission {}: {:?}", id, e);
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch data")
+        }
+    }
+}
+
+/// Erasure-codes a blob and stores its chunks and manifest next to it
+async fn store_chunks(
+    state: &AppState,
+    kzg: &'static KzgSettings,
+    chain_id: u64,
+    commitment: &[u8; 32],
+    data: Bytes,
+) -> Result<das::Manifest, store::StoreError> {
     let start = std::time::Instant::now();
-    println!("Received data of length: {}", data.len());
-    if data.is_empty() {
-        return (
+    let length = data.len();
+    let blobs = tokio::task::spawn_blocking(move || das::encode(kzg, &data)).await??;
+    let manifest = das::Manifest {
+        length,
+        commitments: blobs
+            .iter()
+            .map(|blob| format!("0x{}", hex::encode(blob.commitment.into_inner())))
+            .collect(),
+        chunks_per_blob: c_kzg::CELLS_PER_EXT_BLOB,
+    };
+    for (index, blob) in blobs.into_iter().enumerate() {
+        state
+            .store
+            .put(
+                &batches::chunks_key(chain_id, commitment, index),
+                Bytes::from(blob.chunks),
+            )
+            .await?;
+    }
+    state
+        .store
+        .put(
+            &batches::manifest_key(chain_id, commitment),
+            Bytes::from(serde_json::to_vec(&manifest)?),
+        )
+        .await?;
+    println!(
+        "🧩 Erasure-coded into {} blobs in {}s",
+        manifest.commitments.len(),
+        start.elapsed().as_secs_f64()
+    );
+    Ok(manifest)
+}
+
+/// Fetches a blob by commitment and checks that its contents hash back to it
+async fn fetch_verified(
+    state: &AppState,
+    chain_id: u64,
+    commitment: &str,
+) -> Result<Bytes, Response> {
+    let expected = parse_commitment(commitment).ok_or_else(|| {
+        error_response(
             StatusCode::BAD_REQUEST,
-            Json(json!({
-                "success": "false",
-                "error": "Empty data",
-            })),
+            "Commitment was not parseable as a 32-byte hex string",
+        )
+    })?;
+    let key = batches::blob_key(chain_id, &expected);
+    let data = match state.store.get(&key).await {
+        Ok(Some(data)) => data,
+        Ok(None) => return Err(error_response(StatusCode::NOT_FOUND, "Blob not found")),
+        Err(e) => {
+            eprintln!(
+                "❌ Failed to fetch {} from {}: {:?}",
+                key,
+                state.store.name(),
+                e
+            );
+            return Err(error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to fetch data",
+            ));
+        }
+    };
+    if self::commitment(&data) != expected {
+        eprintln!("❌ Integrity check failed for {}", key);
+        return Err(error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Stored data does not match commitment",
+        ));
+    }
+    Ok(data)
+}
+
+#[inline]
+async fn get_blob(
+    state: State<Arc<AppState>>,
+    Path((chain_id, commitment)): Path<(u64, String)>,
+) -> Response {
+    let start = std::time::Instant::now();
+    match fetch_verified(&state, chain_id, &commitment).await {
+        Ok(data) => {
+            println!(
+                "📥 Served blob {} of length {} in {}s",
+                commitment,
+                data.len(),
+                start.elapsed().as_secs_f64()
+            );
+            (
+                StatusCode::OK,
+                [(header::CONTENT_TYPE, "application/octet-stream")],
+                data,
+            )
+                .into_response()
+        }
+        Err(response) => response,
+    }
+}
+
+#[inline]
+async fn get_blob_range(
+    state: State<Arc<AppState>>,
+    Path((chain_id, commitment)): Path<(u64, String)>,
+    Query(range): Query<RangeQuery>,
+) -> Response {
+    let data = match fetch_verified(&state, chain_id, &commitment).await {
+        Ok(data) => data,
+        Err(response) => return response,
+    };
+    let end = range.end.unwrap_or(data.len());
+    if range.start > end || end > data.len() {
+        return error_response(StatusCode::RANGE_NOT_SATISFIABLE, "Invalid range");
+    }
+    (
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, "application/octet-stream")],
+        data.slice(range.start..end),
+    )
+        .into_response()
+}
+
+#[inline]
+async fn get_record(
+    state: State<Arc<AppState>>,
+    Path((chain_id, commitment)): Path<(u64, String)>,
+) -> Response {
+    let Some(commitment) = parse_commitment(&commitment) else {
+        return error_response(
+            StatusCode::BAD_REQUEST,
+            "Commitment was not parseable as a 32-byte hex string",
         );
+    };
+    let key = batches::record_key(chain_id, &commitment);
+    match batches::load_record(state.index.as_ref(), &key).await {
+        Ok(Some(record)) => (StatusCode::OK, Json(json!(record))).into_response(),
+        Ok(None) => error_response(StatusCode::NOT_FOUND, "Blob not found"),
+        Err(e) => {
+            eprintln!("❌ Failed to fetch {}: {:?}", key, e);
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch data")
+        }
     }
-    let uuid = Uuid::new_v4();
-    let body = aws_sdk_s3::primitives::ByteStream::from(data);
+}
+
+/// Lists the batches of a chain by sequence number, `from` and `to` inclusive
+#[inline]
+async fn list_batches(
+    state: State<Arc<AppState>>,
+    Path(chain_id): Path<u64>,
+    Query(query): Query<BatchesQuery>,
+) -> Response {
+    let head = match state.sequences.head(state.index.as_ref(), chain_id).await {
+        Ok(head) => head,
+        Err(e) => {
+            eprintln!("❌ Failed to load head of chain {}: {:?}", chain_id, e);
+            return error_response(StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch data");
+        }
+    };
+    let from = query.from.unwrap_or(0);
+    let to = query
+        .to
+        .unwrap_or_else(|| from.saturating_add(MAX_LISTED_BATCHES - 1))
+        .min(head.saturating_sub(1));
+    if query.to.is_some_and(|to| to < from) {
+        return error_response(StatusCode::BAD_REQUEST, "Invalid range");
+    }
+    if to.saturating_sub(from) >= MAX_LISTED_BATCHES {
+        return error_response(
+            StatusCode::BAD_REQUEST,
+            &format!(
+                "At most {} batches can be listed at once",
+                MAX_LISTED_BATCHES
+            ),
+        );
+    }
+    let mut records = Vec::new();
+    if from < head {
+        for sequence in from..=to {
+            let key = batches::sequence_key(chain_id, sequence);
+            match batches::load_record(state.index.as_ref(), &key).await {
+                Ok(Some(record)) => records.push(record),
+                Ok(None) => {
+                    eprintln!("❌ Missing batch record {}", key);
+                    return error_response(StatusCode::INTERNAL_SERVER_ERROR, "Missing batch");
+                }
+                Err(e) => {
+                    eprintln!("❌ Failed to fetch {}: {:?}", key, e);
+                    return error_response(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        "Failed to fetch data",
+                    );
+                }
+            }
+        }
+    }
+    (
+        StatusCode::OK,
+        Json(json!({
+            "chainId": chain_id,
+            "count": head,
+            "batches": records,
+        })),
+    )
+        .into_response()
+}
+
+/// Loads the erasure coding manifest of a blob
+async fn load_manifest(
+    state: &AppState,
+    chain_id: u64,
+    commitment: &str,
+) -> Result<das::Manifest, Response> {
+    if state.kzg.is_none() {
+        return Err(error_response(
+            StatusCode::NOT_FOUND,
+            "Erasure coding is disabled",
+        ));
+    }
+    let commitment = parse_commitment(commitment).ok_or_else(|| {
+        error_response(
+            StatusCode::BAD_REQUEST,
+            "Commitment was not parseable as a 32-byte hex string",
+        )
+    })?;
     match state
-        .s3_client
-        .put_object()
-        .bucket(&state.s3_bucket_name)
-        .key(uuid)
-        .body(body)
-        .send()
+        .store
+        .get(&batches::manifest_key(chain_id, &commitment))
         .await
     {
-        Ok(_) => {
-            let elapsed = start.elapsed().as_secs_f64();
-            println!("📤 Uploaded to S3 with UUID: {} in {}s", uuid, elapsed);
-            (
-                StatusCode::CREATED,
-                Json(json!({
-                    "success": "true"
-                })),
-            )
+        Ok(Some(manifest)) => serde_json::from_slice(&manifest).map_err(|e| {
+            eprintln!(
+                "❌ Corrupt manifest for {}: {:?}",
+                hex::encode(commitment),
+                e
+            );
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, "Corrupt manifest")
+        }),
+        Ok(None) => Err(error_response(StatusCode::NOT_FOUND, "Blob not found")),
+        Err(e) => {
+            eprintln!("❌ Failed to fetch manifest: {:?}", e);
+            Err(error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to fetch data",
+            ))
         }
-        Err(_) => (
+    }
+}
+
+#[inline]
+async fn get_manifest(
+    state: State<Arc<AppState>>,
+    Path((chain_id, commitment)): Path<(u64, String)>,
+) -> Response {
+    match load_manifest(&state, chain_id, &commitment).await {
+        Ok(manifest) => (StatusCode::OK, Json(json!(manifest))).into_response(),
+        Err(response) => response,
+    }
+}
+
+#[inline]
+async fn get_chunk(
+    state: State<Arc<AppState>>,
+    Path((chain_id, commitment, blob, chunk)): Path<(u64, String, usize, usize)>,
+) -> Response {
+    let manifest = match load_manifest(&state, chain_id, &commitment).await {
+        Ok(manifest) => manifest,
+        Err(response) => return response,
+    };
+    if blob >= manifest.commitments.len() || chunk >= manifest.chunks_per_blob {
+        return error_response(StatusCode::NOT_FOUND, "Chunk not found");
+    }
+    // the manifest was found, so the commitment is known to parse
+    let key = batches::chunks_key(chain_id, &parse_commitment(&commitment).unwrap(), blob);
+    let chunks = match state.store.get(&key).await {
+        Ok(Some(chunks)) => chunks,
+        Ok(None) => return error_response(StatusCode::NOT_FOUND, "Chunk not found"),
+        Err(e) => {
+            eprintln!("❌ Failed to fetch {}: {:?}", key, e);
+            return error_response(StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch data");
+        }
+    };
+    let offset = chunk * das::BYTES_PER_CHUNK;
+    let Some(sample) = chunks.get(offset..offset + das::BYTES_PER_CHUNK) else {
+        return error_response(StatusCode::INTERNAL_SERVER_ERROR, "Corrupt chunks");
+    };
+    // a chunk that no longer matches its commitment fails every sample, so it is not served,
+    // and the manifest was found, so erasure coding is enabled
+    let kzg = state.kzg.unwrap();
+    let verified = c_kzg::Bytes48::from_hex(&manifest.commitments[blob]).and_then(|commitment| {
+        das::verify_chunks(kzg, &commitment, &[chunk as u64], &[sample.to_vec()])
+    });
+    if !matches!(verified, Ok(true)) {
+        eprintln!(
+            "❌ Chunk {} of blob {} of {} does not match its commitment",
+            chunk, blob, commitment
+        );
+        return error_response(
             StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({
-                "success": "false"
-            })),
-        ),
+            "Chunk does not match its commitment",
+        );
+    }
+    let (cell, proof) = sample.split_at(c_kzg::BYTES_PER_CELL);
+    (
+        StatusCode::OK,
+        Json(json!({
+            "commitment": manifest.commitments[blob],
+            "index": chunk,
+            "cell": format!("0x{}", hex::encode(cell)),
+            "proof": format!("0x{}", hex::encode(proof)),
+        })),
+    )
+        .into_response()
+}
+
+type Stores = (Box<dyn BlobStore>, Box<dyn BlobStore>);
+
+/// Builds the blob store selected by `DA_STORE` (s3, local, memory or avail, defaulting to s3)
+/// and the store batch records are indexed in
+async fn load_store() -> Result<Stores, store::StoreError> {
+    let backend = dotenvy::var("DA_STORE").unwrap_or_else(|_| "s3".to_string());
+    match backend.as_str() {
+        "s3" => {
+            let region_provider =
+                RegionProviderChain::first_try(Region::new(dotenvy::var("AWS_REGION")?));
+            let shared_config = aws_config::from_env().region(region_provider).load().await;
+            let mut s3_config = aws_sdk_s3::config::Builder::from(&shared_config);
+            // S3-compatible stand-ins such as MinIO need path-style addressing
+            if let Ok(endpoint) = dotenvy::var("S3_ENDPOINT") {
+                s3_config = s3_config.endpoint_url(endpoint).force_path_style(true);
+            }
+            let s3_client = Client::from_conf(s3_config.build());
+            let bucket = dotenvy::var("S3_BUCKET")?;
+            Ok((
+                Box::new(S3Store::new(s3_client.clone(), bucket.clone())),
+                Box::new(S3Store::new(s3_client, bucket)),
+            ))
+        }
+        "local" => {
+            let path = dotenvy::var("DA_STORE_PATH").unwrap_or_else(|_| "chains/da".to_string());
+            Ok((
+                Box::new(LocalStore::new(path.clone())),
+                Box::new(LocalStore::new(path)),
+            ))
+        }
+        "memory" => Ok((Box::<MemoryStore>::default(), Box::<MemoryStore>::default())),
+        "avail" => {
+            // blob locations and batch records are indexed on local disk
+            let path = dotenvy::var("DA_STORE_PATH").unwrap_or_else(|_| "chains/da".to_string());
+            let timeout = match dotenvy::var("AVAIL_INCLUSION_TIMEOUT_SECS") {
+                Ok(secs) => Duration::from_secs(secs.parse()?),
+                Err(_) => Duration::from_secs(120),
+            };
+            let finality_timeout = match dotenvy::var("AVAIL_FINALITY_TIMEOUT_SECS") {
+                Ok(secs) => Duration::from_secs(secs.parse()?),
+                Err(_) => Duration::from_secs(600),
+            };
+            let store = AvailStore::new(
+                dotenvy::var("AVAIL_LIGHT_CLIENT_URL")
+                    .unwrap_or_else(|_| "http://127.0.0.1:7007".to_string()),
+                dotenvy::var("AVAIL_APP_ID")?.parse()?,
+                Box::new(LocalStore::new(path.clone())),
+                timeout,
+                finality_timeout,
+            )
+            .await?;
+            Ok((Box::new(store), Box::new(LocalStore::new(path))))
+        }
+        other => Err(format!("Unknown DA_STORE backend: {}", other).into()),
     }
 }
 
+fn app(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/", get(root))
+        .route("/submit/:chain_id", post(submit))
+        .route("/status/:id", get(get_status))
+        .route("/chains/:chain_id/batches", get(list_batches))
+        .route("/chains/:chain_id/blob/:commitment", get(get_blob))
+        .route(
+            "/chains/:chain_id/blob/:commitment/range",
+            get(get_blob_range),
+        )
+        .route("/chains/:chain_id/blob/:commitment/meta", get(get_record))
+        .route("/chains/:chain_id/das/:commitment", get(get_manifest))
+        .route(
+            "/chains/:chain_id/das/:commitment/:blob/:chunk",
+            get(get_chunk),
+        )
+        .with_state(state)
+}
+
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    dotenvy::dotenv()?;
-    let region_provider = RegionProviderChain::first_try(Region::new(dotenvy::var("AWS_REGION")?));
-    let shared_config = aws_config::from_env().region(region_provider).load().await;
-    let s3_client = Client::new(&shared_config);
-    let s3_bucket_name = dotenvy::var("S3_BUCKET")?;
+async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    // a .env file is optional, the variables may come from the environment instead
+    dotenvy::dotenv().ok();
+    let (store, index) = load_store().await?;
+    println!("🗄️ Using {} blob store", store.name());
+    let kzg = match dotenvy::var("DA_ERASURE_CODING").as_deref() {
+        Ok("true") if store.erasure_codes() => {
+            return Err(format!(
+                "DA_ERASURE_CODING is not supported with the {} backend, which erasure codes blobs itself",
+                store.name()
+            )
+            .into())
+        }
+        Ok("true") => {
+            println!("🧩 Erasure coding enabled");
+            Some(c_kzg::ethereum_kzg_settings(8))
+        }
+        _ => None,
+    };
+    let sequencers = match dotenvy::var("DA_SEQUENCERS") {
+        Ok(entries) => {
+            let sequencers = Sequencers::parse(&entries)?;
+            println!(
+                "🔏 Accepting submissions from {} sequencers",
+                sequencers.len()
+            );
+            Some(sequencers)
+        }
+        Err(_) => {
+            println!("⚠️ DA_SEQUENCERS is not set, accepting unsigned submissions");
+            None
+        }
+    };
+    let auth_token = dotenvy::var("DA_AUTH_TOKEN").ok();
+    if auth_token.is_some() {
+        println!("🔑 Requiring an auth token for submissions");
+    }
+    let max_blob_size = match dotenvy::var("DA_MAX_BLOB_SIZE") {
+        Ok(size) => size.parse()?,
+        Err(_) => DEFAULT_MAX_BLOB_SIZE,
+    };
+    println!("📏 Accepting blobs of up to {} bytes", max_blob_size);
     let app_state = Arc::new(AppState {
-        s3_client,
-        s3_bucket_name,
+        store,
+        index,
+        sequences: Sequences::default(),
+        submissions: Submissions::default(),
+        kzg,
+        sequencers,
+        auth_token,
+        max_blob_size,
     });
-    // build our application with a single route
-    let app = Router::new()
-        .route("/", get(root))
-        .route("/submit", post(submit))
-        .with_state(app_state);
 
     // run our app with hyper, listening globally on port 3000
     let listener = tokio::net::TcpListener::bind("127.0.0.1:3000").await?;
-    axum::serve(listener, app).await?;
+    axum::serve(listener, app(app_state)).await?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHAIN_ID: u64 = 31338;
+
+    fn state_with(
+        kzg: Option<&'static KzgSettings>,
+        sequencers: Option<Sequencers>,
+    ) -> State<Arc<AppState>> {
+        State(Arc::new(AppState {
+            store: Box::<MemoryStore>::default(),
+            index: Box::<MemoryStore>::default(),
+            sequences: Sequences::default(),
+            submissions: Submissions::default(),
+            kzg,
+            sequencers,
+            auth_token: None,
+            max_blob_size: DEFAULT_MAX_BLOB_SIZE,
+        }))
+    }
+
+    fn memory_state() -> State<Arc<AppState>> {
+        state_with(None, None)
+    }
+
+    fn sync() -> Query<SubmitQuery> {
+        Query(SubmitQuery {
+            asynchronous: false,
+        })
+    }
+
+    async fn body_json(response: Response) -> serde_json::Value {
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    async fn list(state: &State<Arc<AppState>>, from: Option<u64>, to: Option<u64>) -> Response {
+        list_batches(
+            state.clone(),
+            Path(CHAIN_ID),
+            Query(BatchesQuery { from, to }),
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_submit_and_fetch() {
+        let state = memory_state();
+        let data = Bytes::from_static(b"some rollup batch");
+        let response = submit(
+            state.clone(),
+            Path(CHAIN_ID),
+            sync(),
+            HeaderMap::new(),
+            Body::from(data.clone()),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let body = body_json(response).await;
+        let commitment = body["commitment"].as_str().unwrap().to_string();
+        assert_eq!(parse_commitment(&commitment), Some(self::commitment(&data)));
+        assert_eq!(body["sequence"], 0);
+
+        let response = get_blob(state.clone(), Path((CHAIN_ID, commitment.clone()))).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(body, data);
+
+        // blobs are only served from the namespace they were submitted to
+        let response = get_blob(state.clone(), Path((CHAIN_ID + 1, commitment.clone()))).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let range = RangeQuery {
+            start: 5,
+            end: Some(10),
+        };
+        let response = get_blob_range(state, Path((CHAIN_ID, commitment)), Query(range)).await;
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(body, data.slice(5..10));
+    }
+
+    #[tokio::test]
+    async fn test_rejects_oversized_blobs() {
+        let state = State(Arc::new(AppState {
+            max_blob_size: 16,
+            ..Arc::into_inner(memory_state().0).unwrap()
+        }));
+        let data = Bytes::from(vec![1u8; 17]);
+        // rejected up front from the declared length
+        let mut headers = HeaderMap::new();
+        headers.insert(header::CONTENT_LENGTH, "17".parse().unwrap());
+        let response = submit(
+            state.clone(),
+            Path(CHAIN_ID),
+            sync(),
+            headers,
+            Body::from(data.clone()),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        // and while streaming when the length is not declared
+        let chunks: Vec<Result<Bytes, std::io::Error>> =
+            vec![Ok(data.slice(..10)), Ok(data.slice(10..))];
+        let body = Body::from_stream(futures::stream::iter(chunks));
+        let response = submit(
+            state.clone(),
+            Path(CHAIN_ID),
+            sync(),
+            HeaderMap::new(),
+            body,
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        assert!(body_json(response).await["error"]
+            .as_str()
+            .unwrap()
+            .contains("16 bytes"));
+
+        let response = submit(
+            state,
+            Path(CHAIN_ID),
+            sync(),
+            HeaderMap::new(),
+            Body::from(data.slice(..16)),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::CREATED);
+    }
+
+    #[tokio::test]
+    async fn test_requires_auth_token() {
+        let state = State(Arc::new(AppState {
+            auth_token: Some("secret".to_string()),
+            ..Arc::into_inner(memory_state().0).unwrap()
+        }));
+        let data = Bytes::from_static(b"some rollup batch");
+        let submit_with = |token: Option<&str>| {
+            let mut headers = HeaderMap::new();
+            if let Some(token) = token {
+                headers.insert(header::AUTHORIZATION, token.parse().unwrap());
+            }
+            submit(
+                state.clone(),
+                Path(CHAIN_ID),
+                sync(),
+                headers,
+                Body::from(data.clone()),
+            )
+        };
+        assert_eq!(submit_with(None).await.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            submit_with(Some("Bearer wrong")).await.status(),
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            submit_with(Some("secret")).await.status(),
+            StatusCode::UNAUTHORIZED
+        );
+        let response = list(&state, None, None).await;
+        assert_eq!(body_json(response).await["batches"], json!([]));
+
+        assert_eq!(
+            submit_with(Some("Bearer secret")).await.status(),
+            StatusCode::CREATED
+        );
+    }
+
+    #[tokio::test]
+    async fn test_async_submission() {
+        let state = memory_state();
+        let data = Bytes::from_static(b"some rollup batch");
+        let query = Query(SubmitQuery { asynchronous: true });
+        let response = submit(
+            state.clone(),
+            Path(CHAIN_ID),
+            query,
+            HeaderMap::new(),
+            Body::from(data.clone()),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        let body = body_json(response).await;
+        assert_eq!(body["status"], "pending");
+        let id = body["id"].as_str().unwrap().to_string();
+
+        let mut status = json!(null);
+        for _ in 0..100 {
+            status = body_json(get_status(state.clone(), Path(id.clone())).await).await;
+            if status["status"] != "pending" {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(status["status"], "stored");
+        assert_eq!(status["record"]["sequence"], 0);
+        assert_eq!(status["commitment"], body["commitment"]);
+
+        let commitment = status["commitment"].as_str().unwrap().to_string();
+        let response = get_blob(state.clone(), Path((CHAIN_ID, commitment))).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = get_status(state.clone(), Path("not-an-id".to_string())).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let response = get_status(state, Path(uuid::Uuid::new_v4().to_string())).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_list_batches() {
+        let state = memory_state();
+        let body = body_json(list(&state, None, None).await).await;
+        assert_eq!(body["batches"], json!([]));
+
+        for i in 0..5u8 {
+            let data = Bytes::from(vec![i; 10]);
+            let response = submit(
+                state.clone(),
+                Path(CHAIN_ID),
+                sync(),
+                HeaderMap::new(),
+                Body::from(data),
+            )
+            .await;
+            assert_eq!(body_json(response).await["sequence"], i);
+        }
+        // resubmitting a blob returns its existing record
+        let data = Bytes::from(vec![1u8; 10]);
+        let response = submit(
+            state.clone(),
+            Path(CHAIN_ID),
+            sync(),
+            HeaderMap::new(),
+            Body::from(data),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body_json(response).await["sequence"], 1);
+        // other chains are numbered independently
+        let data = Bytes::from(vec![9u8; 10]);
+        let response = submit(
+            state.clone(),
+            Path(CHAIN_ID + 1),
+            sync(),
+            HeaderMap::new(),
+            Body::from(data),
+        )
+        .await;
+        assert_eq!(body_json(response).await["sequence"], 0);
+
+        let body = body_json(list(&state, Some(1), Some(3)).await).await;
+        assert_eq!(body["count"], 5);
+        let batches = body["batches"].as_array().unwrap();
+        assert_eq!(batches.len(), 3);
+        assert_eq!(batches[0]["sequence"], 1);
+        assert_eq!(
+            batches[0]["commitment"],
+            format!("0x{}", hex::encode(commitment(&[1u8; 10])))
+        );
+        let body = body_json(list(&state, Some(3), None).await).await;
+        assert_eq!(body["batches"].as_array().unwrap().len(), 2);
+        let body = body_json(list(&state, Some(10), None).await).await;
+        assert_eq!(body["batches"], json!([]));
+
+        let response = list(&state, Some(3), Some(1)).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        // ranges past the head are cut short rather than rejected
+        let body = body_json(list(&state, Some(0), Some(MAX_LISTED_BATCHES * 2)).await).await;
+        assert_eq!(body["batches"].as_array().unwrap().len(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_rejects_tampered_blob() {
+        let state = memory_state();
+        let data = Bytes::from_static(b"some rollup batch");
+        let key = batches::blob_key(CHAIN_ID, &commitment(&data));
+        state
+            .store
+            .put(&key, Bytes::from_static(b"something else"))
+            .await
+            .unwrap();
+        let commitment = format!("0x{}", hex::encode(commitment(&data)));
+        let response = get_blob(state, Path((CHAIN_ID, commitment))).await;
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_errors() {
+        let state = memory_state();
+        let response = get_blob(state.clone(), Path((CHAIN_ID, "0x1234".to_string()))).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let missing = format!("0x{}", hex::encode([0u8; 32]));
+        let response = get_blob(state, Path((CHAIN_ID, missing))).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_signed_submissions() {
+        let key = k256::ecdsa::SigningKey::from_slice(&[7u8; 32]).unwrap();
+        let data = Bytes::from_static(b"some rollup batch");
+        let (sequencer, signature) = auth::tests::sign(&key, CHAIN_ID, &commitment(&data));
+        let sequencers = Sequencers::parse(&format!("{}={}", CHAIN_ID, sequencer)).unwrap();
+        let state = state_with(None, Some(sequencers));
+        let signed = |signature: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(auth::SIGNATURE_HEADER, signature.parse().unwrap());
+            headers
+        };
+
+        let response = submit(
+            state.clone(),
+            Path(CHAIN_ID),
+            sync(),
+            HeaderMap::new(),
+            Body::from(data.clone()),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        // a signature for one chain does not let the blob into another chain's namespace
+        let response = submit(
+            state.clone(),
+            Path(CHAIN_ID + 1),
+            sync(),
+            signed(&signature),
+            Body::from(data.clone()),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let other = k256::ecdsa::SigningKey::from_slice(&[8u8; 32]).unwrap();
+        let (_, forged) = auth::tests::sign(&other, CHAIN_ID, &commitment(&data));
+        let response = submit(
+            state.clone(),
+            Path(CHAIN_ID),
+            sync(),
+            signed(&forged),
+            Body::from(data.clone()),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let response = submit(
+            state.clone(),
+            Path(CHAIN_ID),
+            sync(),
+            signed(&signature),
+            Body::from(data),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let body = body_json(response).await;
+        assert_eq!(body["signer"], json!(sequencer));
+        let commitment = body["commitment"].as_str().unwrap().to_string();
+        let record = body_json(get_record(state, Path((CHAIN_ID, commitment))).await).await;
+        assert_eq!(record["sequence"], 0);
+        assert_eq!(record["signer"], json!(sequencer));
+    }
+
+    #[tokio::test]
+    async fn test_erasure_coded_chunks() {
+        let state = state_with(Some(c_kzg::ethereum_kzg_settings(0)), None);
+        let data = Bytes::from(vec![7u8; das::PAYLOAD_BYTES_PER_BLOB + 10]);
+        let response = submit(
+            state.clone(),
+            Path(CHAIN_ID),
+            sync(),
+            HeaderMap::new(),
+            Body::from(data),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let body = body_json(response).await;
+        assert_eq!(body["kzgCommitments"].as_array().unwrap().len(), 2);
+        let commitment = body["commitment"].as_str().unwrap().to_string();
+
+        let manifest =
+            body_json(get_manifest(state.clone(), Path((CHAIN_ID, commitment.clone()))).await)
+                .await;
+        assert_eq!(manifest["length"], das::PAYLOAD_BYTES_PER_BLOB + 10);
+        assert_eq!(manifest["commitments"], body["kzgCommitments"]);
+
+        let response = get_chunk(state.clone(), Path((CHAIN_ID, commitment.clone(), 1, 127))).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let chunk = body_json(response).await;
+        assert_eq!(chunk["commitment"], body["kzgCommitments"][1]);
+        let response = get_chunk(state.clone(), Path((CHAIN_ID, commitment.clone(), 2, 0))).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        // a chunk corrupted in the store is not served
+        let key = batches::chunks_key(CHAIN_ID, &parse_commitment(&commitment).unwrap(), 1);
+        let mut chunks = state.store.get(&key).await.unwrap().unwrap().to_vec();
+        chunks[127 * das::BYTES_PER_CHUNK + 1] ^= 1;
+        state.store.put(&key, Bytes::from(chunks)).await.unwrap();
+        let response = get_chunk(state.clone(), Path((CHAIN_ID, commitment.clone(), 1, 127))).await;
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        let response = get_chunk(state, Path((CHAIN_ID, commitment, 1, 126))).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
@@ -0,0 +1,138 @@
+use crate::{
+    batches::BatchRecord,
+    store::{BlobStore, StoreError},
+};
+use axum::body::Bytes;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Where an asynchronous submission is at
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SubmissionStatus {
+    /// Accepted and waiting to be stored
+    Pending,
+    /// Stored by a backend that keeps blobs itself, this is final
+    Stored,
+    /// Included in a block of the DA layer that is not finalized yet, so it may be reorged out
+    Included,
+    /// Included in a finalized block of the DA layer
+    Finalized,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Submission {
+    pub id: String,
+    pub chain_id: u64,
+    pub commitment: String,
+    pub status: SubmissionStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub record: Option<BatchRecord>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kzg_commitments: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+fn submission_key(id: &uuid::Uuid) -> String {
+    format!("submissions/{}", id)
+}
+
+/// Tracks asynchronous submissions
+///
+/// Pending submissions only live in memory, as they are lost with the server anyway. Once a
+/// submission settles it is persisted to the index and dropped from memory.
+#[derive(Default)]
+pub struct Submissions {
+    pending: RwLock<HashMap<uuid::Uuid, Submission>>,
+}
+
+impl Submissions {
+    /// Registers a new pending submission and returns it
+    pub fn start(&self, chain_id: u64, commitment: String) -> Submission {
+        let id = uuid::Uuid::new_v4();
+        let submission = Submission {
+            id: id.to_string(),
+            chain_id,
+            commitment,
+            status: SubmissionStatus::Pending,
+            record: None,
+            kzg_commitments: None,
+            error: None,
+        };
+        self.pending.write().insert(id, submission.clone());
+        submission
+    }
+
+    /// Persists a settled submission
+    pub async fn settle(
+        &self,
+        index: &dyn BlobStore,
+        submission: Submission,
+    ) -> Result<(), StoreError> {
+        let id: uuid::Uuid = submission.id.parse()?;
+        let encoded = Bytes::from(serde_json::to_vec(&submission)?);
+        let stored = index.put(&submission_key(&id), encoded).await;
+        // keep the failure visible in memory if it could not be persisted
+        let mut pending = self.pending.write();
+        match stored {
+            Ok(_) => {
+                pending.remove(&id);
+                Ok(())
+            }
+            Err(e) => {
+                pending.insert(id, submission);
+                Err(e)
+            }
+        }
+    }
+
+    pub async fn get(
+        &self,
+        index: &dyn BlobStore,
+        id: &uuid::Uuid,
+    ) -> Result<Option<Submission>, StoreError> {
+        if let Some(submission) = self.pending.read().get(id) {
+            return Ok(Some(submission.clone()));
+        }
+        match index.get(&submission_key(id)).await? {
+            Some(submission) => Ok(Some(serde_json::from_slice(&submission)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::MemoryStore;
+
+    #[tokio::test]
+    async fn test_submission_lifecycle() {
+        let index = MemoryStore::default();
+        let submissions = Submissions::default();
+        let mut submission = submissions.start(31338, "0x01".to_string());
+        let id: uuid::Uuid = submission.id.parse().unwrap();
+        let pending = submissions.get(&index, &id).await.unwrap().unwrap();
+        assert_eq!(pending.status, SubmissionStatus::Pending);
+
+        submission.status = SubmissionStatus::Failed;
+        submission.error = Some("Failed to store data".to_string());
+        submissions.settle(&index, submission).await.unwrap();
+        assert!(submissions.pending.read().is_empty());
+        // settled submissions are still found, also by a restarted server
+        let settled = Submissions::default()
+            .get(&index, &id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(settled.status, SubmissionStatus::Failed);
+        assert_eq!(settled.error.as_deref(), Some("Failed to store data"));
+
+        let unknown = uuid::Uuid::new_v4();
+        assert!(submissions.get(&index, &unknown).await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,453 @@
+use crate::avail::AvailReceipt;
+use async_trait::async_trait;
+use aws_sdk_s3::{
+    primitives::ByteStream,
+    types::{CompletedMultipartUpload, CompletedPart},
+    Client,
+};
+use axum::body::Bytes;
+use parking_lot::RwLock;
+use std::{
+    collections::{HashMap, VecDeque},
+    io::ErrorKind,
+    path::PathBuf,
+};
+use tokio::task::JoinHandle;
+
+/// Blobs larger than this are uploaded to S3 in parts
+const MULTIPART_THRESHOLD: usize = 16 * 1024 * 1024;
+/// Size of each part of a multipart upload, S3 needs at least 5 MiB for all but the last one
+const PART_SIZE: usize = 8 * 1024 * 1024;
+/// Parts of a multipart upload that are in flight at once
+const CONCURRENT_PARTS: usize = 4;
+
+pub type StoreError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Where a backend put a blob, returned to the submitter
+#[derive(Debug, Clone)]
+pub enum Receipt {
+    /// The blob is retrievable from the backend under its storage key
+    Stored,
+    /// The blob was included in an Avail block
+    Avail(AvailReceipt),
+}
+
+/// Backend that blobs are persisted to, keyed by their storage key
+#[async_trait]
+pub trait BlobStore: Send + Sync {
+    /// Short name of the backend for logging
+    fn name(&self) -> &'static str;
+
+    /// Whether the backend erasure codes and commits to blobs itself, leaving nothing for
+    /// `DA_ERASURE_CODING` to add
+    fn erasure_codes(&self) -> bool {
+        false
+    }
+
+    async fn put(&self, key: &str, data: Bytes) -> Result<Receipt, StoreError>;
+
+    /// Returns `None` if nothing is stored under `key`
+    async fn get(&self, key: &str) -> Result<Option<Bytes>, StoreError>;
+
+    /// Waits until the blob `receipt` was returned for can no longer be reorged out, which it
+    /// already cannot on backends that keep blobs themselves
+    async fn wait_finalized(&self, _receipt: &Receipt) -> Result<(), StoreError> {
+        Ok(())
+    }
+
+    /// Starts writing a blob as it streams in, which is held in memory until it is finished
+    /// unless the backend streams it
+    fn upload(&self) -> Box<dyn Upload> {
+        Box::<BufferedUpload>::default()
+    }
+}
+
+/// A blob written to a backend as it streams in, before the key it is stored under is known
+///
+/// An upload that failed to write is aborted by its caller, one that failed to finish has
+/// already cleaned up after itself.
+#[async_trait]
+pub trait Upload: Send {
+    async fn write(&mut self, chunk: Bytes) -> Result<(), StoreError>;
+
+    /// Stores everything written under `key` of `store`
+    async fn finish(
+        self: Box<Self>,
+        store: &dyn BlobStore,
+        key: &str,
+    ) -> Result<Receipt, StoreError>;
+
+    /// Discards everything written
+    async fn abort(self: Box<Self>);
+}
+
+/// Holds a blob in memory, for backends that store a blob in one piece
+#[derive(Default)]
+pub struct BufferedUpload {
+    data: Vec<u8>,
+}
+
+#[async_trait]
+impl Upload for BufferedUpload {
+    async fn write(&mut self, chunk: Bytes) -> Result<(), StoreError> {
+        self.data.extend_from_slice(&chunk);
+        Ok(())
+    }
+
+    async fn finish(
+        self: Box<Self>,
+        store: &dyn BlobStore,
+        key: &str,
+    ) -> Result<Receipt, StoreError> {
+        store.put(key, Bytes::from(self.data)).await
+    }
+
+    async fn abort(self: Box<Self>) {}
+}
+
+pub struct S3Store {
+    client: Client,
+    bucket: String,
+}
+
+impl S3Store {
+    pub fn new(client: Client, bucket: String) -> Self {
+        Self { client, bucket }
+    }
+}
+
+/// Splits the full parts off the front of `buffer`, leaving what does not fill a part yet
+fn take_parts(buffer: &mut Vec<u8>) -> Vec<Vec<u8>> {
+    let mut parts = Vec::new();
+    while buffer.len() >= PART_SIZE {
+        let rest = buffer.split_off(PART_SIZE);
+        parts.push(std::mem::replace(buffer, rest));
+    }
+    parts
+}
+
+/// Streams a blob to S3, in `PART_SIZE` parts to `target` once it outgrows
+/// `MULTIPART_THRESHOLD`, several at a time
+///
+/// Smaller blobs are buffered and put straight under their key. A multipart upload to another
+/// target is copied to the key once it is complete.
+pub struct S3Upload {
+    client: Client,
+    bucket: String,
+    target: String,
+    buffer: Vec<u8>,
+    upload_id: Option<String>,
+    /// Parts being uploaded, in order
+    in_flight: VecDeque<JoinHandle<Result<CompletedPart, StoreError>>>,
+    completed: Vec<CompletedPart>,
+}
+
+impl S3Upload {
+    fn new(store: &S3Store, target: String) -> Self {
+        Self {
+            client: store.client.clone(),
+            bucket: store.bucket.clone(),
+            target,
+            buffer: Vec::new(),
+            upload_id: None,
+            in_flight: VecDeque::new(),
+            completed: Vec::new(),
+        }
+    }
+
+    /// Sends every full part of the buffer, once the multipart upload is started
+    async fn send_parts(&mut self) -> Result<(), StoreError> {
+        if self.upload_id.is_none() {
+            if self.buffer.len() <= MULTIPART_THRESHOLD {
+                return Ok(());
+            }
+            let upload_id = self
+                .client
+                .create_multipart_upload()
+                .bucket(&self.bucket)
+                .key(&self.target)
+                .send()
+                .await?
+                .upload_id
+                .ok_or("S3 did not return a multipart upload id")?;
+            self.upload_id = Some(upload_id);
+        }
+        for part in take_parts(&mut self.buffer) {
+            self.send_part(part).await?;
+        }
+        Ok(())
+    }
+
+    /// Starts uploading the next part, first waiting for the oldest one if too many are in
+    /// flight
+    async fn send_part(&mut self, data: Vec<u8>) -> Result<(), StoreError> {
+        if self.in_flight.len() >= CONCURRENT_PARTS {
+            if let Some(part) = self.in_flight.pop_front() {
+                self.completed.push(part.await??);
+            }
+        }
+        let part_number = (self.completed.len() + self.in_flight.len()) as i32 + 1;
+        let upload = self
+            .client
+            .upload_part()
+            .bucket(&self.bucket)
+            .key(&self.target)
+            .set_upload_id(self.upload_id.clone())
+            .part_number(part_number)
+            .body(ByteStream::from(data))
+            .send();
+        self.in_flight.push_back(tokio::spawn(async move {
+            let output = upload.await?;
+            Ok(CompletedPart::builder()
+                .set_e_tag(output.e_tag)
+                .part_number(part_number)
+                .build())
+        }));
+        Ok(())
+    }
+
+    async fn finish_at(&mut self, key: &str) -> Result<(), StoreError> {
+        if self.upload_id.is_none() {
+            self.client
+                .put_object()
+                .bucket(&self.bucket)
+                .key(key)
+                .body(ByteStream::from(std::mem::take(&mut self.buffer)))
+                .send()
+                .await?;
+            return Ok(());
+        }
+        if !self.buffer.is_empty() {
+            let last = std::mem::take(&mut self.buffer);
+            self.send_part(last).await?;
+        }
+        while let Some(part) = self.in_flight.pop_front() {
+            self.completed.push(part.await??);
+        }
+        self.client
+            .complete_multipart_upload()
+            .bucket(&self.bucket)
+            .key(&self.target)
+            .set_upload_id(self.upload_id.take())
+            .multipart_upload(
+                CompletedMultipartUpload::builder()
+                    .set_parts(Some(std::mem::take(&mut self.completed)))
+                    .build(),
+            )
+            .send()
+            .await?;
+        if self.target != key {
+            self.client
+                .copy_object()
+                .bucket(&self.bucket)
+                .copy_source(format!("{}/{}", self.bucket, self.target))
+                .key(key)
+                .send()
+                .await?;
+            self.client
+                .delete_object()
+                .bucket(&self.bucket)
+                .key(&self.target)
+                .send()
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn abort_parts(&mut self) {
+        for part in self.in_flight.drain(..) {
+            part.abort();
+        }
+        // incomplete uploads keep their parts billed until they are aborted
+        if let Some(upload_id) = self.upload_id.take() {
+            let _ = self
+                .client
+                .abort_multipart_upload()
+                .bucket(&self.bucket)
+                .key(&self.target)
+                .upload_id(upload_id)
+                .send()
+                .await;
+        }
+    }
+}
+
+#[async_trait]
+impl Upload for S3Upload {
+    async fn write(&mut self, chunk: Bytes) -> Result<(), StoreError> {
+        self.buffer.extend_from_slice(&chunk);
+        self.send_parts().await
+    }
+
+    async fn finish(
+        mut self: Box<Self>,
+        _store: &dyn BlobStore,
+        key: &str,
+    ) -> Result<Receipt, StoreError> {
+        match self.finish_at(key).await {
+            Ok(()) => Ok(Receipt::Stored),
+            Err(e) => {
+                self.abort_parts().await;
+                Err(e)
+            }
+        }
+    }
+
+    async fn abort(mut self: Box<Self>) {
+        self.abort_parts().await;
+    }
+}
+
+#[async_trait]
+impl BlobStore for S3Store {
+    fn name(&self) -> &'static str {
+        "S3"
+    }
+
+    async fn put(&self, key: &str, data: Bytes) -> Result<Receipt, StoreError> {
+        let mut upload = Box::new(S3Upload::new(self, key.to_string()));
+        match upload.write(data).await {
+            Ok(()) => upload.finish(self, key).await,
+            Err(e) => {
+                upload.abort().await;
+                Err(e)
+            }
+        }
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Bytes>, StoreError> {
+        let output = match self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await
+        {
+            Ok(output) => output,
+            Err(e) if e.as_service_error().is_some_and(|e| e.is_no_such_key()) => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Some(output.body.collect().await?.into_bytes()))
+    }
+
+    /// Uploads to a temporary key, as the key of a blob is only known once all of it is read
+    fn upload(&self) -> Box<dyn Upload> {
+        Box::new(S3Upload::new(
+            self,
+            format!("uploads/{}", uuid::Uuid::new_v4()),
+        ))
+    }
+}
+
+/// Stores each blob as a file under `root`, using the key as a relative path
+pub struct LocalStore {
+    root: PathBuf,
+}
+
+impl LocalStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+}
+
+#[async_trait]
+impl BlobStore for LocalStore {
+    fn name(&self) -> &'static str {
+        "local"
+    }
+
+    async fn put(&self, key: &str, data: Bytes) -> Result<Receipt, StoreError> {
+        let path = self.root.join(key);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        // write to a temporary file first so readers never see a partial blob
+        let tmp = path.with_extension("tmp");
+        tokio::fs::write(&tmp, &data).await?;
+        tokio::fs::rename(&tmp, &path).await?;
+        Ok(Receipt::Stored)
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Bytes>, StoreError> {
+        match tokio::fs::read(self.root.join(key)).await {
+            Ok(data) => Ok(Some(Bytes::from(data))),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// Keeps blobs in memory, for tests and throwaway local runs
+#[derive(Default)]
+pub struct MemoryStore {
+    blobs: RwLock<HashMap<String, Bytes>>,
+}
+
+#[async_trait]
+impl BlobStore for MemoryStore {
+    fn name(&self) -> &'static str {
+        "memory"
+    }
+
+    async fn put(&self, key: &str, data: Bytes) -> Result<Receipt, StoreError> {
+        self.blobs.write().insert(key.to_string(), data);
+        Ok(Receipt::Stored)
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Bytes>, StoreError> {
+        Ok(self.blobs.read().get(key).cloned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn roundtrip(store: &dyn BlobStore) {
+        // a streamed blob is only stored once it is finished
+        let mut upload = store.upload();
+        upload.write(Bytes::from_static(b"rei")).await.unwrap();
+        upload.write(Bytes::from_static(b"mann")).await.unwrap();
+        assert!(store.get("blobs/0xdef").await.unwrap().is_none());
+        upload.finish(store, "blobs/0xdef").await.unwrap();
+        assert_eq!(
+            store.get("blobs/0xdef").await.unwrap(),
+            Some(Bytes::from_static(b"reimann"))
+        );
+
+        assert!(store.get("blobs/missing").await.unwrap().is_none());
+        let data = Bytes::from_static(b"reimann");
+        store.put("blobs/0xabc", data.clone()).await.unwrap();
+        assert_eq!(store.get("blobs/0xabc").await.unwrap(), Some(data));
+        // overwriting a key replaces its contents
+        let data = Bytes::from_static(b"nexus");
+        store.put("blobs/0xabc", data.clone()).await.unwrap();
+        assert_eq!(store.get("blobs/0xabc").await.unwrap(), Some(data));
+    }
+
+    #[test]
+    fn test_take_parts() {
+        let mut buffer = vec![1u8; PART_SIZE - 1];
+        assert!(take_parts(&mut buffer).is_empty());
+        assert_eq!(buffer.len(), PART_SIZE - 1);
+        buffer.extend_from_slice(&[2u8; PART_SIZE + 2]);
+        let parts = take_parts(&mut buffer);
+        assert_eq!(parts.len(), 2);
+        assert!(parts.iter().all(|part| part.len() == PART_SIZE));
+        assert_eq!(parts[0][PART_SIZE - 1], 2);
+        assert_eq!(buffer, vec![2u8; 1]);
+    }
+
+    #[tokio::test]
+    async fn test_memory_store() {
+        roundtrip(&MemoryStore::default()).await;
+    }
+
+    #[tokio::test]
+    async fn test_local_store() {
+        let root = std::env::temp_dir().join(format!("da-store-{}", std::process::id()));
+        roundtrip(&LocalStore::new(&root)).await;
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
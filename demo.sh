@@ -67,9 +67,9 @@ mkdir -p logs
 
 echo "⬆️ Starting services..."
 
-# Start DA server
+# Start DA server, keeping blobs on disk under chains/da rather than in S3
 echo "🧱 Starting DA server..."
-cargo run --bin cli run da > logs/da.log 2>&1 &
+DA_STORE=local cargo run --bin cli run da > logs/da.log 2>&1 &
 pids+=($!)
 echo "ℹ️ DA server started with PID: ${pids[0]}"
 sleep 2
@@ -85,30 +85,37 @@ sleep 2
 echo "📜 Generating genesis files..."
 cargo run --bin cli genesis init
 
-# Start Nexus chain
-echo "🚀 Starting Nexus chain..."
-cargo run --bin cli run nexus > logs/nexus.log 2>&1 &
-pids+=($!)
-echo "ℹ️ Nexus chain started with PID: ${pids[2]}"
-sleep 1
-
-# Start rollup1
-echo "📤 Starting origin rollup..."
-cargo run --bin cli run rollup --name rollup1 > logs/rollup1.log 2>&1 &
-pids+=($!)
-echo "ℹ️ Rollup started with PID: ${pids[3]}"
-sleep 1
+# Start the chains, which read the settlers from the deployments of `test full init` once it
+# has run and run without them before
+start_chains() {
+    echo "🚀 Starting Nexus chain..."
+    cargo run --bin cli run nexus >> logs/nexus.log 2>&1 &
+    echo "ℹ️ Nexus chain started with PID: $!"
+    sleep 1
+
+    echo "📤 Starting origin rollup..."
+    cargo run --bin cli run rollup --name rollup1 --peer rollup2@http://127.0.0.1:8547 >> logs/rollup1.log 2>&1 &
+    echo "ℹ️ Rollup started with PID: $!"
+    sleep 1
+
+    echo "📥 Starting destination rollup..."
+    cargo run --bin cli run rollup --name rollup2 --port 8547 --p2p-port 30305 --authrpc-port 8553 --peer rollup1@http://127.0.0.1:8546 >> logs/rollup2.log 2>&1 &
+    echo "ℹ️ Rollup started with PID: $!"
+    sleep 1
+
+    # Wait for chains to be ready
+    echo "⏳ Waiting for chains to be ready..."
+    sleep 7
+}
 
-# Start rollup2
-echo "📥 Starting destination rollup..."
-cargo run --bin cli run rollup --name rollup2 --port 8547 --p2p-port 30305 --authrpc-port 8553 > logs/rollup2.log 2>&1 &
-pids+=($!)
-echo "ℹ️ Rollup started with PID: ${pids[4]}"
-sleep 1
+stop_chains() {
+    pkill -f da-exex
+    while pgrep -f da-exex > /dev/null; do
+        sleep 0.5
+    done
+}
 
-# Wait for chains to be ready
-echo "⏳ Waiting for chains to be ready..."
-sleep 7
+start_chains
 
 # Run test transfers
 echo "📤 Running test transfers..."
@@ -120,7 +127,10 @@ sleep 2
 echo "🏃 Running full Reimann initialization..."
 cargo run --bin cli test full init
 
-sleep 2
+# Restart the chains so they post to, index and verify against the deployed settlers
+echo "🔁 Restarting chains with the deployed settlers..."
+stop_chains
+start_chains
 
 # Run full test
 echo "🏃 Running full Reimann test..."
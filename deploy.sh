@@ -80,14 +80,14 @@ sleep 1
 
 # Start rollup1
 echo "📤 Starting origin rollup..."
-cargo run --bin cli run rollup --name rollup1 > logs/rollup1.log 2>&1 &
+cargo run --bin cli run rollup --name rollup1 --peer-rpc http://127.0.0.1:8547 > logs/rollup1.log 2>&1 &
 pids+=($!)
 echo "ℹ️ Rollup started with PID: ${pids[3]}"
 sleep 1
 
 # Start rollup2
 echo "📥 Starting destination rollup..."
-cargo run --bin cli run rollup --name rollup2 --port 8547 --p2p-port 30305 --authrpc-port 8553 > logs/rollup2.log 2>&1 &
+cargo run --bin cli run rollup --name rollup2 --port 8547 --p2p-port 30305 --authrpc-port 8553 --peer-rpc http://127.0.0.1:8546 > logs/rollup2.log 2>&1 &
 pids+=($!)
 echo "ℹ️ Rollup started with PID: ${pids[4]}"
 sleep 1
@@ -45,6 +45,16 @@ impl MerkleTree {
         self.root()
     }
 
+    /// Drops the leaves from `len` on, as when the blocks that appended them are reorged out
+    pub fn truncate(&mut self, len: usize) -> B256 {
+        if len < self.count {
+            self.tree[0].truncate(len);
+            self.count = len;
+            self.calc_branches();
+        }
+        self.root()
+    }
+
     #[inline]
     fn calc_branches(&mut self) {
         for i in 0..self.height {
@@ -336,6 +346,35 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_truncate() {
+        let mut tree = MerkleTree::new(4);
+        let mut roots = vec![tree.root()];
+        for i in 0..5 {
+            roots.push(tree.append(create_test_leaf(i)));
+        }
+
+        // truncating restores the root the tree had at that length
+        assert_eq!(tree.truncate(2), roots[2]);
+        assert_eq!(tree.len(), 2);
+        let proof = tree.generate_proof(1);
+        assert!(MerkleTree::verify_proof(
+            create_test_leaf(1),
+            1,
+            roots[2],
+            proof
+        ));
+        assert_eq!(tree.truncate(3), roots[2]);
+
+        assert_eq!(tree.append(create_test_leaf(9)), {
+            let mut other = MerkleTree::new(4);
+            other.append(create_test_leaf(0));
+            other.append(create_test_leaf(1));
+            other.append(create_test_leaf(9))
+        });
+        assert_eq!(tree.truncate(0), roots[0]);
+    }
+
     #[test]
     #[should_panic(expected = "assertion failed")]
     fn test_invalid_height() {
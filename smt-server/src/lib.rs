@@ -0,0 +1 @@
+pub mod incremental_merkle_tree;
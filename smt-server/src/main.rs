@@ -7,10 +7,9 @@ use axum::{
     routing::{get, post},
     Router,
 };
-mod incremental_merkle_tree;
-use incremental_merkle_tree::MerkleTree;
 use parking_lot::RwLock;
 use serde_json::json;
+use smt_server::incremental_merkle_tree::MerkleTree;
 use std::sync::Arc;
 
 struct AppState {
@@ -0,0 +1,156 @@
+use alloy::primitives::{Address, U256};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::Path};
+
+/// A token the solver fills orders with on a chain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenConfig {
+    /// Names the same asset on every chain
+    pub symbol: String,
+    pub address: Address,
+    #[serde(default = "default_decimals")]
+    pub decimals: u8,
+    /// Balance in base units under which the solver alerts and rebalances into this chain
+    #[serde(default)]
+    pub min_balance: Option<U256>,
+    /// Balance in base units rebalancing fills up to, and above which it takes from this chain
+    #[serde(default)]
+    pub target_balance: Option<U256>,
+}
+
+fn default_decimals() -> u8 {
+    18
+}
+
+/// A rollup the solver reads orders from and fills orders on
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChainConfig {
+    pub chain_id: u64,
+    /// HTTP RPC of a node serving the `reimann` and `debug` namespaces. Without `debug`,
+    /// orders sent through other contracts cannot be decoded and discovery stops at their
+    /// block.
+    pub rpc: String,
+    pub settler: Address,
+    #[serde(default)]
+    pub tokens: Vec<TokenConfig>,
+}
+
+impl ChainConfig {
+    pub fn token(&self, address: Address) -> Option<&TokenConfig> {
+        self.tokens.iter().find(|token| token.address == address)
+    }
+}
+
+#[derive(Deserialize)]
+struct RegistryFile {
+    chains: Vec<ChainConfig>,
+}
+
+/// The rollups the solver knows, by chain id
+///
+/// Every rollup is watched for orders, and an order is filled on the rollup its
+/// `destination` names, as long as that rollup is here too.
+#[derive(Debug, Clone)]
+pub struct ChainRegistry {
+    chains: BTreeMap<u64, ChainConfig>,
+}
+
+impl ChainRegistry {
+    /// Reads a registry from a JSON file holding `{ "chains": [...] }`
+    pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let file = std::fs::read_to_string(path)
+            .map_err(|err| format!("Failed to read {}: {}", path.display(), err))?;
+        let file: RegistryFile = serde_json::from_str(&file)?;
+        Self::new(file.chains)
+    }
+
+    pub fn new(chains: Vec<ChainConfig>) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        if chains.is_empty() {
+            return Err("The chain registry is empty".into());
+        }
+        let mut registry = BTreeMap::new();
+        for chain in chains {
+            let chain_id = chain.chain_id;
+            if registry.insert(chain_id, chain).is_some() {
+                return Err(format!("Chain {} is in the registry twice", chain_id).into());
+            }
+        }
+        Ok(Self { chains: registry })
+    }
+
+    pub fn get(&self, chain_id: u64) -> Option<&ChainConfig> {
+        self.chains.get(&chain_id)
+    }
+
+    /// The chain an order's `destination` names
+    pub fn destination(&self, destination: U256) -> Option<&ChainConfig> {
+        u64::try_from(destination)
+            .ok()
+            .and_then(|chain_id| self.get(chain_id))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ChainConfig> {
+        self.chains.values()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry() {
+        let file: RegistryFile = serde_json::from_str(
+            r#"{
+                "chains": [
+                    {
+                        "chainId": 31339,
+                        "rpc": "http://127.0.0.1:8547",
+                        "settler": "0x5FbDB2315678afecb367f032d93F642f64180aa3",
+                        "tokens": [
+                            {
+                                "symbol": "TERC20",
+                                "address": "0xe7f1725E7734CE288F8367e1Bb143E90bb3F0512",
+                                "minBalance": "1000000000000000000000"
+                            }
+                        ]
+                    },
+                    {
+                        "chainId": 31338,
+                        "rpc": "http://127.0.0.1:8546",
+                        "settler": "0x5FbDB2315678afecb367f032d93F642f64180aa3"
+                    }
+                ]
+            }"#,
+        )
+        .unwrap();
+        let registry = ChainRegistry::new(file.chains.clone()).unwrap();
+        let chain_ids = registry
+            .iter()
+            .map(|chain| chain.chain_id)
+            .collect::<Vec<_>>();
+        assert_eq!(chain_ids, vec![31338, 31339]);
+
+        let rollup2 = registry.destination(U256::from(31339)).unwrap();
+        let token = "0xe7f1725E7734CE288F8367e1Bb143E90bb3F0512"
+            .parse()
+            .unwrap();
+        assert_eq!(rollup2.token(token).unwrap().symbol, "TERC20");
+        assert_eq!(rollup2.token(token).unwrap().decimals, 18);
+        assert_eq!(
+            rollup2.token(token).unwrap().min_balance,
+            Some(U256::from(10).pow(U256::from(21)))
+        );
+        assert_eq!(rollup2.token(token).unwrap().target_balance, None);
+        assert!(registry.get(31338).unwrap().token(token).is_none());
+        assert!(registry.destination(U256::from(31337)).is_none());
+        assert!(registry.destination(U256::MAX).is_none());
+
+        let mut chains = file.chains;
+        chains.push(chains[0].clone());
+        assert!(ChainRegistry::new(chains).is_err());
+        assert!(ChainRegistry::new(Vec::new()).is_err());
+    }
+}
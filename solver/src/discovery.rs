@@ -0,0 +1,236 @@
+use crate::RollupSettler::{self, sendCall};
+use alloy::{
+    consensus::Transaction,
+    primitives::{Address, Bytes, B256},
+    providers::Provider,
+    rpc::types::{Filter, Log},
+    sol_types::{SolCall, SolEvent},
+    transports::http::{Client, Http},
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::time::Duration;
+
+/// Most blocks asked for in one `eth_getLogs` call
+pub const MAX_LOG_RANGE: u64 = 1000;
+
+/// Wait between two reads of the new blocks of a chain
+pub const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Longest wait before reading blocks that could not be read again
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+/// Failed reads of a chain in a row after which it is reported as stalled
+pub const PERSISTENT_FAILURES: u32 = 5;
+
+/// Wait before reading a chain that failed `failures` times in a row, doubling with every
+/// failure up to [`MAX_RETRY_DELAY`]
+pub fn backoff(failures: u32) -> Duration {
+    POLL_INTERVAL
+        .saturating_mul(2u32.saturating_pow(failures.saturating_sub(1)))
+        .min(MAX_RETRY_DELAY)
+}
+
+/// An order sent on the origin rollup, before the solver gives it a nonce
+#[derive(Debug, Clone)]
+pub struct SentOrder {
+    pub order_hash: B256,
+    pub block_number: u64,
+    /// The caller of `send`, which the settler records as the order's sender
+    pub sender: Address,
+    pub call: sendCall,
+}
+
+/// A frame of the `callTracer` output, with the fields needed to find `send` calls
+#[derive(Debug, Deserialize)]
+struct CallFrame {
+    from: Address,
+    #[serde(default)]
+    to: Option<Address>,
+    #[serde(default)]
+    input: Bytes,
+    #[serde(default)]
+    error: Option<String>,
+    #[serde(default)]
+    calls: Vec<CallFrame>,
+}
+
+/// Collects the `send` calls to `settler` that did not revert, in the order they ran
+fn settler_sends(frame: &CallFrame, settler: Address, sends: &mut Vec<(Address, sendCall)>) {
+    // the logs of a reverted frame are dropped with everything under it
+    if frame.error.is_some() {
+        return;
+    }
+    if frame.to == Some(settler) {
+        if let Ok(call) = sendCall::abi_decode(&frame.input, true) {
+            sends.push((frame.from, call));
+        }
+    }
+    for call in &frame.calls {
+        settler_sends(call, settler, sends);
+    }
+}
+
+/// Reads the orders sent to `settler` in blocks `from..=to`, in the order they were sent
+///
+/// Orders are found by their `OrderSent` logs. Their arguments are decoded from the
+/// transaction when it called the settler itself, and otherwise from a `callTracer` trace of
+/// it, which needs the `debug` namespace, so orders sent through other contracts or in
+/// multicalls are found too. Fails if the orders of a transaction do not match its calls.
+pub async fn sent_orders<P: Provider<Http<Client>>>(
+    provider: &P,
+    settler: Address,
+    from: u64,
+    to: u64,
+) -> Result<Vec<SentOrder>, Box<dyn std::error::Error + Send + Sync>> {
+    let filter = Filter::new()
+        .address(settler)
+        .event_signature(RollupSettler::OrderSent::SIGNATURE_HASH)
+        .from_block(from)
+        .to_block(to);
+    let logs = provider.get_logs(&filter).await?;
+    let mut orders = Vec::new();
+    // logs come in chain order, so the logs of a transaction are next to each other
+    for logs in logs.chunk_by(|a, b| a.transaction_hash == b.transaction_hash) {
+        orders.extend(transaction_orders(provider, settler, logs).await?);
+    }
+    Ok(orders)
+}
+
+async fn transaction_orders<P: Provider<Http<Client>>>(
+    provider: &P,
+    settler: Address,
+    logs: &[Log],
+) -> Result<Vec<SentOrder>, Box<dyn std::error::Error + Send + Sync>> {
+    let tx_hash = logs[0]
+        .transaction_hash
+        .ok_or("OrderSent log without a transaction hash")?;
+    let block_number = logs[0]
+        .block_number
+        .ok_or("OrderSent log without a block number")?;
+    let order_hashes = logs
+        .iter()
+        .map(|log| {
+            RollupSettler::OrderSent::decode_log_data(log.data(), true).map(|event| event.orderHash)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let tx = provider
+        .get_transaction_by_hash(tx_hash)
+        .await?
+        .ok_or_else(|| format!("Transaction {} of an OrderSent log is missing", tx_hash))?;
+    let sends = match sendCall::abi_decode(tx.inner.input(), true) {
+        Ok(call) if tx.inner.to() == Some(settler) => vec![(tx.from, call)],
+        _ => {
+            let frame: CallFrame = provider
+                .raw_request(
+                    "debug_traceTransaction".into(),
+                    (tx_hash, json!({ "tracer": "callTracer" })),
+                )
+                .await?;
+            let mut sends = Vec::new();
+            settler_sends(&frame, settler, &mut sends);
+            sends
+        }
+    };
+    // the orders cannot be matched to their calls, which a node without the `debug`
+    // namespace causes too, so none of them is dropped in silence
+    if sends.len() != order_hashes.len() {
+        return Err(format!(
+            "Found {} send calls for the {} orders of {}",
+            sends.len(),
+            order_hashes.len(),
+            tx_hash
+        )
+        .into());
+    }
+    Ok(order_hashes
+        .into_iter()
+        .zip(sends)
+        .map(|(order_hash, (sender, call))| SentOrder {
+            order_hash,
+            block_number,
+            sender,
+            call,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::U256;
+
+    fn send(amount_in: u64) -> sendCall {
+        sendCall {
+            fillDeadline: 100,
+            fromToken: Address::with_last_byte(1),
+            toToken: Address::with_last_byte(2),
+            recipient: Address::with_last_byte(3),
+            amountIn: U256::from(amount_in),
+            minAmountOut: U256::from(amount_in),
+            destination: U256::from(31339),
+        }
+    }
+
+    fn frame(from: u8, to: u8, input: Bytes, error: bool, calls: Vec<CallFrame>) -> CallFrame {
+        CallFrame {
+            from: Address::with_last_byte(from),
+            to: Some(Address::with_last_byte(to)),
+            input,
+            error: error.then(|| "execution reverted".to_string()),
+            calls,
+        }
+    }
+
+    #[test]
+    fn test_backoff() {
+        assert_eq!(backoff(1), POLL_INTERVAL);
+        assert_eq!(backoff(3), Duration::from_millis(800));
+        assert_eq!(backoff(12), MAX_RETRY_DELAY);
+        assert_eq!(backoff(u32::MAX), MAX_RETRY_DELAY);
+    }
+
+    #[test]
+    fn test_settler_sends() {
+        let settler = Address::with_last_byte(9);
+        let call = |amount_in| Bytes::from(send(amount_in).abi_encode());
+        // a multicall sending three orders, one of them reverted and caught
+        let trace = frame(
+            1,
+            5,
+            Bytes::new(),
+            false,
+            vec![
+                frame(
+                    5,
+                    9,
+                    call(1),
+                    false,
+                    vec![frame(9, 2, Bytes::new(), false, vec![])],
+                ),
+                frame(
+                    5,
+                    6,
+                    Bytes::new(),
+                    true,
+                    vec![frame(6, 9, call(2), false, vec![])],
+                ),
+                frame(
+                    5,
+                    6,
+                    Bytes::new(),
+                    false,
+                    vec![frame(6, 9, call(3), false, vec![])],
+                ),
+                frame(5, 8, call(4), false, vec![]),
+            ],
+        );
+        let mut sends = Vec::new();
+        settler_sends(&trace, settler, &mut sends);
+        let sends = sends
+            .iter()
+            .map(|(sender, call)| (sender.0[19], call.amountIn.to::<u64>()))
+            .collect::<Vec<_>>();
+        assert_eq!(sends, vec![(5, 1), (6, 3)]);
+    }
+}
@@ -0,0 +1,376 @@
+use crate::{
+    chains::{ChainRegistry, TokenConfig},
+    MockERC20,
+};
+use alloy::{
+    primitives::{Address, U256},
+    providers::Provider,
+    transports::http::{Client, Http},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    io::Write,
+    path::PathBuf,
+};
+
+/// Decimals balances of the same token on different chains are compared in
+const COMMON_DECIMALS: u8 = 18;
+
+/// A balance that dropped under its token's `minBalance`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LowBalance {
+    pub chain_id: u64,
+    pub token: Address,
+    pub symbol: String,
+    pub balance: U256,
+    pub min_balance: U256,
+}
+
+/// What the solver holds of each listed token on each chain
+#[derive(Debug, Default)]
+pub struct Inventory {
+    balances: HashMap<(u64, Address), U256>,
+    /// Balances already alerted on, so each drop under the minimum is alerted once
+    low: HashSet<(u64, Address)>,
+    /// JSON lines file alerts are appended to
+    alerts: Option<PathBuf>,
+}
+
+impl Inventory {
+    pub fn new(alerts: PathBuf) -> Self {
+        Self {
+            alerts: Some(alerts),
+            ..Default::default()
+        }
+    }
+
+    pub fn balance(&self, chain_id: u64, token: Address) -> U256 {
+        self.balances
+            .get(&(chain_id, token))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Takes what a fill paid out of a balance, until the next refresh reads it again
+    pub fn spend(&mut self, chain_id: u64, token: Address, amount: U256) {
+        if let Some(balance) = self.balances.get_mut(&(chain_id, token)) {
+            *balance = balance.saturating_sub(amount);
+        }
+    }
+
+    /// Records a balance read from its chain, returning an alert if it just dropped under the
+    /// token's minimum
+    fn record(&mut self, chain_id: u64, token: &TokenConfig, balance: U256) -> Option<LowBalance> {
+        let key = (chain_id, token.address);
+        self.balances.insert(key, balance);
+        match token.min_balance {
+            Some(min_balance) if balance < min_balance => {
+                self.low.insert(key).then(|| LowBalance {
+                    chain_id,
+                    token: token.address,
+                    symbol: token.symbol.clone(),
+                    balance,
+                    min_balance,
+                })
+            }
+            _ => {
+                self.low.remove(&key);
+                None
+            }
+        }
+    }
+
+    /// Reads the balances of every listed token, approves settlers whose allowance ran low and
+    /// alerts on balances under their minimum
+    ///
+    /// Settlers are approved for `U256::MAX` once, so fills do not approve each time.
+    pub async fn refresh<P: Provider<Http<Client>>>(
+        &mut self,
+        registry: &ChainRegistry,
+        providers: &HashMap<u64, P>,
+        owner: Address,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        for chain in registry.iter() {
+            for token in &chain.tokens {
+                let erc20 = MockERC20::new(token.address, &providers[&chain.chain_id]);
+                let allowance = erc20.allowance(owner, chain.settler).call().await?._0;
+                if allowance < U256::MAX >> 1 {
+                    erc20
+                        .approve(chain.settler, U256::MAX)
+                        .send()
+                        .await?
+                        .with_required_confirmations(1)
+                        .watch()
+                        .await?;
+                    println!(
+                        "🔓 Approved settler {} to spend {} on chain {}",
+                        chain.settler, token.symbol, chain.chain_id
+                    );
+                }
+                let balance = erc20.balanceOf(owner).call().await?._0;
+                if let Some(alert) = self.record(chain.chain_id, token, balance) {
+                    self.raise(&alert)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn raise(&self, alert: &LowBalance) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        eprintln!(
+            "🪫 Holding {} {} on chain {}, under the minimum of {}",
+            alert.balance, alert.symbol, alert.chain_id, alert.min_balance
+        );
+        if let Some(path) = &self.alerts {
+            let mut file = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)?;
+            writeln!(file, "{}", serde_json::to_string(alert)?)?;
+        }
+        Ok(())
+    }
+}
+
+/// Funds to move between rollups by sending an order through the origin settler
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transfer {
+    pub symbol: String,
+    pub from_chain: u64,
+    pub from_token: Address,
+    /// In base units of `from_token`
+    pub amount: U256,
+    pub to_chain: u64,
+    pub to_token: Address,
+    /// `amount` in base units of `to_token`
+    pub received: U256,
+}
+
+/// A rebalancing order the solver sent, paying itself on `to_chain`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InFlight {
+    pub to_chain: u64,
+    pub to_token: Address,
+    pub amount_out: U256,
+    pub deadline: u64,
+}
+
+/// Converts an amount between token decimals, rounding down
+fn scale(amount: U256, from: u8, to: u8) -> U256 {
+    let factor = |decimals: u8| U256::from(10).pow(U256::from(decimals));
+    if to >= from {
+        amount.saturating_mul(factor(to - from))
+    } else {
+        amount / factor(from - to)
+    }
+}
+
+/// Plans transfers that bring every balance under its `minBalance` back up to its
+/// `targetBalance`, taking from the chains that hold the most over theirs
+///
+/// Tokens are matched across chains by symbol. `incoming` is what orders still in flight will
+/// bring to each chain and token. A token without a `targetBalance` is never taken from.
+///
+/// Nothing is planned into the chains and tokens in `stalled`, whose earlier rebalancing order
+/// expired. It may have gone unfilled, and its input stays in escrow until the settler can
+/// release it, so planning the transfer again would only lock up more.
+pub fn plan_rebalance(
+    registry: &ChainRegistry,
+    inventory: &Inventory,
+    incoming: &HashMap<(u64, Address), U256>,
+    stalled: &HashSet<(u64, Address)>,
+) -> Vec<Transfer> {
+    let mut by_symbol: HashMap<&str, Vec<(u64, &TokenConfig)>> = HashMap::new();
+    for chain in registry.iter() {
+        for token in &chain.tokens {
+            by_symbol
+                .entry(&token.symbol)
+                .or_default()
+                .push((chain.chain_id, token));
+        }
+    }
+    let mut symbols = by_symbol.keys().copied().collect::<Vec<_>>();
+    symbols.sort();
+
+    let norm = |amount, token: &TokenConfig| scale(amount, token.decimals, COMMON_DECIMALS);
+    let mut transfers = Vec::new();
+    for symbol in symbols {
+        // amounts in common decimals
+        let mut deficits = Vec::new();
+        let mut surpluses = Vec::new();
+        for &(chain_id, token) in &by_symbol[symbol] {
+            let key = (chain_id, token.address);
+            let held = inventory
+                .balance(chain_id, token.address)
+                .saturating_add(incoming.get(&key).copied().unwrap_or_default());
+            let held = norm(held, token);
+            let min = token.min_balance.map(|min| norm(min, token));
+            let target = token.target_balance.map(|target| norm(target, token));
+            match (min, target) {
+                (Some(min), target) if held < min && !stalled.contains(&key) => {
+                    let target = target.unwrap_or(min).max(min);
+                    deficits.push((target - held, chain_id, token));
+                }
+                (_, Some(target)) if held > target => {
+                    surpluses.push((held - target, chain_id, token));
+                }
+                _ => {}
+            }
+        }
+        // largest first, ties broken by chain id so plans are stable
+        deficits.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+        surpluses.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+        for (mut need, to_chain, to_token) in deficits {
+            for (surplus, from_chain, from_token) in surpluses.iter_mut() {
+                if need.is_zero() {
+                    break;
+                }
+                let moved = need.min(*surplus);
+                // what the origin token's decimals can carry of it
+                let amount = scale(moved, COMMON_DECIMALS, from_token.decimals);
+                if amount.is_zero() {
+                    continue;
+                }
+                let moved = norm(amount, from_token);
+                *surplus -= moved;
+                need -= moved;
+                transfers.push(Transfer {
+                    symbol: symbol.to_string(),
+                    from_chain: *from_chain,
+                    from_token: from_token.address,
+                    amount,
+                    to_chain,
+                    to_token: to_token.address,
+                    received: scale(moved, COMMON_DECIMALS, to_token.decimals),
+                });
+            }
+        }
+    }
+    transfers
+}
+
+/// What the order of a transfer asks for, once the fee for the solver filling it is taken out
+pub fn amount_out(transfer: &Transfer, fee_bps: u64) -> U256 {
+    transfer.received - transfer.received * U256::from(fee_bps) / U256::from(10_000)
+}
+
+/// Sums what rebalancing orders before their deadline bring, by chain and token
+///
+/// A filled order is counted until its deadline too, as the solver cannot tell its output
+/// from the rest of the balance.
+pub fn incoming(in_flight: &[InFlight], now: u64) -> HashMap<(u64, Address), U256> {
+    let mut incoming: HashMap<_, U256> = HashMap::new();
+    for order in in_flight.iter().filter(|order| order.deadline >= now) {
+        let amount = incoming
+            .entry((order.to_chain, order.to_token))
+            .or_default();
+        *amount = amount.saturating_add(order.amount_out);
+    }
+    incoming
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chains::ChainConfig;
+
+    fn ether(amount: u64) -> U256 {
+        U256::from(amount) * U256::from(10).pow(U256::from(18))
+    }
+
+    fn chain(chain_id: u64, token: TokenConfig) -> ChainConfig {
+        ChainConfig {
+            chain_id,
+            rpc: format!("http://127.0.0.1:{}", chain_id),
+            settler: Address::with_last_byte(chain_id as u8),
+            tokens: vec![token],
+        }
+    }
+
+    fn terc20(chain_id: u64, decimals: u8) -> TokenConfig {
+        let unit = U256::from(10).pow(U256::from(decimals));
+        TokenConfig {
+            symbol: "TERC20".to_string(),
+            address: Address::with_last_byte(chain_id as u8 + 100),
+            decimals,
+            min_balance: Some(U256::from(100) * unit),
+            target_balance: Some(U256::from(500) * unit),
+        }
+    }
+
+    #[test]
+    fn test_low_balance_alerts() {
+        let token = terc20(1, 18);
+        let mut inventory = Inventory::default();
+        assert_eq!(inventory.record(1, &token, ether(200)), None);
+        let alert = inventory.record(1, &token, ether(50)).unwrap();
+        assert_eq!(alert.min_balance, ether(100));
+        // alerted once until the balance recovers
+        assert_eq!(inventory.record(1, &token, ether(40)), None);
+        assert_eq!(inventory.record(1, &token, ether(150)), None);
+        assert!(inventory.record(1, &token, ether(10)).is_some());
+
+        inventory.spend(1, token.address, ether(4));
+        assert_eq!(inventory.balance(1, token.address), ether(6));
+        inventory.spend(1, token.address, ether(10));
+        assert_eq!(inventory.balance(1, token.address), U256::ZERO);
+    }
+
+    #[test]
+    fn test_plan_rebalance() {
+        // chain 3 counts its token in 6 decimals
+        let registry = ChainRegistry::new(vec![
+            chain(1, terc20(1, 18)),
+            chain(2, terc20(2, 18)),
+            chain(3, terc20(3, 6)),
+        ])
+        .unwrap();
+        let token = |chain_id| registry.get(chain_id).unwrap().tokens[0].clone();
+        let mut inventory = Inventory::default();
+        inventory.record(1, &token(1), ether(1_000));
+        inventory.record(2, &token(2), ether(20));
+        inventory.record(3, &token(3), U256::from(700_000_000));
+
+        // chain 2 needs 480, chain 1 has 500 over target and chain 3 200
+        let transfers = plan_rebalance(&registry, &inventory, &HashMap::new(), &HashSet::new());
+        assert_eq!(transfers.len(), 1);
+        assert_eq!(transfers[0].from_chain, 1);
+        assert_eq!(transfers[0].to_chain, 2);
+        assert_eq!(transfers[0].amount, ether(480));
+        assert_eq!(transfers[0].received, ether(480));
+
+        // with chain 1 drained too, only chain 3 has any to spare, in its own decimals, and
+        // the largest need is met first
+        inventory.record(1, &token(1), ether(50));
+        let transfers = plan_rebalance(&registry, &inventory, &HashMap::new(), &HashSet::new());
+        assert_eq!(transfers.len(), 1);
+        assert_eq!(transfers[0].from_chain, 3);
+        assert_eq!(transfers[0].to_chain, 2);
+        assert_eq!(transfers[0].amount, U256::from(200_000_000));
+        assert_eq!(transfers[0].received, ether(200));
+
+        // orders in flight count as held
+        assert_eq!(amount_out(&transfers[0], 10), ether(1998) / U256::from(10));
+        let in_flight = [1, 2]
+            .map(|to_chain| InFlight {
+                to_chain,
+                to_token: token(to_chain).address,
+                amount_out: ether(450),
+                deadline: 100,
+            })
+            .to_vec();
+        let arriving = incoming(&in_flight, 100);
+        assert!(plan_rebalance(&registry, &inventory, &arriving, &HashSet::new()).is_empty());
+        assert!(incoming(&in_flight, 101).is_empty());
+
+        // an expired order into chain 2 holds back the transfer it was for, chain 1 gets the
+        // spare funds instead
+        let stalled = HashSet::from([(2, token(2).address)]);
+        let transfers = plan_rebalance(&registry, &inventory, &HashMap::new(), &stalled);
+        assert_eq!(transfers.len(), 1);
+        assert_eq!(transfers[0].to_chain, 1);
+    }
+}
@@ -1,29 +1,32 @@
 use alloy::{
-    consensus::Transaction,
-    eips::BlockNumberOrTag,
     network::EthereumWallet,
     primitives::{Address, B256, U256},
     providers::{Provider, ProviderBuilder},
-    rpc::types::BlockTransactionsKind,
     signers::local::PrivateKeySigner,
     sol,
-    sol_types::SolCall,
+    sol_types::SolEvent,
 };
-use parking_lot::RwLock;
+use chains::{ChainConfig, ChainRegistry};
+use clap::Parser;
+use discovery::{backoff, sent_orders, MAX_LOG_RANGE, PERSISTENT_FAILURES, POLL_INTERVAL};
+use inventory::{amount_out, incoming, plan_rebalance, InFlight, Inventory};
 use reqwest::Url;
-use serde::Deserialize;
-use serde_json::Value;
-use std::{collections::HashMap, fs, sync::Arc};
-use tokio::{self, try_join};
-use RollupSettler::sendCall;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    sync::Arc,
+};
+use store::{OrderState, OrderStore, StoredOrder};
+use strategy::{Decision, ProfitStrategy, Quote, StaticPrices, Strategy};
 
-// Re-export contract types
-sol!(
-    #[sol(rpc)]
-    NexusSettler,
-    "../cli/artifacts/NexusSettler.json"
-);
+mod chains;
+mod discovery;
+mod inventory;
+mod store;
+mod strategy;
 
+// Re-export contract types
 sol!(
     #[sol(rpc)]
     RollupSettler,
@@ -38,12 +41,55 @@ sol!(
 
 const PRIVATE_KEY: &str = "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
 
-struct SharedState {
-    orders: Arc<RwLock<HashMap<B256, Order>>>,
-    order_hashes: Arc<RwLock<Vec<B256>>>,
+/// Where the solver keeps its orders across restarts
+const ORDER_DB_PATH: &str = "chains/solver/orders.redb";
+
+/// Where `cli test full init` writes the chain registry of the local rollups
+const CHAINS_PATH: &str = "chains/solver/chains.json";
+
+/// Where `cli test full init` writes the token prices the solver values orders with
+const PRICES_PATH: &str = "chains/solver/prices.json";
+
+/// Where low-balance alerts are appended, one JSON object per line
+const ALERTS_PATH: &str = "chains/solver/alerts.jsonl";
+
+/// Seconds other solvers get to fill a rebalancing order
+const REBALANCE_LIFETIME: u64 = 600;
+
+/// Fills orders between the rollups of a chain registry
+#[derive(Parser)]
+struct Args {
+    /// JSON file listing the rollups to watch and fill on
+    #[arg(long, default_value = CHAINS_PATH)]
+    chains: PathBuf,
+
+    /// JSON file of USD prices by token symbol, including the gas token `ETH`
+    #[arg(long, default_value = PRICES_PATH)]
+    prices: PathBuf,
+
+    /// Least profit in USD an order must make after gas to be filled
+    #[arg(long, default_value_t = 0.0)]
+    min_profit: f64,
+
+    /// Seconds that must be left before an order's deadline to start filling it
+    #[arg(long, default_value_t = 10)]
+    deadline_margin: u64,
+
+    /// Send orders moving funds to the chains running low, for other solvers to fill
+    ///
+    /// Off by default. If no other solver fills a rebalancing order before it expires, its escrow
+    /// stays locked in the settler, which has no refund for expired orders. This solver never
+    /// fills its own rebalancing orders.
+    #[arg(long)]
+    rebalance: bool,
+
+    /// Share of each rebalancing transfer, in basis points, left to the solver that fills it
+    #[arg(long, default_value_t = 10)]
+    rebalance_fee_bps: u64,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct Order {
     fill_deadline: u32,
     from_token: Address,
@@ -52,234 +98,297 @@ struct Order {
     recipient: Address,
     amount_in: U256,
     min_amount_out: U256,
+    /// Chain id of the rollup the order was sent on
+    source: u64,
     destination: U256,
     nonce: u32,
     order_hash: B256,
 }
 
-#[derive(Clone)]
-struct ContractAddresses {
-    nexus_settler: Address,
-    rollup1_settler: Address,
-    rollup2_settler: Address,
-    rollup1_erc20: Address,
-    rollup2_erc20: Address,
-}
-
-#[derive(Clone)]
-struct Chains {
-    nexus: ChainData,
-    rollup1: ChainData,
-    rollup2: ChainData,
-}
-
-#[derive(Clone)]
-struct ChainData {
-    http_url: String,
-}
-
-async fn load_contract_addresses(
-) -> Result<ContractAddresses, Box<dyn std::error::Error + Send + Sync>> {
-    let deployment_json = fs::read_to_string("chains/deployments/run-latest.json")?;
-    let deployment: Value = serde_json::from_str(&deployment_json)?;
-
-    Ok(ContractAddresses {
-        nexus_settler: deployment["nexusSettler"]["address"]
-            .as_str()
-            .unwrap_or("Missing Nexus settler address")
-            .parse::<Address>()?,
-        rollup1_settler: deployment["rollup1Settler"]["address"]
-            .as_str()
-            .unwrap_or("Missing Rollup1 settler address")
-            .parse::<Address>()?,
-        rollup2_settler: deployment["rollup2Settler"]["address"]
-            .as_str()
-            .unwrap_or("Missing Rollup2 settler address")
-            .parse::<Address>()?,
-        rollup1_erc20: deployment["rollup1ERC20"]["address"]
-            .as_str()
-            .unwrap_or("Missing Rollup1 token address")
-            .parse::<Address>()?,
-        rollup2_erc20: deployment["rollup2ERC20"]["address"]
-            .as_str()
-            .unwrap_or("Missing Rollup2 token address")
-            .parse::<Address>()?,
-    })
+/// An order as the origin node indexed it
+#[derive(Deserialize)]
+struct IndexedOrder {
+    nonce: u32,
 }
 
+/// A `reimann_orderProof` result
 #[derive(Deserialize)]
-struct AppendResponse {
-    index: u32,
+struct OrderProof {
+    root: B256,
+    proof: Vec<B256>,
 }
 
+/// Reads the orders sent on a chain into the store, forever
+///
+/// Blocks that cannot be read are read again, backing off while the chain keeps failing so
+/// an unreachable node is neither hammered nor allowed to stop the other chains.
 async fn read_orders(
-    state: Arc<SharedState>,
-    chains: Chains,
-    wallet: Arc<EthereumWallet>,
+    store: Arc<OrderStore>,
+    chain: ChainConfig,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    let http_provider = ProviderBuilder::new()
-        .with_recommended_fillers()
-        .wallet(&wallet)
-        .on_http(chains.rollup1.http_url.parse::<Url>()?);
-    let client = reqwest::Client::new();
-    let mut from = http_provider.get_block_number().await?;
-    let mut to = from;
+    let http_provider = ProviderBuilder::new().on_http(chain.rpc.parse::<Url>()?);
+    // pick up where the last run stopped, so orders sent while the solver was down are found
+    let mut from = store
+        .last_block(chain.chain_id)?
+        .map(|last_block| last_block + 1);
+    let mut failures = 0;
     loop {
-        println!("ℹ️ Checking blocks {} to {}", from, to);
-        for i in from..(to + 1) {
-            let block = http_provider
-                .get_block_by_number(BlockNumberOrTag::from(i), BlockTransactionsKind::Full)
-                .await?
-                .unwrap();
-            let mut hashes = block.transactions.hashes();
-            // check block for send() transactions
-            for tx in block.transactions.txns() {
-                let tx = tx.clone();
-                let data = tx.inner.input();
-                let tx_hash = hashes.next().unwrap();
-                if let Ok(decoded) = sendCall::abi_decode(data, false) {
-                    let receipt = http_provider
-                        .get_transaction_receipt(tx_hash)
-                        .await?
-                        .unwrap();
-                    if !receipt.inner.status() {
-                        continue;
-                    }
-                    let order_hash = receipt.inner.logs()[0].data().data.clone();
-                    let response = client
-                        .post("http://127.0.0.1:3001/add")
-                        .body(format!("{}", order_hash))
-                        .send()
-                        .await?;
-                    let index = response.json::<AppendResponse>().await?;
-                    let order = Order {
-                        fill_deadline: decoded.fillDeadline,
-                        from_token: decoded.fromToken,
-                        to_token: decoded.toToken,
-                        sender: tx.from,
-                        recipient: decoded.recipient,
-                        amount_in: decoded.amountIn,
-                        min_amount_out: decoded.minAmountOut,
-                        destination: decoded.destination,
-                        nonce: index.index,
-                        order_hash: B256::from_slice(&order_hash),
-                    };
-                    {
-                        let orders_guard = state.orders.try_write();
-                        match orders_guard {
-                            Some(mut orders) => {
-                                orders.insert(order.order_hash, order.clone());
-                            }
-                            None => eprintln!("❌ Failed to acquire write lock for orders"),
-                        }
-                    }
-                    {
-                        let order_hashes_guard = state.order_hashes.try_write();
-                        match order_hashes_guard {
-                            Some(mut order_hashes) => {
-                                order_hashes.push(order.order_hash);
-                            }
-                            None => eprintln!("Failed to acquire write lock for order_hashes"),
-                        }
-                    }
-                    println!("🔎 Found an order: {:?}", order);
+        match read_new_orders(&store, &chain, &http_provider, &mut from).await {
+            Ok(()) => {
+                if failures > 0 {
+                    println!(
+                        "ℹ️ Reading orders of chain {} again after {} failures",
+                        chain.chain_id, failures
+                    );
                 }
+                failures = 0;
+                tokio::time::sleep(POLL_INTERVAL).await;
+            }
+            Err(e) => {
+                // the blocks are read again until every order in them is found
+                failures += 1;
+                let delay = backoff(failures);
+                if failures >= PERSISTENT_FAILURES {
+                    eprintln!(
+                        "❌ Reading orders of chain {} failed {} times in a row, no orders are found on it until it recovers, retrying in {:?}: {}",
+                        chain.chain_id, failures, delay, e
+                    );
+                } else {
+                    eprintln!(
+                        "⚠️ Cannot read the orders of chain {}, retrying in {:?}: {}",
+                        chain.chain_id, delay, e
+                    );
+                }
+                tokio::time::sleep(delay).await;
             }
         }
-        tokio::time::sleep(tokio::time::Duration::from_millis(200)).await;
-        from = to + 1;
-        to = http_provider.get_block_number().await?;
     }
 }
 
-#[derive(Deserialize)]
-struct ProofResponse {
-    proof: [B256; 32],
+/// Reads the orders sent in the blocks from `from` up to the chain's head, advancing `from`
+/// past every block whose orders are in the store
+///
+/// Stops early at an order the node has not indexed yet. `from` starts at the head if the
+/// chain was never read.
+async fn read_new_orders<P: Provider<alloy::transports::http::Http<reqwest::Client>>>(
+    store: &OrderStore,
+    chain: &ChainConfig,
+    http_provider: &P,
+    from: &mut Option<u64>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let head = http_provider.get_block_number().await?;
+    let from = from.get_or_insert(head);
+    while *from <= head {
+        let to = head.min(*from + MAX_LOG_RANGE - 1);
+        println!(
+            "ℹ️ Checking blocks {} to {} of chain {}",
+            from, to, chain.chain_id
+        );
+        let sent = sent_orders(http_provider, chain.settler, *from, to)
+            .await
+            .map_err(|e| format!("Cannot read the orders in blocks {} to {}: {}", from, to, e))?;
+        let mut orders = Vec::new();
+        for sent in sent {
+            if store.contains(&sent.order_hash)? {
+                continue;
+            }
+            // the node indexes a block's orders once it is committed, which may be after
+            // its logs are served
+            let indexed: Option<IndexedOrder> = http_provider
+                .raw_request("reimann_getOrder".into(), (sent.order_hash,))
+                .await
+                .map_err(|e| format!("Cannot look up order {}: {}", sent.order_hash, e))?;
+            let Some(indexed) = indexed else {
+                return Ok(());
+            };
+            let order = Order {
+                fill_deadline: sent.call.fillDeadline,
+                from_token: sent.call.fromToken,
+                to_token: sent.call.toToken,
+                sender: sent.sender,
+                recipient: sent.call.recipient,
+                amount_in: sent.call.amountIn,
+                min_amount_out: sent.call.minAmountOut,
+                source: chain.chain_id,
+                destination: sent.call.destination,
+                nonce: indexed.nonce,
+                order_hash: sent.order_hash,
+            };
+            println!(
+                "🔎 Found an order in block {}: {:?}",
+                sent.block_number, order
+            );
+            orders.push(order);
+        }
+        store.record_block(chain.chain_id, to, &orders)?;
+        *from = to + 1;
+    }
+    Ok(())
+}
+
+/// Settles an order left in `Filling`, by a crash or by a transaction still in the pool
+async fn check_fill<P: Provider<alloy::transports::http::Http<reqwest::Client>>>(
+    store: &OrderStore,
+    provider: &P,
+    stored: &StoredOrder,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let order_hash = stored.order.order_hash;
+    let Some(fill_tx) = stored.fill_tx else {
+        store.set_state(&order_hash, OrderState::ProofReady)?;
+        return Ok(());
+    };
+    if let Some(receipt) = provider.get_transaction_receipt(fill_tx).await? {
+        if receipt.inner.status() {
+            store.set_state(&order_hash, OrderState::Filled)?;
+            println!("✅ Order {} was fulfilled by {}", order_hash, fill_tx);
+        } else {
+            store.failed(
+                &order_hash,
+                format!("Fill transaction {} reverted", fill_tx),
+            )?;
+            println!("❌ Fill {} of order {} reverted", fill_tx, order_hash);
+        }
+    } else if provider.get_transaction_by_hash(fill_tx).await?.is_none() {
+        // the transaction never reached the node, so the order can be filled again
+        store.set_state(&order_hash, OrderState::ProofReady)?;
+        println!(
+            "🔁 Fill {} of order {} was lost, filling again",
+            fill_tx, order_hash
+        );
+    }
+    Ok(())
 }
 
 async fn fill_orders(
-    state: Arc<SharedState>,
-    chains: Chains,
-    addresses: ContractAddresses,
+    store: Arc<OrderStore>,
+    registry: &ChainRegistry,
+    strategy: &dyn Strategy,
+    inventory: &mut Inventory,
     wallet: Arc<EthereumWallet>,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    let nexus_provider = ProviderBuilder::new()
-        .with_recommended_fillers()
-        .wallet(&wallet)
-        .on_http(chains.nexus.http_url.parse::<Url>()?);
-
-    let rollup1_provider = ProviderBuilder::new()
-        .with_recommended_fillers()
-        .wallet(&wallet)
-        .on_http(chains.rollup1.http_url.parse::<Url>()?);
-
-    let rollup2_provider = ProviderBuilder::new()
-        .with_recommended_fillers()
-        .wallet(&wallet)
-        .on_http(chains.rollup2.http_url.parse::<Url>()?);
-
-    let nexus_settler = NexusSettler::new(addresses.nexus_settler, &nexus_provider);
-
-    let rollup1_settler = RollupSettler::new(addresses.rollup1_settler, &rollup1_provider);
-
-    let rollup2_settler = RollupSettler::new(addresses.rollup2_settler, &rollup2_provider);
-    let provider = ProviderBuilder::new()
-        .with_recommended_fillers()
-        .wallet(&wallet)
-        .on_http(chains.rollup2.http_url.parse::<Url>()?);
-
-    let erc20 = MockERC20::new(addresses.rollup2_erc20, &provider);
-    let settler = RollupSettler::new(addresses.rollup2_settler, &provider);
-
-    let orders_to_process: Vec<Order> = {
-        let order_hashes = state.order_hashes.read();
-        let orders = state.orders.read();
-        order_hashes
-            .iter()
-            .filter_map(|hash| orders.get(hash).cloned())
-            .collect()
-    };
+    let owner = wallet.default_signer().address();
+    let mut providers = HashMap::new();
+    let mut gas_prices = HashMap::new();
+    for chain in registry.iter() {
+        let provider = ProviderBuilder::new()
+            .with_recommended_fillers()
+            .wallet(&wallet)
+            .on_http(chain.rpc.parse::<Url>()?);
+        gas_prices.insert(chain.chain_id, provider.get_gas_price().await?);
+        providers.insert(chain.chain_id, provider);
+    }
+    inventory.refresh(registry, &providers, owner).await?;
 
-    println!("📸 Got {} orders to process", orders_to_process.len());
+    let orders_to_process = store.in_states(&[
+        OrderState::Discovered,
+        OrderState::ProofReady,
+        OrderState::Filling,
+    ])?;
 
-    for order in &orders_to_process {
-        erc20
-            .mint(wallet.default_signer().address(), order.min_amount_out)
-            .send()
-            .await?
-            .with_required_confirmations(1)
-            .watch()
-            .await?;
-        erc20
-            .approve(*settler.address(), order.min_amount_out)
-            .send()
-            .await?
-            .with_required_confirmations(1)
-            .watch()
-            .await?;
-        let client = reqwest::Client::new();
-        let response = client
-            .get(format!("http://127.0.0.1:3001/query/{}", order.nonce))
-            .send()
-            .await?;
-        let proof = response.json::<ProofResponse>().await?;
-        let order_root = rollup1_settler.orderRoot().call().await?._0;
-        nexus_settler
-            .updateRollupOrderRoot(U256::from(31338), order_root)
-            .send()
-            .await?
-            .with_required_confirmations(1)
-            .watch()
-            .await?;
+    println!("📸 Got {} orders to process", orders_to_process.len());
 
-        rollup2_settler
-            .updateRollupOrderRoot(U256::from(31338), order_root)
-            .send()
+    for stored in &orders_to_process {
+        let order = &stored.order;
+        let route = registry
+            .get(order.source)
+            .zip(registry.destination(order.destination));
+        if stored.state == OrderState::Filling {
+            match route {
+                Some((_, destination)) => {
+                    check_fill(&store, &providers[&destination.chain_id], stored).await?
+                }
+                None => eprintln!(
+                    "⚠️ Cannot check the fill of order {}, chain {} left the registry",
+                    order.order_hash, order.destination
+                ),
+            }
+            continue;
+        }
+        if store.is_rebalance(&order.order_hash)? {
+            store.skipped(
+                &order.order_hash,
+                "Rebalancing order of this solver".to_string(),
+            )?;
+            continue;
+        }
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs();
+        if u64::from(order.fill_deadline) < now {
+            store.set_state(&order.order_hash, OrderState::Expired)?;
+            println!("⌛ Order {} expired before it was filled", order.order_hash);
+            continue;
+        }
+        let Some((origin, destination)) = route else {
+            store.failed(
+                &order.order_hash,
+                format!(
+                    "No route from chain {} to chain {} in the registry",
+                    order.source, order.destination
+                ),
+            )?;
+            println!(
+                "❌ Order {} is to chain {}, which is not in the registry",
+                order.order_hash, order.destination
+            );
+            continue;
+        };
+        let Some(to_token) = destination.token(order.to_token) else {
+            store.failed(
+                &order.order_hash,
+                format!(
+                    "Token {} is not listed for chain {}",
+                    order.to_token, destination.chain_id
+                ),
+            )?;
+            println!(
+                "❌ Order {} pays out {}, which is not listed for chain {}",
+                order.order_hash, order.to_token, destination.chain_id
+            );
+            continue;
+        };
+        let provider = &providers[&destination.chain_id];
+        let quote = Quote {
+            order,
+            from_token: origin.token(order.from_token),
+            to_token,
+            origin_gas_price: gas_prices[&origin.chain_id],
+            destination_gas_price: gas_prices[&destination.chain_id],
+            inventory: inventory.balance(destination.chain_id, order.to_token),
+            now,
+        };
+        match strategy.evaluate(&quote) {
+            Decision::Fill => {}
+            Decision::Wait(reason) => {
+                println!("⏳ Holding order {}: {}", order.order_hash, reason);
+                continue;
+            }
+            Decision::Skip(reason) => {
+                println!("🙅 Skipping order {}: {}", order.order_hash, reason);
+                store.skipped(&order.order_hash, reason)?;
+                continue;
+            }
+        }
+        let settler = RollupSettler::new(destination.settler, provider);
+        // the origin node proves against its current order root, so the proof is only good
+        // once its rollup node has relayed that root to the destination
+        let destination_root = settler
+            .orderRoots(U256::from(order.source))
+            .call()
             .await?
-            .with_required_confirmations(1)
-            .watch()
+            ._0;
+        let proof: Option<OrderProof> = providers[&origin.chain_id]
+            .raw_request("reimann_orderProof".into(), (order.nonce,))
             .await?;
-        let receipt = settler
+        let Some(proof) = proof.filter(|proof| proof.root == destination_root) else {
+            continue;
+        };
+        let proof: [B256; 32] = proof
+            .proof
+            .try_into()
+            .map_err(|_| "Order proof is not 32 nodes deep")?;
+        store.set_state(&order.order_hash, OrderState::ProofReady)?;
+        let request = settler
             .fulfil(
                 order.fill_deadline,
                 order.from_token,
@@ -288,104 +397,200 @@ async fn fill_orders(
                 order.recipient,
                 order.amount_in,
                 order.min_amount_out,
-                U256::from(31338),
+                U256::from(order.source),
                 order.nonce,
-                proof.proof,
+                proof,
             )
-            .send()
+            .into_transaction_request();
+        // the fill is signed and recorded before it is sent, so a crash cannot lose track of it
+        let envelope = provider
+            .fill(request)
+            .await?
+            .as_envelope()
+            .cloned()
+            .ok_or("The wallet did not sign the fill transaction")?;
+        let fill_tx = *envelope.tx_hash();
+        store.filling(&order.order_hash, fill_tx)?;
+        let receipt = provider
+            .send_tx_envelope(envelope)
             .await?
             .with_required_confirmations(1)
-            .watch()
+            .get_receipt()
             .await?;
-        println!("✅ Order fulfilled with hash {} on destination with txhash: {}", order.order_hash, receipt);
+        if receipt.inner.status() {
+            store.set_state(&order.order_hash, OrderState::Filled)?;
+            inventory.spend(destination.chain_id, order.to_token, order.min_amount_out);
+            println!(
+                "✅ Order fulfilled with hash {} on chain {} with txhash: {}",
+                order.order_hash, destination.chain_id, fill_tx
+            );
+        } else {
+            store.failed(
+                &order.order_hash,
+                format!("Fill transaction {} reverted", fill_tx),
+            )?;
+            println!("❌ Fill {} of order {} reverted", fill_tx, order.order_hash);
+        }
     }
 
-    if !orders_to_process.is_empty() {
-        {
-            let orders_guard = state.orders.try_write();
-            match orders_guard {
-                Some(mut orders) => {
-                    orders.clear();
-                }
-                None => eprintln!("❌ Failed to acquire write lock for orders"),
-            }
-        }
-        {
-            let order_hashes_guard = state.order_hashes.try_write();
-            match order_hashes_guard {
-                Some(mut order_hashes) => {
-                    order_hashes.clear();
-                }
-                None => eprintln!("❌ Failed to acquire write lock for order_hashes"),
-            }
+    Ok(())
+}
+
+/// Sends an order through the origin settler for each transfer the inventory needs, for other
+/// solvers to fill
+async fn rebalance(
+    store: &OrderStore,
+    registry: &ChainRegistry,
+    inventory: &Inventory,
+    fee_bps: u64,
+    wallet: &Arc<EthereumWallet>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)?
+        .as_secs();
+    for order in store.expire_rebalances(now)? {
+        eprintln!(
+            "⚠️ Rebalancing order for {} of {} on chain {} expired, not rebalancing into it again",
+            order.amount_out, order.to_token, order.to_chain
+        );
+    }
+    let stalled = store
+        .expired_rebalances()?
+        .iter()
+        .map(|order| (order.to_chain, order.to_token))
+        .collect::<HashSet<_>>();
+    let arriving = incoming(&store.rebalances()?, now);
+    for transfer in plan_rebalance(registry, inventory, &arriving, &stalled) {
+        let origin = registry
+            .get(transfer.from_chain)
+            .ok_or("Rebalancing from a chain that is not in the registry")?;
+        let provider = ProviderBuilder::new()
+            .with_recommended_fillers()
+            .wallet(wallet)
+            .on_http(origin.rpc.parse::<Url>()?);
+        let settler = RollupSettler::new(origin.settler, &provider);
+        let deadline = now + REBALANCE_LIFETIME;
+        let min_amount_out = amount_out(&transfer, fee_bps);
+        let receipt = settler
+            .send(
+                u32::try_from(deadline)?,
+                transfer.from_token,
+                transfer.to_token,
+                wallet.default_signer().address(),
+                transfer.amount,
+                min_amount_out,
+                U256::from(transfer.to_chain),
+            )
+            .send()
+            .await?
+            .with_required_confirmations(1)
+            .get_receipt()
+            .await?;
+        if !receipt.inner.status() {
+            eprintln!(
+                "⚠️ Rebalancing order {} from chain {} reverted",
+                receipt.transaction_hash, transfer.from_chain
+            );
+            continue;
         }
+        let order_hash = receipt
+            .inner
+            .logs()
+            .iter()
+            .find_map(|log| RollupSettler::OrderSent::decode_log_data(log.data(), true).ok())
+            .ok_or("Rebalancing order without an OrderSent log")?
+            .orderHash;
+        store.record_rebalance(
+            &order_hash,
+            &InFlight {
+                to_chain: transfer.to_chain,
+                to_token: transfer.to_token,
+                amount_out: min_amount_out,
+                deadline,
+            },
+        )?;
+        println!(
+            "🚚 Moving {} {} from chain {} to chain {} with order {}",
+            transfer.amount, transfer.symbol, transfer.from_chain, transfer.to_chain, order_hash
+        );
     }
-
     Ok(())
 }
 
+/// Fills orders and, unless `rebalance_fee_bps` is `None`, rebalances inventory every 2 seconds
 async fn monitor_transactions(
-    state: Arc<SharedState>,
-    chains: Chains,
-    addresses: ContractAddresses,
+    store: Arc<OrderStore>,
+    registry: ChainRegistry,
+    strategy: Box<dyn Strategy>,
+    rebalance_fee_bps: Option<u64>,
     wallet: Arc<EthereumWallet>,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let mut inventory = Inventory::new(PathBuf::from(ALERTS_PATH));
     loop {
         fill_orders(
-            Arc::clone(&state),
-            chains.clone(),
-            addresses.clone(),
+            Arc::clone(&store),
+            &registry,
+            strategy.as_ref(),
+            &mut inventory,
             wallet.clone(),
         )
         .await?;
+        if let Some(fee_bps) = rebalance_fee_bps {
+            rebalance(&store, &registry, &inventory, fee_bps, &wallet).await?;
+        }
         tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
     }
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    let state = Arc::new(SharedState {
-        orders: Arc::new(RwLock::new(HashMap::new())),
-        order_hashes: Arc::new(RwLock::new(Vec::new())),
-    });
-
-    let chains: Chains = Chains {
-        nexus: ChainData {
-            http_url: "http://localhost:8545".to_string(),
-        },
-        rollup1: ChainData {
-            http_url: "http://localhost:8546".to_string(),
-        },
-        rollup2: ChainData {
-            http_url: "http://localhost:8547".to_string(),
-        },
+    let args = Args::parse();
+    let registry = ChainRegistry::load(&args.chains)?;
+    let strategy = ProfitStrategy {
+        prices: StaticPrices::load(&args.prices)?,
+        min_profit: args.min_profit,
+        deadline_margin: args.deadline_margin,
     };
-    let addresses = load_contract_addresses().await?;
+    // orders left open by the last run are picked up by the first fill pass, those in
+    // `Filling` by checking their transaction
+    let store = Arc::new(OrderStore::open(std::path::Path::new(ORDER_DB_PATH))?);
+    println!(
+        "📂 Loaded orders from {}: {:?}",
+        ORDER_DB_PATH,
+        store.counts()?
+    );
+
     let wallet = Arc::new(EthereumWallet::new(
         PRIVATE_KEY.parse::<PrivateKeySigner>()?,
     ));
 
     println!(
-        "🧮 Starting solver with address: {:?}",
-        wallet.default_signer().address()
+        "🧮 Starting solver with address {:?} on chains {:?}",
+        wallet.default_signer().address(),
+        registry
+            .iter()
+            .map(|chain| chain.chain_id)
+            .collect::<Vec<_>>()
     );
 
-    let read_order_handle = tokio::spawn(read_orders(
-        Arc::clone(&state),
-        chains.clone(),
-        Arc::clone(&wallet),
-    ));
-    let monitor_transactions_handle = tokio::spawn(monitor_transactions(
-        state,
-        chains.clone(),
-        addresses,
+    // one reader per origin rollup, all feeding the same store
+    let mut handles = registry
+        .iter()
+        .map(|chain| tokio::spawn(read_orders(Arc::clone(&store), chain.clone())))
+        .collect::<Vec<_>>();
+    handles.push(tokio::spawn(monitor_transactions(
+        store,
+        registry,
+        Box::new(strategy),
+        args.rebalance.then_some(args.rebalance_fee_bps),
         Arc::clone(&wallet),
-    ));
-    let (read_order_result, monitor_transactions_result) =
-        try_join!(read_order_handle, monitor_transactions_handle)?;
-
-    read_order_result?;
-    monitor_transactions_result?;
+    )));
+    futures::future::try_join_all(
+        handles
+            .into_iter()
+            .map(|handle| async move { handle.await? }),
+    )
+    .await?;
 
     Ok(())
 }
@@ -0,0 +1,408 @@
+use crate::{inventory::InFlight, Order};
+use alloy::primitives::B256;
+use redb::{Database, ReadableTable, TableDefinition};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::Path};
+
+pub type StoreError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Orders by hash, as JSON
+const ORDERS: TableDefinition<&[u8], &[u8]> = TableDefinition::new("orders");
+/// Last block of each origin chain whose orders are all in the store, by chain id
+const LAST_BLOCKS: TableDefinition<u64, u64> = TableDefinition::new("last_blocks");
+/// Rebalancing orders the solver sent, by order hash, as JSON
+const REBALANCES: TableDefinition<&[u8], &[u8]> = TableDefinition::new("rebalances");
+/// Rebalancing orders past their deadline, by order hash, as JSON
+///
+/// The solver cannot tell whether another solver filled them, and the settler cannot release
+/// the escrow of one that was not filled yet, so they are kept to hold back more transfers.
+const EXPIRED_REBALANCES: TableDefinition<&[u8], &[u8]> =
+    TableDefinition::new("expired_rebalances");
+
+/// Where an order is in the solver's hands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OrderState {
+    /// Seen on the origin rollup
+    Discovered,
+    /// The destination settler holds an order root that includes the order
+    ProofReady,
+    /// A fill transaction was signed, and maybe sent
+    Filling,
+    Filled,
+    /// The fill deadline passed before the order was filled
+    Expired,
+    /// The fill transaction reverted
+    Failed,
+    /// The strategy declined to fill the order
+    Skipped,
+}
+
+impl OrderState {
+    fn can_move_to(self, next: OrderState) -> bool {
+        use OrderState::*;
+        matches!(
+            (self, next),
+            (Discovered, ProofReady | Expired | Failed | Skipped)
+                | (ProofReady, Filling | Expired | Failed | Skipped)
+                // back to `ProofReady` when the fill transaction never reached the chain
+                | (Filling, Filled | Failed | ProofReady)
+        )
+    }
+}
+
+/// An order with what the solver did about it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StoredOrder {
+    pub order: Order,
+    pub state: OrderState,
+    /// Hash of the last fill transaction, recorded before it is sent
+    pub fill_tx: Option<B256>,
+    /// Why the order failed or was skipped
+    pub error: Option<String>,
+}
+
+/// Orders on local disk, so a restart neither loses nor fills an order twice
+///
+/// Every change is its own transaction. Moving an order to the state it is already in does
+/// nothing, so a step that is repeated after a crash is harmless.
+pub struct OrderStore {
+    db: Database,
+}
+
+impl OrderStore {
+    pub fn open(path: &Path) -> Result<Self, StoreError> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let db = Database::create(path)?;
+        let txn = db.begin_write()?;
+        txn.open_table(ORDERS)?;
+        txn.open_table(LAST_BLOCKS)?;
+        txn.open_table(REBALANCES)?;
+        txn.open_table(EXPIRED_REBALANCES)?;
+        txn.commit()?;
+        Ok(Self { db })
+    }
+
+    pub fn get(&self, hash: &B256) -> Result<Option<StoredOrder>, StoreError> {
+        let txn = self.db.begin_read()?;
+        let table = txn.open_table(ORDERS)?;
+        let order = table.get(hash.as_slice())?;
+        Ok(order
+            .map(|order| serde_json::from_slice(order.value()))
+            .transpose()?)
+    }
+
+    pub fn contains(&self, hash: &B256) -> Result<bool, StoreError> {
+        Ok(self.get(hash)?.is_some())
+    }
+
+    /// Records the orders of origin blocks up to `number` and the block itself, returning how
+    /// many orders were new
+    pub fn record_block(
+        &self,
+        chain_id: u64,
+        number: u64,
+        orders: &[Order],
+    ) -> Result<usize, StoreError> {
+        let txn = self.db.begin_write()?;
+        let mut added = 0;
+        {
+            let mut table = txn.open_table(ORDERS)?;
+            for order in orders {
+                if table.get(order.order_hash.as_slice())?.is_some() {
+                    continue;
+                }
+                let stored = StoredOrder {
+                    order: order.clone(),
+                    state: OrderState::Discovered,
+                    fill_tx: None,
+                    error: None,
+                };
+                table.insert(
+                    order.order_hash.as_slice(),
+                    serde_json::to_vec(&stored)?.as_slice(),
+                )?;
+                added += 1;
+            }
+            txn.open_table(LAST_BLOCKS)?.insert(chain_id, number)?;
+        }
+        txn.commit()?;
+        Ok(added)
+    }
+
+    pub fn last_block(&self, chain_id: u64) -> Result<Option<u64>, StoreError> {
+        let txn = self.db.begin_read()?;
+        let table = txn.open_table(LAST_BLOCKS)?;
+        let last_block = table.get(chain_id)?;
+        Ok(last_block.map(|number| number.value()))
+    }
+
+    /// Moves an order to `state`, returning `false` if it already was in it
+    pub fn set_state(&self, hash: &B256, state: OrderState) -> Result<bool, StoreError> {
+        self.transition(hash, state, |_| {})
+    }
+
+    /// Moves an order to `Filling` with the transaction about to fill it
+    pub fn filling(&self, hash: &B256, fill_tx: B256) -> Result<bool, StoreError> {
+        self.transition(hash, OrderState::Filling, |order| {
+            order.fill_tx = Some(fill_tx)
+        })
+    }
+
+    pub fn failed(&self, hash: &B256, error: String) -> Result<bool, StoreError> {
+        self.transition(hash, OrderState::Failed, |order| order.error = Some(error))
+    }
+
+    pub fn skipped(&self, hash: &B256, reason: String) -> Result<bool, StoreError> {
+        self.transition(hash, OrderState::Skipped, |order| {
+            order.error = Some(reason)
+        })
+    }
+
+    fn transition(
+        &self,
+        hash: &B256,
+        state: OrderState,
+        update: impl FnOnce(&mut StoredOrder),
+    ) -> Result<bool, StoreError> {
+        let txn = self.db.begin_write()?;
+        {
+            let mut table = txn.open_table(ORDERS)?;
+            let mut order: StoredOrder = match table.get(hash.as_slice())? {
+                Some(order) => serde_json::from_slice(order.value())?,
+                None => return Err(format!("Order {} is not in the store", hash).into()),
+            };
+            if order.state == state {
+                return Ok(false);
+            }
+            if !order.state.can_move_to(state) {
+                return Err(format!(
+                    "Order {} cannot move from {:?} to {:?}",
+                    hash, order.state, state
+                )
+                .into());
+            }
+            order.state = state;
+            update(&mut order);
+            table.insert(hash.as_slice(), serde_json::to_vec(&order)?.as_slice())?;
+        }
+        txn.commit()?;
+        Ok(true)
+    }
+
+    /// Orders in any of `states`, by nonce, so every origin's oldest orders come first
+    pub fn in_states(&self, states: &[OrderState]) -> Result<Vec<StoredOrder>, StoreError> {
+        let txn = self.db.begin_read()?;
+        let table = txn.open_table(ORDERS)?;
+        let mut orders = Vec::new();
+        for entry in table.iter()? {
+            let (_, order) = entry?;
+            let order: StoredOrder = serde_json::from_slice(order.value())?;
+            if states.contains(&order.state) {
+                orders.push(order);
+            }
+        }
+        orders.sort_by_key(|order| (order.order.nonce, order.order.source));
+        Ok(orders)
+    }
+
+    /// Records a rebalancing order the solver sent, so it is not filled as anyone else's
+    pub fn record_rebalance(&self, hash: &B256, order: &InFlight) -> Result<(), StoreError> {
+        let txn = self.db.begin_write()?;
+        txn.open_table(REBALANCES)?
+            .insert(hash.as_slice(), serde_json::to_vec(order)?.as_slice())?;
+        txn.commit()?;
+        Ok(())
+    }
+
+    pub fn is_rebalance(&self, hash: &B256) -> Result<bool, StoreError> {
+        let txn = self.db.begin_read()?;
+        Ok(txn.open_table(REBALANCES)?.get(hash.as_slice())?.is_some()
+            || txn
+                .open_table(EXPIRED_REBALANCES)?
+                .get(hash.as_slice())?
+                .is_some())
+    }
+
+    /// Rebalancing orders still in flight
+    pub fn rebalances(&self) -> Result<Vec<InFlight>, StoreError> {
+        self.read_rebalances(REBALANCES)
+    }
+
+    /// Rebalancing orders that reached their deadline, filled or not
+    pub fn expired_rebalances(&self) -> Result<Vec<InFlight>, StoreError> {
+        self.read_rebalances(EXPIRED_REBALANCES)
+    }
+
+    fn read_rebalances(
+        &self,
+        definition: TableDefinition<&[u8], &[u8]>,
+    ) -> Result<Vec<InFlight>, StoreError> {
+        let txn = self.db.begin_read()?;
+        let table = txn.open_table(definition)?;
+        let mut orders = Vec::new();
+        for entry in table.iter()? {
+            let (_, order) = entry?;
+            orders.push(serde_json::from_slice(order.value())?);
+        }
+        Ok(orders)
+    }
+
+    /// Moves the rebalancing orders whose deadline is before `now` out of those in flight,
+    /// returning them
+    pub fn expire_rebalances(&self, now: u64) -> Result<Vec<InFlight>, StoreError> {
+        let txn = self.db.begin_write()?;
+        let mut expired = Vec::new();
+        {
+            let mut table = txn.open_table(REBALANCES)?;
+            for entry in table.iter()? {
+                let (hash, order) = entry?;
+                let order: InFlight = serde_json::from_slice(order.value())?;
+                if order.deadline < now {
+                    expired.push((hash.value().to_vec(), order));
+                }
+            }
+            let mut expired_table = txn.open_table(EXPIRED_REBALANCES)?;
+            for (hash, order) in &expired {
+                table.remove(hash.as_slice())?;
+                expired_table.insert(hash.as_slice(), serde_json::to_vec(order)?.as_slice())?;
+            }
+        }
+        txn.commit()?;
+        Ok(expired.into_iter().map(|(_, order)| order).collect())
+    }
+
+    /// Number of orders in each state
+    pub fn counts(&self) -> Result<BTreeMap<OrderState, usize>, StoreError> {
+        let txn = self.db.begin_read()?;
+        let table = txn.open_table(ORDERS)?;
+        let mut counts = BTreeMap::new();
+        for entry in table.iter()? {
+            let (_, order) = entry?;
+            let order: StoredOrder = serde_json::from_slice(order.value())?;
+            *counts.entry(order.state).or_default() += 1;
+        }
+        Ok(counts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::{Address, U256};
+
+    fn order(nonce: u32) -> Order {
+        Order {
+            fill_deadline: 100,
+            from_token: Address::with_last_byte(1),
+            to_token: Address::with_last_byte(2),
+            sender: Address::with_last_byte(3),
+            recipient: Address::with_last_byte(4),
+            amount_in: U256::from(10),
+            min_amount_out: U256::from(9),
+            source: 31338,
+            destination: U256::from(31339),
+            nonce,
+            order_hash: B256::with_last_byte(nonce as u8 + 1),
+        }
+    }
+
+    fn path(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("solver-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir.join("orders.redb")
+    }
+
+    #[test]
+    fn test_transitions() {
+        let path = path("transitions");
+        let store = OrderStore::open(&path).unwrap();
+        let hash = order(0).order_hash;
+        assert_eq!(
+            store.record_block(31338, 5, &[order(0), order(1)]).unwrap(),
+            2
+        );
+        // orders seen again are not reset
+        assert!(store.set_state(&hash, OrderState::ProofReady).unwrap());
+        assert_eq!(store.record_block(31338, 5, &[order(0)]).unwrap(), 0);
+        assert_eq!(
+            store.get(&hash).unwrap().unwrap().state,
+            OrderState::ProofReady
+        );
+
+        // repeating a transition does nothing, skipping one fails
+        assert!(!store.set_state(&hash, OrderState::ProofReady).unwrap());
+        assert!(store.set_state(&hash, OrderState::Filled).is_err());
+        assert!(store.filling(&hash, B256::with_last_byte(9)).unwrap());
+        assert!(store.set_state(&hash, OrderState::Filled).unwrap());
+        assert!(store.set_state(&hash, OrderState::ProofReady).is_err());
+        assert!(store
+            .set_state(&B256::ZERO, OrderState::ProofReady)
+            .is_err());
+
+        let open = store
+            .in_states(&[OrderState::Discovered, OrderState::ProofReady])
+            .unwrap();
+        assert_eq!(open.len(), 1);
+        assert_eq!(open[0].order.nonce, 1);
+
+        let skipped = order(1).order_hash;
+        assert!(store.skipped(&skipped, "Unprofitable".to_string()).unwrap());
+        assert!(store.set_state(&skipped, OrderState::ProofReady).is_err());
+        let stored = store.get(&skipped).unwrap().unwrap();
+        assert_eq!(stored.error.as_deref(), Some("Unprofitable"));
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_recovers_after_restart() {
+        let path = path("restart");
+        {
+            let store = OrderStore::open(&path).unwrap();
+            assert_eq!(store.last_block(31338).unwrap(), None);
+            store.record_block(31338, 7, &[order(0)]).unwrap();
+            store.record_block(31339, 3, &[]).unwrap();
+            store
+                .set_state(&order(0).order_hash, OrderState::ProofReady)
+                .unwrap();
+            store
+                .filling(&order(0).order_hash, B256::with_last_byte(9))
+                .unwrap();
+        }
+        let store = OrderStore::open(&path).unwrap();
+        assert_eq!(store.last_block(31338).unwrap(), Some(7));
+        assert_eq!(store.last_block(31339).unwrap(), Some(3));
+        let filling = store.in_states(&[OrderState::Filling]).unwrap();
+        assert_eq!(filling[0].fill_tx, Some(B256::with_last_byte(9)));
+        assert_eq!(store.counts().unwrap()[&OrderState::Filling], 1);
+
+        let rebalance = InFlight {
+            to_chain: 31339,
+            to_token: Address::with_last_byte(2),
+            amount_out: U256::from(5),
+            deadline: 100,
+        };
+        store
+            .record_rebalance(&B256::with_last_byte(7), &rebalance)
+            .unwrap();
+        drop(store);
+        let store = OrderStore::open(&path).unwrap();
+        assert!(store.is_rebalance(&B256::with_last_byte(7)).unwrap());
+        assert!(!store.is_rebalance(&order(0).order_hash).unwrap());
+        assert_eq!(store.rebalances().unwrap(), vec![rebalance.clone()]);
+
+        // an order is in flight up to its deadline, then kept as expired
+        assert!(store.expire_rebalances(100).unwrap().is_empty());
+        assert_eq!(
+            store.expire_rebalances(101).unwrap(),
+            vec![rebalance.clone()]
+        );
+        assert!(store.rebalances().unwrap().is_empty());
+        assert_eq!(store.expired_rebalances().unwrap(), vec![rebalance]);
+        assert!(store.is_rebalance(&B256::with_last_byte(7)).unwrap());
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,270 @@
+use crate::{chains::TokenConfig, Order};
+use alloy::primitives::U256;
+use std::{collections::HashMap, path::Path};
+
+/// Symbol the gas of every rollup is priced in
+pub const NATIVE_SYMBOL: &str = "ETH";
+/// Gas of a `fulfil` call, which checks a 32 node proof and appends to the fill tree
+pub const FILL_GAS: u64 = 250_000;
+/// Gas of claiming the escrowed input on the origin once the fill is proven
+pub const CLAIM_GAS: u64 = 100_000;
+
+/// What to do with an order
+#[derive(Debug, Clone, PartialEq)]
+pub enum Decision {
+    Fill,
+    /// Look at the order again on the next pass, when gas or inventory may have changed
+    Wait(String),
+    /// Never fill the order
+    Skip(String),
+}
+
+/// What the solver knows about an order when deciding on it
+#[derive(Debug)]
+pub struct Quote<'a> {
+    pub order: &'a Order,
+    /// The token the order pays with on its origin, if the registry lists it
+    pub from_token: Option<&'a TokenConfig>,
+    pub to_token: &'a TokenConfig,
+    /// Gas prices in wei
+    pub origin_gas_price: u128,
+    pub destination_gas_price: u128,
+    /// What the solver holds of `to_token` on the destination
+    pub inventory: U256,
+    /// Unix time in seconds
+    pub now: u64,
+}
+
+/// Decides whether and when the solver fills an order
+pub trait Strategy: Send + Sync {
+    fn evaluate(&self, quote: &Quote<'_>) -> Decision;
+}
+
+/// USD prices of whole tokens, by symbol
+pub trait PriceFeed: Send + Sync {
+    fn price(&self, symbol: &str) -> Option<f64>;
+}
+
+/// Prices read once from a JSON file of `{ "SYMBOL": usd }`, standing in for a real feed
+#[derive(Debug, Clone, Default)]
+pub struct StaticPrices {
+    prices: HashMap<String, f64>,
+}
+
+impl StaticPrices {
+    pub fn new(prices: HashMap<String, f64>) -> Self {
+        Self { prices }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let file = std::fs::read_to_string(path)
+            .map_err(|err| format!("Failed to read {}: {}", path.display(), err))?;
+        Ok(Self::new(serde_json::from_str(&file)?))
+    }
+}
+
+impl PriceFeed for StaticPrices {
+    fn price(&self, symbol: &str) -> Option<f64> {
+        self.prices.get(symbol).copied()
+    }
+}
+
+/// Fills orders whose spread pays for the gas on both chains with `min_profit` to spare
+///
+/// An order whose spread alone is too small, or whose deadline is too close, is skipped. One
+/// that only gas, a lack of inventory or a missing price or token listing holds back is
+/// waited on.
+#[derive(Debug, Clone)]
+pub struct ProfitStrategy<F> {
+    pub prices: F,
+    /// Least profit in USD an order must make after gas
+    pub min_profit: f64,
+    /// Least time in seconds that must be left before the deadline to start a fill
+    pub deadline_margin: u64,
+}
+
+/// Value in USD of `amount` base units of a token
+fn usd(amount: U256, decimals: u8, price: f64) -> f64 {
+    f64::from(amount) / 10f64.powi(decimals.into()) * price
+}
+
+impl<F: PriceFeed> ProfitStrategy<F> {
+    fn price(&self, symbol: &str) -> Result<f64, Decision> {
+        self.prices
+            .price(symbol)
+            .ok_or_else(|| Decision::Wait(format!("No price for {}", symbol)))
+    }
+
+    /// Errors with the decision when the order cannot be valued
+    fn decide(&self, quote: &Quote<'_>) -> Result<Decision, Decision> {
+        let order = quote.order;
+        let time_left = u64::from(order.fill_deadline).saturating_sub(quote.now);
+        if time_left < self.deadline_margin {
+            return Ok(Decision::Skip(format!(
+                "{}s left before the deadline, {}s are needed to fill",
+                time_left, self.deadline_margin
+            )));
+        }
+        let from_token = quote.from_token.ok_or_else(|| {
+            Decision::Wait(format!(
+                "Token {} is not listed for chain {}",
+                order.from_token, order.source
+            ))
+        })?;
+        let value_in = usd(
+            order.amount_in,
+            from_token.decimals,
+            self.price(&from_token.symbol)?,
+        );
+        let value_out = usd(
+            order.min_amount_out,
+            quote.to_token.decimals,
+            self.price(&quote.to_token.symbol)?,
+        );
+        let spread = value_in - value_out;
+        if spread < self.min_profit {
+            return Ok(Decision::Skip(format!(
+                "Pays ${:.4} for ${:.4}, under the ${} minimum profit",
+                value_in, value_out, self.min_profit
+            )));
+        }
+        let gas_wei = FILL_GAS as f64 * quote.destination_gas_price as f64
+            + CLAIM_GAS as f64 * quote.origin_gas_price as f64;
+        let gas = gas_wei / 1e18 * self.price(NATIVE_SYMBOL)?;
+        if spread - gas < self.min_profit {
+            return Ok(Decision::Wait(format!(
+                "Gas of ${:.4} leaves too little of the ${:.4} spread",
+                gas, spread
+            )));
+        }
+        if quote.inventory < order.min_amount_out {
+            return Ok(Decision::Wait(format!(
+                "Holds {} of the {} {} needed",
+                quote.inventory, order.min_amount_out, quote.to_token.symbol
+            )));
+        }
+        Ok(Decision::Fill)
+    }
+}
+
+impl<F: PriceFeed> Strategy for ProfitStrategy<F> {
+    fn evaluate(&self, quote: &Quote<'_>) -> Decision {
+        self.decide(quote).unwrap_or_else(|decision| decision)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::{Address, B256};
+
+    fn token(symbol: &str) -> TokenConfig {
+        TokenConfig {
+            symbol: symbol.to_string(),
+            address: Address::with_last_byte(1),
+            decimals: 18,
+            min_balance: None,
+            target_balance: None,
+        }
+    }
+
+    fn order(amount_in: u64, min_amount_out: u64) -> Order {
+        let unit = U256::from(10).pow(U256::from(16));
+        Order {
+            fill_deadline: 1_000,
+            from_token: Address::with_last_byte(1),
+            to_token: Address::with_last_byte(2),
+            sender: Address::with_last_byte(3),
+            recipient: Address::with_last_byte(4),
+            amount_in: U256::from(amount_in) * unit,
+            min_amount_out: U256::from(min_amount_out) * unit,
+            source: 31338,
+            destination: U256::from(31339),
+            nonce: 0,
+            order_hash: B256::ZERO,
+        }
+    }
+
+    #[test]
+    fn test_profit_strategy() {
+        let strategy = ProfitStrategy {
+            prices: StaticPrices::new(HashMap::from([
+                ("ETH".to_string(), 2_000.0),
+                ("TERC20".to_string(), 1.0),
+            ])),
+            min_profit: 0.005,
+            deadline_margin: 30,
+        };
+        let terc20 = token("TERC20");
+        let quote = |order, gas_price, inventory, now| Quote {
+            order,
+            from_token: Some(&terc20),
+            to_token: &terc20,
+            origin_gas_price: gas_price,
+            destination_gas_price: gas_price,
+            inventory: U256::from(10).pow(U256::from(18)) * U256::from(inventory),
+            now,
+        };
+        let wait = |decision| matches!(decision, Decision::Wait(_));
+        let skip = |decision| matches!(decision, Decision::Skip(_));
+
+        // $1 in for $0.99 out at 7 wei gas
+        let profitable = order(100, 99);
+        assert_eq!(
+            strategy.evaluate(&quote(&profitable, 7, 1, 0)),
+            Decision::Fill
+        );
+        // 350k gas at 1 gwei costs $0.70
+        assert!(wait(strategy.evaluate(&quote(
+            &profitable,
+            1_000_000_000,
+            1,
+            0
+        ))));
+        assert!(wait(strategy.evaluate(&quote(&profitable, 7, 0, 0))));
+        assert!(skip(strategy.evaluate(&quote(&profitable, 7, 1, 980))));
+        let even = order(100, 100);
+        assert!(skip(strategy.evaluate(&quote(&even, 7, 1, 0))));
+
+        // prices and listings may still show up
+        let unpriced = token("UNPRICED");
+        let mut unpriced_quote = quote(&profitable, 7, 1, 0);
+        unpriced_quote.to_token = &unpriced;
+        assert!(wait(strategy.evaluate(&unpriced_quote)));
+        unpriced_quote.from_token = None;
+        assert!(wait(strategy.evaluate(&unpriced_quote)));
+    }
+
+    #[test]
+    fn test_default_min_profit() {
+        // the solver's defaults and `test full`'s prices
+        let strategy = ProfitStrategy {
+            prices: StaticPrices::new(HashMap::from([
+                ("ETH".to_string(), 3_000.0),
+                ("TERC20".to_string(), 1.0),
+            ])),
+            min_profit: 0.0,
+            deadline_margin: 10,
+        };
+        let terc20 = token("TERC20");
+        let quote = |order| Quote {
+            order,
+            from_token: Some(&terc20),
+            to_token: &terc20,
+            origin_gas_price: 7,
+            destination_gas_price: 7,
+            inventory: U256::from(10).pow(U256::from(18)),
+            now: 0,
+        };
+
+        // `test full` sends 1 token for at least 0.99
+        let demo = order(100, 99);
+        assert_eq!(strategy.evaluate(&quote(&demo)), Decision::Fill);
+        // a spread of nothing never pays for the gas
+        let even = order(100, 100);
+        assert!(matches!(
+            strategy.evaluate(&quote(&even)),
+            Decision::Wait(_)
+        ));
+    }
+}